@@ -137,7 +137,7 @@ fn main() {
                 lookup_user_by_email(payload.email, &connection);
 
             let user = unauthenticated_user
-                .log_in_local(local_auth, payload.password)
+                .log_in_local(local_auth, payload.password, &connection)
                 .unwrap();
 
             assert!(
@@ -192,7 +192,7 @@ fn main() {
                 lookup_user_by_email(payload.email, &connection);
 
             let user = unauthenticated_user
-                .log_in_local(local_auth, payload.password)
+                .log_in_local(local_auth, payload.password, &connection)
                 .unwrap();
 
             assert!(
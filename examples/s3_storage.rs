@@ -0,0 +1,35 @@
+extern crate aardwolf_models;
+extern crate dotenv;
+
+use std::env;
+
+use aardwolf_models::file::storage::{S3Storage, Storage, StorageError};
+use dotenv::dotenv;
+
+fn main() {
+    dotenv().ok();
+
+    // Point these at a local S3 stand-in, like MinIO, to run this example
+    let storage = S3Storage::with_endpoint(
+        env::var("TEST_S3_BUCKET").unwrap(),
+        env::var("TEST_S3_REGION").unwrap_or_else(|_| "us-east-1".to_owned()),
+        env::var("TEST_S3_ENDPOINT").unwrap(),
+    );
+
+    let key = "aardwolf-models/example.txt";
+    let bytes = b"Hewwo from aardwolf-models";
+
+    storage.store(key, bytes, "text/plain").unwrap();
+    println!("Stored file!");
+
+    let fetched = storage.fetch(key).unwrap();
+    assert_eq!(fetched, bytes.to_vec(), "Fetched bytes should match stored bytes");
+    println!("Fetched file!");
+
+    storage.delete(key).unwrap();
+
+    match storage.fetch(key) {
+        Err(StorageError::NotFound) => println!("Deleted file!"),
+        other => panic!("Expected file to be deleted, got {:?}", other),
+    }
+}
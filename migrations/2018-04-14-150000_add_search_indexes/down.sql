@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX base_actors_display_name_search_idx;
+DROP INDEX personas_shortname_search_idx;
+DROP INDEX base_posts_name_search_idx;
+DROP INDEX posts_content_search_idx;
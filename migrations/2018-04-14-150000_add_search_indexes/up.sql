@@ -0,0 +1,9 @@
+-- Your SQL goes here
+CREATE INDEX posts_content_search_idx ON posts
+    USING GIN (to_tsvector('english', content));
+CREATE INDEX base_posts_name_search_idx ON base_posts
+    USING GIN (to_tsvector('english', coalesce(name, '')));
+CREATE INDEX personas_shortname_search_idx ON personas
+    USING GIN (to_tsvector('simple', shortname));
+CREATE INDEX base_actors_display_name_search_idx ON base_actors
+    USING GIN (to_tsvector('simple', display_name));
@@ -0,0 +1,10 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE personas DROP COLUMN preferred_languages;
+
+DROP INDEX posts_primary_language_idx;
+ALTER TABLE posts DROP COLUMN language;
+
+UPDATE links SET href_lang = 'EnUs' WHERE href_lang = 'en-US';
+UPDATE links SET href_lang = 'EnUk' WHERE href_lang = 'en-GB';
+UPDATE links SET href_lang = 'EnAu' WHERE href_lang = 'en-AU';
+ALTER TABLE links ALTER COLUMN href_lang TYPE VARCHAR(8) USING left(href_lang, 8);
@@ -0,0 +1,10 @@
+-- Your SQL goes here
+ALTER TABLE links ALTER COLUMN href_lang TYPE VARCHAR(35);
+UPDATE links SET href_lang = 'en-US' WHERE href_lang = 'EnUs';
+UPDATE links SET href_lang = 'en-GB' WHERE href_lang = 'EnUk';
+UPDATE links SET href_lang = 'en-AU' WHERE href_lang = 'EnAu';
+
+ALTER TABLE posts ADD COLUMN language VARCHAR(35) NOT NULL DEFAULT 'und';
+CREATE INDEX posts_primary_language_idx ON posts (split_part(language, '-', 1));
+
+ALTER TABLE personas ADD COLUMN preferred_languages VARCHAR(35)[] NOT NULL DEFAULT '{}';
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE personas DROP COLUMN default_sensitive;
+
+ALTER TABLE base_posts DROP COLUMN sensitive;
+ALTER TABLE base_posts DROP COLUMN summary;
@@ -0,0 +1,5 @@
+-- Your SQL goes here
+ALTER TABLE base_posts ADD COLUMN summary TEXT;
+ALTER TABLE base_posts ADD COLUMN sensitive BOOLEAN NOT NULL DEFAULT FALSE;
+
+ALTER TABLE personas ADD COLUMN default_sensitive BOOLEAN NOT NULL DEFAULT FALSE;
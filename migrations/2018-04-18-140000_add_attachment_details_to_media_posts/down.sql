@@ -0,0 +1,9 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE media_posts DROP CONSTRAINT media_posts_post_id_position_key;
+
+ALTER TABLE media_posts DROP COLUMN blurhash;
+ALTER TABLE media_posts DROP COLUMN focal_y;
+ALTER TABLE media_posts DROP COLUMN focal_x;
+ALTER TABLE media_posts DROP COLUMN description;
+ALTER TABLE media_posts DROP COLUMN attachment_type;
+ALTER TABLE media_posts DROP COLUMN position;
@@ -0,0 +1,14 @@
+-- Your SQL goes here
+ALTER TABLE media_posts ADD COLUMN position INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE media_posts ADD COLUMN attachment_type VARCHAR(8) NOT NULL DEFAULT 'image';
+ALTER TABLE media_posts ADD COLUMN description TEXT;
+ALTER TABLE media_posts ADD COLUMN focal_x REAL;
+ALTER TABLE media_posts ADD COLUMN focal_y REAL;
+ALTER TABLE media_posts ADD COLUMN blurhash VARCHAR(128);
+
+UPDATE media_posts SET position = numbered.position FROM (
+    SELECT id, (row_number() OVER (PARTITION BY post_id ORDER BY id) - 1) AS position
+    FROM media_posts
+) AS numbered WHERE media_posts.id = numbered.id;
+
+ALTER TABLE media_posts ADD CONSTRAINT media_posts_post_id_position_key UNIQUE (post_id, position);
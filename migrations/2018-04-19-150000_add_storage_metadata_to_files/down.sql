@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE files DROP COLUMN created_at;
+ALTER TABLE files DROP COLUMN media_type;
+ALTER TABLE files DROP COLUMN size;
+ALTER TABLE files DROP COLUMN content_hash;
+ALTER TABLE files RENAME COLUMN storage_key TO file_path;
@@ -0,0 +1,6 @@
+-- Your SQL goes here
+ALTER TABLE files RENAME COLUMN file_path TO storage_key;
+ALTER TABLE files ADD COLUMN content_hash VARCHAR(64) UNIQUE;
+ALTER TABLE files ADD COLUMN size BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE files ADD COLUMN media_type VARCHAR(120) NOT NULL DEFAULT 'application/octet-stream';
+ALTER TABLE files ADD COLUMN created_at TIMESTAMPTZ NOT NULL DEFAULT now();
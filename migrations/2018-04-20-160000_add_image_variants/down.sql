@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE images DROP CONSTRAINT images_original_image_variant_key;
+ALTER TABLE images DROP COLUMN original_image;
+ALTER TABLE images DROP COLUMN variant;
@@ -0,0 +1,4 @@
+-- Your SQL goes here
+ALTER TABLE images ADD COLUMN variant VARCHAR(16) NOT NULL DEFAULT 'original';
+ALTER TABLE images ADD COLUMN original_image INTEGER REFERENCES images(id) ON DELETE CASCADE;
+ALTER TABLE images ADD CONSTRAINT images_original_image_variant_key UNIQUE (original_image, variant);
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX remote_media_last_accessed_at_index;
+DROP TABLE remote_media;
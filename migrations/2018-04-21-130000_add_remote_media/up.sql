@@ -0,0 +1,11 @@
+-- Your SQL goes here
+CREATE TABLE remote_media (
+  id SERIAL PRIMARY KEY,
+  source_url VARCHAR(2048) UNIQUE NOT NULL,
+  file_id INTEGER REFERENCES files(id) ON DELETE CASCADE NOT NULL,
+  fetched_at TIMESTAMPTZ NOT NULL,
+  byte_size BIGINT NOT NULL,
+  last_accessed_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX remote_media_last_accessed_at_index ON remote_media (last_accessed_at);
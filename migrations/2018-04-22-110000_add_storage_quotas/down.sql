@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE roles DROP COLUMN storage_quota;
+
+DROP INDEX files_owner_index;
+ALTER TABLE files DROP COLUMN owner;
@@ -0,0 +1,6 @@
+-- Your SQL goes here
+ALTER TABLE files ADD COLUMN owner INTEGER REFERENCES users(id) ON DELETE SET NULL;
+CREATE INDEX files_owner_index ON files (owner);
+
+ALTER TABLE roles ADD COLUMN storage_quota BIGINT;
+UPDATE roles SET storage_quota = 1073741824 WHERE name = 'verified';
@@ -0,0 +1,20 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX files_created_at_index;
+DROP INDEX media_posts_file_id_index;
+DROP INDEX images_file_id_index;
+
+ALTER TABLE personas DROP CONSTRAINT personas_avatar_fkey;
+ALTER TABLE personas ADD CONSTRAINT personas_avatar_fkey
+  FOREIGN KEY (avatar) REFERENCES images(id) ON DELETE CASCADE;
+
+ALTER TABLE base_posts DROP CONSTRAINT base_posts_icon_fkey;
+ALTER TABLE base_posts ADD CONSTRAINT base_posts_icon_fkey
+  FOREIGN KEY (icon) REFERENCES images(id) ON DELETE CASCADE;
+
+ALTER TABLE media_posts DROP CONSTRAINT media_posts_file_id_fkey;
+ALTER TABLE media_posts ADD CONSTRAINT media_posts_file_id_fkey
+  FOREIGN KEY (file_id) REFERENCES files(id) ON DELETE CASCADE;
+
+ALTER TABLE images DROP CONSTRAINT images_file_id_fkey;
+ALTER TABLE images ADD CONSTRAINT images_file_id_fkey
+  FOREIGN KEY (file_id) REFERENCES files(id) ON DELETE CASCADE;
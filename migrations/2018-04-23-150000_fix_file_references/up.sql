@@ -0,0 +1,20 @@
+-- Your SQL goes here
+ALTER TABLE images DROP CONSTRAINT images_file_id_fkey;
+ALTER TABLE images ADD CONSTRAINT images_file_id_fkey
+  FOREIGN KEY (file_id) REFERENCES files(id) ON DELETE RESTRICT;
+
+ALTER TABLE media_posts DROP CONSTRAINT media_posts_file_id_fkey;
+ALTER TABLE media_posts ADD CONSTRAINT media_posts_file_id_fkey
+  FOREIGN KEY (file_id) REFERENCES files(id) ON DELETE RESTRICT;
+
+ALTER TABLE base_posts DROP CONSTRAINT base_posts_icon_fkey;
+ALTER TABLE base_posts ADD CONSTRAINT base_posts_icon_fkey
+  FOREIGN KEY (icon) REFERENCES images(id) ON DELETE SET NULL;
+
+ALTER TABLE personas DROP CONSTRAINT personas_avatar_fkey;
+ALTER TABLE personas ADD CONSTRAINT personas_avatar_fkey
+  FOREIGN KEY (avatar) REFERENCES images(id) ON DELETE SET NULL;
+
+CREATE INDEX images_file_id_index ON images (file_id);
+CREATE INDEX media_posts_file_id_index ON media_posts (file_id);
+CREATE INDEX files_created_at_index ON files (created_at);
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE links DROP COLUMN link_card;
+
+DROP TABLE link_cards;
@@ -0,0 +1,15 @@
+-- Your SQL goes here
+CREATE TABLE link_cards (
+  id SERIAL PRIMARY KEY,
+  url VARCHAR(2048) UNIQUE NOT NULL,
+  title TEXT,
+  description TEXT,
+  image_url VARCHAR(2048),
+  image_width INTEGER,
+  image_height INTEGER,
+  provider_name VARCHAR(256),
+  provider_url VARCHAR(2048),
+  fetched_at TIMESTAMPTZ NOT NULL
+);
+
+ALTER TABLE links ADD COLUMN link_card INTEGER REFERENCES link_cards(id) ON DELETE SET NULL;
@@ -0,0 +1,10 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE group_bans;
+DROP TABLE group_join_requests;
+DROP TABLE group_invitations;
+
+ALTER TABLE group_actors DROP CONSTRAINT group_actors_group_id_base_actor_id_key;
+ALTER TABLE group_actors DROP COLUMN created_at;
+ALTER TABLE group_actors DROP COLUMN role;
+
+ALTER TABLE groups DROP COLUMN join_policy;
@@ -0,0 +1,34 @@
+-- Your SQL goes here
+ALTER TABLE groups ADD COLUMN join_policy VARCHAR(8) NOT NULL DEFAULT 'OPEN';
+
+ALTER TABLE group_actors ADD COLUMN role VARCHAR(16) NOT NULL DEFAULT 'member';
+ALTER TABLE group_actors ADD COLUMN created_at TIMESTAMPTZ NOT NULL DEFAULT now();
+ALTER TABLE group_actors ADD CONSTRAINT group_actors_group_id_base_actor_id_key
+  UNIQUE (group_id, base_actor_id);
+
+CREATE TABLE group_invitations (
+  id SERIAL PRIMARY KEY,
+  group_id INTEGER REFERENCES groups(id) ON DELETE CASCADE NOT NULL,
+  invited_actor INTEGER REFERENCES base_actors(id) ON DELETE CASCADE NOT NULL,
+  invited_by INTEGER REFERENCES base_actors(id) ON DELETE CASCADE NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL,
+  UNIQUE (group_id, invited_actor)
+);
+
+CREATE TABLE group_join_requests (
+  id SERIAL PRIMARY KEY,
+  group_id INTEGER REFERENCES groups(id) ON DELETE CASCADE NOT NULL,
+  base_actor_id INTEGER REFERENCES base_actors(id) ON DELETE CASCADE NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL,
+  UNIQUE (group_id, base_actor_id)
+);
+
+CREATE TABLE group_bans (
+  id SERIAL PRIMARY KEY,
+  group_id INTEGER REFERENCES groups(id) ON DELETE CASCADE NOT NULL,
+  base_actor_id INTEGER REFERENCES base_actors(id) ON DELETE CASCADE NOT NULL,
+  banned_by INTEGER REFERENCES base_actors(id) ON DELETE SET NULL,
+  reason TEXT,
+  created_at TIMESTAMPTZ NOT NULL,
+  UNIQUE (group_id, base_actor_id)
+);
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX group_posts_base_post_id_index;
+DROP TABLE group_posts;
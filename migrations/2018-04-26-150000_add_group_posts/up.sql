@@ -0,0 +1,10 @@
+-- Your SQL goes here
+CREATE TABLE group_posts (
+  id SERIAL PRIMARY KEY,
+  group_id INTEGER REFERENCES groups(id) ON DELETE CASCADE NOT NULL,
+  base_post_id INTEGER REFERENCES base_posts(id) ON DELETE CASCADE NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL,
+  UNIQUE (group_id, base_post_id)
+);
+
+CREATE INDEX group_posts_base_post_id_index ON group_posts (base_post_id);
@@ -0,0 +1,16 @@
+-- Your SQL goes here
+CREATE TABLE lists (
+  id SERIAL PRIMARY KEY,
+  owner INTEGER REFERENCES base_actors(id) ON DELETE CASCADE NOT NULL,
+  name VARCHAR(256) NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL,
+  UNIQUE (owner, name)
+);
+
+CREATE TABLE list_members (
+  id SERIAL PRIMARY KEY,
+  list_id INTEGER REFERENCES lists(id) ON DELETE CASCADE NOT NULL,
+  base_actor_id INTEGER REFERENCES base_actors(id) ON DELETE CASCADE NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL,
+  UNIQUE (list_id, base_actor_id)
+);
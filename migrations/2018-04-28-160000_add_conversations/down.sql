@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX conversation_posts_conversation_id_created_at_index;
+DROP INDEX conversation_participants_base_actor_id_index;
+DROP TABLE conversation_posts;
+DROP TABLE conversation_participants;
+DROP TABLE conversations;
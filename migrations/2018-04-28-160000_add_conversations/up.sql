@@ -0,0 +1,28 @@
+-- Your SQL goes here
+CREATE TABLE conversations (
+  id SERIAL PRIMARY KEY,
+  created_at TIMESTAMPTZ NOT NULL,
+  last_post_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE conversation_participants (
+  id SERIAL PRIMARY KEY,
+  conversation_id INTEGER REFERENCES conversations(id) ON DELETE CASCADE NOT NULL,
+  base_actor_id INTEGER REFERENCES base_actors(id) ON DELETE CASCADE NOT NULL,
+  last_read_at TIMESTAMPTZ,
+  muted BOOLEAN NOT NULL DEFAULT false,
+  left_conversation BOOLEAN NOT NULL DEFAULT false,
+  UNIQUE (conversation_id, base_actor_id)
+);
+
+CREATE TABLE conversation_posts (
+  id SERIAL PRIMARY KEY,
+  conversation_id INTEGER REFERENCES conversations(id) ON DELETE CASCADE NOT NULL,
+  base_post_id INTEGER REFERENCES base_posts(id) ON DELETE CASCADE UNIQUE NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX conversation_participants_base_actor_id_index
+  ON conversation_participants (base_actor_id);
+CREATE INDEX conversation_posts_conversation_id_created_at_index
+  ON conversation_posts (conversation_id, created_at);
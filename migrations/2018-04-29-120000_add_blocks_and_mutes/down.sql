@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX blocks_blocked_index;
+DROP TABLE mutes;
+DROP TABLE blocks;
@@ -0,0 +1,19 @@
+-- Your SQL goes here
+CREATE TABLE blocks (
+  id SERIAL PRIMARY KEY,
+  blocker INTEGER REFERENCES base_actors(id) ON DELETE CASCADE NOT NULL,
+  blocked INTEGER REFERENCES base_actors(id) ON DELETE CASCADE NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL,
+  UNIQUE (blocker, blocked)
+);
+
+CREATE TABLE mutes (
+  id SERIAL PRIMARY KEY,
+  muter INTEGER REFERENCES base_actors(id) ON DELETE CASCADE NOT NULL,
+  muted INTEGER REFERENCES base_actors(id) ON DELETE CASCADE NOT NULL,
+  timer_id INTEGER REFERENCES timers(id) ON DELETE CASCADE,
+  created_at TIMESTAMPTZ NOT NULL,
+  UNIQUE (muter, muted)
+);
+
+CREATE INDEX blocks_blocked_index ON blocks (blocked);
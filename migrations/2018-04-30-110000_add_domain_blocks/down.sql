@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX base_actors_domain_index;
+ALTER TABLE base_actors DROP COLUMN domain;
+DROP TABLE domain_blocks;
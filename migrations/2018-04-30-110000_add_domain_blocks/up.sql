@@ -0,0 +1,18 @@
+-- Your SQL goes here
+CREATE TABLE domain_blocks (
+  id SERIAL PRIMARY KEY,
+  domain VARCHAR(256) UNIQUE NOT NULL,
+  severity VARCHAR(16) NOT NULL,
+  public_reason TEXT,
+  private_reason TEXT,
+  created_at TIMESTAMPTZ NOT NULL,
+  updated_at TIMESTAMPTZ NOT NULL
+);
+
+ALTER TABLE base_actors ADD COLUMN domain VARCHAR(256);
+
+UPDATE base_actors
+  SET domain = lower(substring(profile_url from '^[A-Za-z][A-Za-z0-9+.-]*://(?:[^@/]*@)?([^/:?#]+)'))
+  WHERE local_user IS NULL;
+
+CREATE INDEX base_actors_domain_index ON base_actors (domain);
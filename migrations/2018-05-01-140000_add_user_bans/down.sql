@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX user_bans_user_id_index;
+DROP TABLE user_bans;
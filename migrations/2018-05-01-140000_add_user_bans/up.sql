@@ -0,0 +1,14 @@
+-- Your SQL goes here
+CREATE TABLE user_bans (
+  id SERIAL PRIMARY KEY,
+  user_id INTEGER REFERENCES users(id) ON DELETE CASCADE NOT NULL,
+  moderator INTEGER REFERENCES users(id) ON DELETE SET NULL,
+  kind VARCHAR(16) NOT NULL,
+  reason TEXT NOT NULL,
+  timer_id INTEGER REFERENCES timers(id),
+  created_at TIMESTAMPTZ NOT NULL,
+  lifted_at TIMESTAMPTZ,
+  lifted_by INTEGER REFERENCES users(id) ON DELETE SET NULL
+);
+
+CREATE INDEX user_bans_user_id_index ON user_bans (user_id) WHERE lifted_at IS NULL;
@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+DELETE FROM permissions WHERE name = 'moderate-reports';
+DROP INDEX reports_target_actor_index;
+DROP INDEX reports_status_index;
+DROP TABLE report_notes;
+DROP TABLE reports;
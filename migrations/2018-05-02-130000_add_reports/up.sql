@@ -0,0 +1,35 @@
+-- Your SQL goes here
+CREATE TABLE reports (
+  id SERIAL PRIMARY KEY,
+  reporter INTEGER REFERENCES base_actors(id) ON DELETE SET NULL,
+  target_actor INTEGER REFERENCES base_actors(id) ON DELETE CASCADE NOT NULL,
+  target_post INTEGER REFERENCES base_posts(id) ON DELETE SET NULL,
+  category VARCHAR(16) NOT NULL,
+  comment TEXT NOT NULL,
+  forward BOOLEAN NOT NULL DEFAULT false,
+  status VARCHAR(16) NOT NULL DEFAULT 'OPEN',
+  assigned_to INTEGER REFERENCES users(id) ON DELETE SET NULL,
+  user_ban INTEGER REFERENCES user_bans(id) ON DELETE SET NULL,
+  domain_block INTEGER REFERENCES domain_blocks(id) ON DELETE SET NULL,
+  created_at TIMESTAMPTZ NOT NULL,
+  updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE report_notes (
+  id SERIAL PRIMARY KEY,
+  report_id INTEGER REFERENCES reports(id) ON DELETE CASCADE NOT NULL,
+  author INTEGER REFERENCES users(id) ON DELETE SET NULL,
+  body TEXT NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX reports_status_index ON reports (status, created_at);
+CREATE INDEX reports_target_actor_index ON reports (target_actor);
+
+INSERT INTO permissions (name, created_at) VALUES ('moderate-reports', 'now');
+
+INSERT INTO role_permissions (role_id, permission_id, created_at) VALUES (
+    (SELECT id FROM roles WHERE name = 'moderator'),
+    (SELECT id FROM permissions WHERE name = 'moderate-reports'),
+    'now'
+);
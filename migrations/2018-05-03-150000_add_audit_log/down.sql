@@ -0,0 +1,7 @@
+-- This file should undo anything in `up.sql`
+DROP TRIGGER audit_log_append_only ON audit_log;
+DROP FUNCTION audit_log_append_only();
+DROP INDEX audit_log_action_target_id_index;
+DROP INDEX audit_log_actor_index;
+DROP INDEX audit_log_created_at_index;
+DROP TABLE audit_log;
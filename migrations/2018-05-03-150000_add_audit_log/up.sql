@@ -0,0 +1,24 @@
+-- Your SQL goes here
+CREATE TABLE audit_log (
+  id SERIAL PRIMARY KEY,
+  actor INTEGER,
+  action VARCHAR(32) NOT NULL,
+  target_id INTEGER,
+  before JSONB,
+  after JSONB,
+  created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX audit_log_created_at_index ON audit_log (created_at);
+CREATE INDEX audit_log_actor_index ON audit_log (actor);
+CREATE INDEX audit_log_action_target_id_index ON audit_log (action, target_id);
+
+CREATE FUNCTION audit_log_append_only() RETURNS trigger AS $$
+BEGIN
+  RAISE EXCEPTION 'audit_log entries cannot be changed or removed';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER audit_log_append_only
+  BEFORE UPDATE OR DELETE ON audit_log
+  FOR EACH ROW EXECUTE PROCEDURE audit_log_append_only();
@@ -0,0 +1,7 @@
+-- This file should undo anything in `up.sql`
+DELETE FROM permissions WHERE name = 'manage-roles';
+
+ALTER TABLE user_roles DROP CONSTRAINT user_roles_user_id_role_id_key;
+ALTER TABLE role_permissions DROP CONSTRAINT role_permissions_role_id_permission_id_key;
+
+UPDATE permissions SET name = 'manage-follow-requests' WHERE name = 'manage-follow-request';
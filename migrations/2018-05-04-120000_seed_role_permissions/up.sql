@@ -0,0 +1,66 @@
+-- Your SQL goes here
+UPDATE permissions SET name = 'manage-follow-request' WHERE name = 'manage-follow-requests';
+
+DELETE FROM role_permissions WHERE id IN (
+  SELECT id FROM (
+    SELECT id, row_number() OVER (PARTITION BY role_id, permission_id ORDER BY id) AS copy_number
+    FROM role_permissions
+  ) AS duplicates
+  WHERE duplicates.copy_number > 1
+);
+
+DELETE FROM user_roles WHERE id IN (
+  SELECT id FROM (
+    SELECT id, row_number() OVER (PARTITION BY user_id, role_id ORDER BY id) AS copy_number
+    FROM user_roles
+  ) AS duplicates
+  WHERE duplicates.copy_number > 1
+);
+
+ALTER TABLE role_permissions
+  ADD CONSTRAINT role_permissions_role_id_permission_id_key UNIQUE (role_id, permission_id);
+ALTER TABLE user_roles
+  ADD CONSTRAINT user_roles_user_id_role_id_key UNIQUE (user_id, role_id);
+
+INSERT INTO roles (name, created_at) VALUES
+  ('admin', 'now'),
+  ('moderator', 'now'),
+  ('verified', 'now')
+ON CONFLICT (name) DO NOTHING;
+
+INSERT INTO permissions (name, created_at) VALUES
+  ('follow-user', 'now'),
+  ('make-post', 'now'),
+  ('make-media-post', 'now'),
+  ('make-comment', 'now'),
+  ('make-persona', 'now'),
+  ('manage-follow-request', 'now'),
+  ('ban-user', 'now'),
+  ('block-instance', 'now'),
+  ('moderate-reports', 'now'),
+  ('configure-instance', 'now'),
+  ('grant-role', 'now'),
+  ('revoke-role', 'now'),
+  ('manage-roles', 'now')
+ON CONFLICT (name) DO NOTHING;
+
+INSERT INTO role_permissions (role_id, permission_id, created_at)
+SELECT roles.id, permissions.id, 'now'
+FROM (VALUES
+  ('verified', 'follow-user'),
+  ('verified', 'make-post'),
+  ('verified', 'make-media-post'),
+  ('verified', 'make-comment'),
+  ('verified', 'make-persona'),
+  ('verified', 'manage-follow-request'),
+  ('moderator', 'ban-user'),
+  ('moderator', 'block-instance'),
+  ('moderator', 'moderate-reports'),
+  ('admin', 'configure-instance'),
+  ('admin', 'grant-role'),
+  ('admin', 'revoke-role'),
+  ('admin', 'manage-roles')
+) AS seed (role_name, permission_name)
+INNER JOIN roles ON roles.name = seed.role_name
+INNER JOIN permissions ON permissions.name = seed.permission_name
+ON CONFLICT (role_id, permission_id) DO NOTHING;
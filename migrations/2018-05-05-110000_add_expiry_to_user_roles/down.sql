@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX user_roles_expires_at_index;
+
+ALTER TABLE user_roles DROP COLUMN granted_by;
+ALTER TABLE user_roles DROP COLUMN expires_at;
@@ -0,0 +1,5 @@
+-- Your SQL goes here
+ALTER TABLE user_roles ADD COLUMN expires_at TIMESTAMPTZ;
+ALTER TABLE user_roles ADD COLUMN granted_by INTEGER REFERENCES users(id) ON DELETE SET NULL;
+
+CREATE INDEX user_roles_expires_at_index ON user_roles (expires_at);
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE actor_restrictions;
+
+DROP INDEX actor_delegations_user_id_index;
+DROP TABLE actor_delegations;
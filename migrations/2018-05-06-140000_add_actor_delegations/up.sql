@@ -0,0 +1,21 @@
+-- Your SQL goes here
+CREATE TABLE actor_delegations (
+  id SERIAL PRIMARY KEY,
+  base_actor INTEGER REFERENCES base_actors(id) ON DELETE CASCADE NOT NULL,
+  user_id INTEGER REFERENCES users(id) ON DELETE CASCADE NOT NULL,
+  granted_by INTEGER REFERENCES users(id) ON DELETE SET NULL,
+  created_at TIMESTAMPTZ NOT NULL,
+  UNIQUE (base_actor, user_id)
+);
+
+CREATE INDEX actor_delegations_user_id_index ON actor_delegations (user_id);
+
+CREATE TABLE actor_restrictions (
+  id SERIAL PRIMARY KEY,
+  base_actor INTEGER REFERENCES base_actors(id) ON DELETE CASCADE NOT NULL,
+  permission_id INTEGER REFERENCES permissions(id) ON DELETE CASCADE NOT NULL,
+  restricted_by INTEGER REFERENCES users(id) ON DELETE SET NULL,
+  reason TEXT,
+  created_at TIMESTAMPTZ NOT NULL,
+  UNIQUE (base_actor, permission_id)
+);
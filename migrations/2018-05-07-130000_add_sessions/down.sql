@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX sessions_expires_at_index;
+DROP INDEX sessions_user_id_index;
+DROP TABLE sessions;
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX files_last_stored_at_index;
+ALTER TABLE files DROP COLUMN last_stored_at;
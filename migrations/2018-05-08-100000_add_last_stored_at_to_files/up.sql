@@ -0,0 +1,4 @@
+-- Your SQL goes here
+ALTER TABLE files ADD COLUMN last_stored_at TIMESTAMPTZ NOT NULL DEFAULT now();
+UPDATE files SET last_stored_at = created_at;
+CREATE INDEX files_last_stored_at_index ON files (last_stored_at);
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX audit_log_target_index;
+CREATE INDEX audit_log_action_target_id_index ON audit_log (action, target_id);
+ALTER TABLE audit_log DROP COLUMN target_type;
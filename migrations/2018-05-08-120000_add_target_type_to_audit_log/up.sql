@@ -0,0 +1,21 @@
+-- Your SQL goes here
+ALTER TABLE audit_log ADD COLUMN target_type VARCHAR(32);
+
+-- Entries are otherwise append-only
+ALTER TABLE audit_log DISABLE TRIGGER audit_log_append_only;
+
+UPDATE audit_log SET target_type = CASE
+  WHEN action IN ('grant-role', 'revoke-role') THEN 'user'
+  WHEN action IN ('ban-user', 'unban-user') THEN 'user-ban'
+  WHEN action IN ('block-domain', 'unblock-domain') THEN 'domain-block'
+  WHEN action = 'update-report' THEN 'report'
+  WHEN action IN ('restrict-actor', 'unrestrict-actor') THEN 'actor-restriction'
+  ELSE 'role'
+END;
+
+ALTER TABLE audit_log ENABLE TRIGGER audit_log_append_only;
+
+ALTER TABLE audit_log ALTER COLUMN target_type SET NOT NULL;
+
+DROP INDEX audit_log_action_target_id_index;
+CREATE INDEX audit_log_target_index ON audit_log (target_type, target_id);
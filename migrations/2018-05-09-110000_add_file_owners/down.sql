@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX file_owners_user_id_index;
+DROP TABLE file_owners;
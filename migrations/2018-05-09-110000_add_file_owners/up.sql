@@ -0,0 +1,13 @@
+-- Your SQL goes here
+CREATE TABLE file_owners (
+  id SERIAL PRIMARY KEY,
+  file_id INTEGER REFERENCES files(id) ON DELETE CASCADE NOT NULL,
+  user_id INTEGER REFERENCES users(id) ON DELETE CASCADE NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL,
+  UNIQUE (file_id, user_id)
+);
+
+CREATE INDEX file_owners_user_id_index ON file_owners (user_id);
+
+INSERT INTO file_owners (file_id, user_id, created_at)
+  SELECT id, owner, created_at FROM files WHERE owner IS NOT NULL;
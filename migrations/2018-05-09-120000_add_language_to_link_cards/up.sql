@@ -0,0 +1,5 @@
+-- Your SQL goes here
+ALTER TABLE link_cards ADD COLUMN language VARCHAR(35) NOT NULL DEFAULT 'und';
+
+-- Links previewed by a card were given the language of the post linking to them
+UPDATE links SET href_lang = 'und' WHERE link_card IS NOT NULL;
@@ -0,0 +1,289 @@
+use serde_json::{Map, Value};
+
+use base_actor::BaseActor;
+use base_post::BasePost;
+use base_post::post::Post;
+use base_post::post::media_post::MediaPost;
+use base_post::post::render::sanitize;
+use sql_types::{AttachmentType, Lang, PostVisibility, Url};
+
+const PUBLIC: &str = "https://www.w3.org/ns/activitystreams#Public";
+
+/// Produce an ActivityStreams `Note` object from a post.
+///
+/// When the post's language is known, its content is also included in the `contentMap` under
+/// that language's tag. Attachments should be provided in order, along with the URL each one can
+/// be fetched from.
+pub fn note(
+    posted_by: &BaseActor,
+    base_post: &BasePost,
+    post: &Post,
+    attachments: &[(MediaPost, Url)],
+) -> Value {
+    let mut object = json!({
+        "type": "Note",
+        "attributedTo": posted_by.profile_url().0.as_str(),
+        "mediaType": base_post.media_type().0.as_ref(),
+        "content": post.content(),
+        "sensitive": base_post.sensitive(),
+    });
+
+    if let Some(name) = base_post.name() {
+        object["name"] = json!(name);
+    }
+
+    if let Some(summary) = base_post.summary() {
+        object["summary"] = json!(summary);
+    }
+
+    if let (Some(source), Some(source_format)) = (post.source(), post.source_format()) {
+        object["source"] = json!({
+            "content": source,
+            "mediaType": format!("{}", source_format),
+        });
+    }
+
+    if *post.language() != Lang::undetermined() {
+        let mut content_map = Map::new();
+        content_map.insert(
+            format!("{}", post.language()),
+            Value::String(post.content().to_owned()),
+        );
+
+        object["contentMap"] = Value::Object(content_map);
+    }
+
+    if !attachments.is_empty() {
+        object["attachment"] = Value::Array(
+            attachments
+                .iter()
+                .map(|&(ref media_post, ref url)| attachment(media_post, url))
+                .collect(),
+        );
+    }
+
+    object
+}
+
+/// Produce an ActivityStreams `Announce` activity, for a group to redistribute a post made in
+/// it to the group's members.
+///
+/// Public posts are also addressed to the public collection.
+pub fn announce(
+    group_actor: &BaseActor,
+    base_post: &BasePost,
+    object: Value,
+    members: &[BaseActor],
+) -> Value {
+    let mut activity = json!({
+        "type": "Announce",
+        "actor": group_actor.profile_url().0.as_str(),
+        "object": object,
+        "to": members
+            .iter()
+            .map(|member| member.profile_url().0.as_str())
+            .collect::<Vec<_>>(),
+    });
+
+    if base_post.visibility() == PostVisibility::Public {
+        activity["cc"] = json!([PUBLIC]);
+    }
+
+    activity
+}
+
+/// Produce an ActivityStreams `Flag` activity, for forwarding a report to the instance the
+/// reported actor lives on.
+///
+/// When a post was reported, its id is included alongside the actor, if the post has one.
+pub fn flag(
+    instance_actor: &BaseActor,
+    target: &BaseActor,
+    base_post: Option<&BasePost>,
+    comment: &str,
+) -> Value {
+    let mut objects = vec![Value::String(target.profile_url().0.as_str().to_owned())];
+
+    if let Some(id) = base_post.and_then(|base_post| base_post.original_json()["id"].as_str()) {
+        objects.push(Value::String(id.to_owned()));
+    }
+
+    json!({
+        "type": "Flag",
+        "actor": instance_actor.profile_url().0.as_str(),
+        "object": objects,
+        "content": comment,
+    })
+}
+
+/// Produce an ActivityStreams object for a single attachment.
+pub fn attachment(media_post: &MediaPost, url: &Url) -> Value {
+    let mut object = json!({
+        "type": attachment_object_type(media_post.attachment_type()),
+        "url": url.0.as_str(),
+    });
+
+    if let Some(description) = media_post.description() {
+        object["name"] = json!(description);
+    }
+
+    if let Some(blurhash) = media_post.blurhash() {
+        object["blurhash"] = json!(blurhash);
+    }
+
+    if let Some((x, y)) = media_post.focus() {
+        object["focalPoint"] = json!([x, y]);
+    }
+
+    object
+}
+
+fn attachment_object_type(attachment_type: AttachmentType) -> &'static str {
+    match attachment_type {
+        AttachmentType::Image => "Image",
+        AttachmentType::Video => "Video",
+        AttachmentType::Audio => "Audio",
+    }
+}
+
+/// An attachment described by a remote ActivityStreams object, which has not been fetched yet.
+#[derive(Debug)]
+pub struct RemoteAttachment {
+    pub url: Url,
+    pub media_type: Option<String>,
+    pub attachment_type: AttachmentType,
+    pub description: Option<String>,
+    pub focus: Option<(f32, f32)>,
+    pub blurhash: Option<String>,
+}
+
+/// Read the attachments from an ActivityStreams object, in order.
+///
+/// Attachments without a usable URL, or that aren't images, videos, or audio, are skipped.
+pub fn attachments(object: &Value) -> Vec<RemoteAttachment> {
+    let attachments = match object.get("attachment") {
+        Some(&Value::Array(ref attachments)) => attachments.iter().collect(),
+        Some(attachment) => vec![attachment],
+        None => Vec::new(),
+    };
+
+    attachments
+        .into_iter()
+        .filter_map(|attachment| {
+            let url = match attachment.get("url") {
+                Some(&Value::String(ref url)) => Some(url.as_str()),
+                Some(&Value::Object(ref link)) => link.get("href").and_then(|href| href.as_str()),
+                _ => None,
+            }?;
+            let url = url.parse().ok().map(Url)?;
+
+            let media_type = attachment
+                .get("mediaType")
+                .and_then(|media_type| media_type.as_str())
+                .map(|media_type| media_type.to_owned());
+
+            let attachment_type = match attachment.get("type").and_then(|kind| kind.as_str()) {
+                Some("Image") => Some(AttachmentType::Image),
+                Some("Video") => Some(AttachmentType::Video),
+                Some("Audio") => Some(AttachmentType::Audio),
+                _ => media_type.as_ref().and_then(|media_type| {
+                    if media_type.starts_with("image/") {
+                        Some(AttachmentType::Image)
+                    } else if media_type.starts_with("video/") {
+                        Some(AttachmentType::Video)
+                    } else if media_type.starts_with("audio/") {
+                        Some(AttachmentType::Audio)
+                    } else {
+                        None
+                    }
+                }),
+            }?;
+
+            let focus = attachment
+                .get("focalPoint")
+                .and_then(|focus| focus.as_array())
+                .and_then(|focus| match (focus.get(0), focus.get(1)) {
+                    (Some(x), Some(y)) => Some((x.as_f64()? as f32, y.as_f64()? as f32)),
+                    _ => None,
+                });
+
+            Some(RemoteAttachment {
+                url,
+                media_type,
+                attachment_type,
+                description: attachment
+                    .get("name")
+                    .and_then(|name| name.as_str())
+                    .map(|name| name.to_owned()),
+                focus,
+                blurhash: attachment
+                    .get("blurhash")
+                    .and_then(|blurhash| blurhash.as_str())
+                    .map(|blurhash| blurhash.to_owned()),
+            })
+        })
+        .collect()
+}
+
+/// Read the content and its language from an ActivityStreams object.
+///
+/// If the object has a `content` field, the language is looked up in the `contentMap`, falling
+/// back to `und` when no entry matches. If the object only has a `contentMap`, the first valid
+/// entry is used.
+pub fn content(object: &Value) -> Option<(String, Lang)> {
+    let content_map = object
+        .get("contentMap")
+        .and_then(|map| map.as_object())
+        .map(|map| {
+            map.iter()
+                .filter_map(|(lang, content)| {
+                    let lang = lang.parse::<Lang>().ok()?;
+                    let content = content.as_str()?;
+
+                    Some((content.to_owned(), lang))
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    match object.get("content").and_then(|content| content.as_str()) {
+        Some(content) => {
+            let lang = content_map
+                .into_iter()
+                .find(|&(ref mapped, _)| mapped == content)
+                .map(|(_, lang)| lang)
+                .unwrap_or_else(Lang::undetermined);
+
+            Some((content.to_owned(), lang))
+        }
+        None => content_map.into_iter().next(),
+    }
+}
+
+/// Read the content warning from an ActivityStreams object.
+///
+/// Remote summaries may contain HTML, so they are sanitized. Empty summaries are treated as
+/// missing.
+pub fn summary(object: &Value) -> Option<String> {
+    object
+        .get("summary")
+        .and_then(|summary| summary.as_str())
+        .map(|summary| sanitize(summary))
+        .and_then(|summary| {
+            if summary.trim().is_empty() {
+                None
+            } else {
+                Some(summary)
+            }
+        })
+}
+
+/// Read whether an ActivityStreams object's media is sensitive.
+///
+/// Objects that don't say otherwise are not sensitive.
+pub fn sensitive(object: &Value) -> bool {
+    object
+        .get("sensitive")
+        .and_then(|sensitive| sensitive.as_bool())
+        .unwrap_or(false)
+}
@@ -0,0 +1,176 @@
+use chrono::DateTime;
+use chrono::offset::Utc;
+use diesel;
+use diesel::pg::PgConnection;
+use serde_json::Value;
+
+use schema::audit_log;
+use sql_types::{AuditAction, AuditTargetType};
+
+/// A record of a privileged action.
+///
+/// Entries can't be changed or removed once written. The `actor` and `target_id` aren't foreign
+/// keys, so entries outlive the users and records they refer to.
+#[derive(Debug, Queryable)]
+pub struct AuditLogEntry {
+    id: i32,
+    actor: Option<i32>, // id of a User
+    action: AuditAction,
+    target_id: Option<i32>,
+    before: Option<Value>,
+    after: Option<Value>,
+    created_at: DateTime<Utc>,
+    target_type: AuditTargetType,
+}
+
+impl AuditLogEntry {
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+
+    /// The user who performed the action, or `None` if the instance did it on its own.
+    pub fn actor(&self) -> Option<i32> {
+        self.actor
+    }
+
+    pub fn action(&self) -> AuditAction {
+        self.action
+    }
+
+    /// The id of the record the action was performed on, whose kind is given by `target_type`.
+    pub fn target_id(&self) -> Option<i32> {
+        self.target_id
+    }
+
+    /// The kind of record `target_id` refers to, which follows from the `action`.
+    pub fn target_type(&self) -> AuditTargetType {
+        self.target_type
+    }
+
+    /// The state of the target before the action, if it existed.
+    pub fn before(&self) -> Option<&Value> {
+        self.before.as_ref()
+    }
+
+    /// The state of the target after the action, if it still exists.
+    pub fn after(&self) -> Option<&Value> {
+        self.after.as_ref()
+    }
+
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+
+    /// Fetch a page of entries matching the filter, newest first.
+    pub(crate) fn query(
+        filter: &AuditLogFilter,
+        limit: i64,
+        offset: i64,
+        conn: &PgConnection,
+    ) -> Result<Vec<AuditLogEntry>, diesel::result::Error> {
+        use diesel::prelude::*;
+
+        let mut query = audit_log::table.into_boxed();
+
+        if let Some(actor) = filter.actor {
+            query = query.filter(audit_log::dsl::actor.eq(actor));
+        }
+
+        if let Some(action) = filter.action {
+            query = query.filter(audit_log::dsl::action.eq(action));
+        }
+
+        if let Some(target_type) = filter.target_type {
+            query = query.filter(audit_log::dsl::target_type.eq(target_type));
+        }
+
+        if let Some(target_id) = filter.target_id {
+            query = query.filter(audit_log::dsl::target_id.eq(target_id));
+        }
+
+        if let Some(since) = filter.since {
+            query = query.filter(audit_log::dsl::created_at.ge(since));
+        }
+
+        if let Some(until) = filter.until {
+            query = query.filter(audit_log::dsl::created_at.lt(until));
+        }
+
+        query
+            .order((audit_log::dsl::created_at.desc(), audit_log::dsl::id.desc()))
+            .limit(limit)
+            .offset(offset)
+            .load(conn)
+    }
+
+    /// Write an entry to the audit log.
+    pub(crate) fn record(
+        actor: Option<i32>,
+        action: AuditAction,
+        target_id: Option<i32>,
+        before: Option<Value>,
+        after: Option<Value>,
+        conn: &PgConnection,
+    ) -> Result<(), diesel::result::Error> {
+        use diesel::prelude::*;
+
+        diesel::insert_into(audit_log::table)
+            .values(&NewAuditLogEntry {
+                actor,
+                action,
+                target_id,
+                before,
+                after,
+                created_at: Utc::now(),
+                target_type: action.target_type(),
+            })
+            .execute(conn)
+            .map(|_| ())
+    }
+}
+
+/// Which audit log entries to fetch. Fields that are `None` match every entry.
+#[derive(Clone, Debug, Default)]
+pub struct AuditLogFilter {
+    pub actor: Option<i32>,
+    pub action: Option<AuditAction>,
+    pub target_type: Option<AuditTargetType>,
+    /// Ids of different kinds of records overlap, so this is usually combined with `action` or
+    /// `target_type`.
+    pub target_id: Option<i32>,
+    /// Only include entries written at or after this time.
+    pub since: Option<DateTime<Utc>>,
+    /// Only include entries written before this time.
+    pub until: Option<DateTime<Utc>>,
+}
+
+#[derive(Insertable)]
+#[table_name = "audit_log"]
+struct NewAuditLogEntry {
+    actor: Option<i32>,
+    action: AuditAction,
+    target_id: Option<i32>,
+    before: Option<Value>,
+    after: Option<Value>,
+    created_at: DateTime<Utc>,
+    target_type: AuditTargetType,
+}
+
+pub struct AuditLogViewer(());
+
+impl AuditLogViewer {
+    pub(crate) fn new() -> AuditLogViewer {
+        AuditLogViewer(())
+    }
+
+    /// Fetch a page of audit log entries matching the filter, newest first.
+    pub fn entries(
+        &self,
+        filter: &AuditLogFilter,
+        limit: i64,
+        offset: i64,
+        conn: &PgConnection,
+    ) -> Result<Vec<AuditLogEntry>, diesel::result::Error> {
+        AuditLogEntry::query(filter, limit, offset, conn)
+    }
+}
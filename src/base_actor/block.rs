@@ -0,0 +1,147 @@
+use chrono::DateTime;
+use chrono::offset::Utc;
+use diesel;
+use diesel::pg::PgConnection;
+
+use base_actor::BaseActor;
+use schema::blocks;
+
+/// A block stops two actors from following each other or replying to each other's posts.
+#[derive(Debug, Identifiable, Queryable)]
+#[table_name = "blocks"]
+pub struct Block {
+    id: i32,
+    blocker: i32, // foreign key to BaseActor
+    blocked: i32, // foreign key to BaseActor
+    created_at: DateTime<Utc>,
+}
+
+impl Block {
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+
+    pub fn blocker(&self) -> i32 {
+        self.blocker
+    }
+
+    pub fn blocked(&self) -> i32 {
+        self.blocked
+    }
+
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "blocks"]
+pub struct NewBlock {
+    blocker: i32,
+    blocked: i32,
+    created_at: DateTime<Utc>,
+}
+
+impl NewBlock {
+    pub fn new(blocker: &BaseActor, blocked: &BaseActor) -> Self {
+        NewBlock {
+            blocker: blocker.id(),
+            blocked: blocked.id(),
+            created_at: Utc::now(),
+        }
+    }
+}
+
+pub struct Blocker<'a>(&'a BaseActor);
+
+impl<'a> Blocker<'a> {
+    pub(crate) fn new(base_actor: &BaseActor) -> Blocker {
+        Blocker(base_actor)
+    }
+
+    pub fn blocks(&self, conn: &PgConnection) -> Result<Vec<Block>, diesel::result::Error> {
+        use schema::blocks;
+        use diesel::prelude::*;
+
+        blocks::table
+            .filter(blocks::dsl::blocker.eq(self.0.id()))
+            .order(blocks::dsl::created_at.desc())
+            .load(conn)
+    }
+
+    /// Block an actor.
+    ///
+    /// Any follows and follow requests between the two actors are removed, in both directions.
+    /// Blocking an actor who is already blocked returns the existing block.
+    pub fn block(
+        &self,
+        target_actor: &BaseActor,
+        conn: &PgConnection,
+    ) -> Result<Block, diesel::result::Error> {
+        use schema::{blocks, follow_requests, followers};
+        use diesel::prelude::*;
+
+        let (blocker, blocked) = (self.0.id(), target_actor.id());
+
+        conn.transaction(|| {
+            diesel::delete(
+                followers::table.filter(
+                    followers::dsl::follower
+                        .eq(blocker)
+                        .and(followers::dsl::follows.eq(blocked))
+                        .or(followers::dsl::follower
+                            .eq(blocked)
+                            .and(followers::dsl::follows.eq(blocker))),
+                ),
+            ).execute(conn)?;
+
+            diesel::delete(
+                follow_requests::table.filter(
+                    follow_requests::dsl::follower
+                        .eq(blocker)
+                        .and(follow_requests::dsl::requested_follow.eq(blocked))
+                        .or(follow_requests::dsl::follower
+                            .eq(blocked)
+                            .and(follow_requests::dsl::requested_follow.eq(blocker))),
+                ),
+            ).execute(conn)?;
+
+            diesel::insert_into(blocks::table)
+                .values(&NewBlock::new(self.0, target_actor))
+                .on_conflict_do_nothing()
+                .execute(conn)?;
+
+            blocks::table
+                .filter(blocks::dsl::blocker.eq(blocker))
+                .filter(blocks::dsl::blocked.eq(blocked))
+                .get_result(conn)
+        })
+    }
+
+    pub fn unblock(&self, block: Block, conn: &PgConnection) -> Result<(), BlockError> {
+        use diesel::prelude::*;
+
+        if block.blocker() != self.0.id() {
+            return Err(BlockError::IdMismatch);
+        }
+
+        diesel::delete(&block)
+            .execute(conn)
+            .map(|_| ())
+            .map_err(From::from)
+    }
+}
+
+#[derive(Debug, Fail)]
+pub enum BlockError {
+    #[fail(display = "Error managing block")]
+    Diesel(#[cause] diesel::result::Error),
+    #[fail(display = "Cannot manage other actor's blocks")]
+    IdMismatch,
+}
+
+impl From<diesel::result::Error> for BlockError {
+    fn from(e: diesel::result::Error) -> Self {
+        BlockError::Diesel(e)
+    }
+}
@@ -0,0 +1,144 @@
+use chrono::DateTime;
+use chrono::offset::Utc;
+use diesel;
+use diesel::pg::PgConnection;
+
+use base_actor::BaseActor;
+use schema::actor_delegations;
+use user::UserLike;
+
+/// Lets a user other than an actor's owner post and comment as that actor.
+///
+/// Delegates act with their own permissions, minus any restrictions placed on the actor. Managing
+/// the actor's account, such as its follows, lists, conversations, blocks, and mutes, is left to
+/// its owner.
+#[derive(Debug, Identifiable, Queryable)]
+#[table_name = "actor_delegations"]
+pub struct ActorDelegation {
+    id: i32,
+    base_actor: i32,         // foreign key to BaseActor
+    user_id: i32,            // foreign key to User
+    granted_by: Option<i32>, // foreign key to User
+    created_at: DateTime<Utc>,
+}
+
+impl ActorDelegation {
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+
+    pub fn base_actor(&self) -> i32 {
+        self.base_actor
+    }
+
+    pub fn user_id(&self) -> i32 {
+        self.user_id
+    }
+
+    /// The user who added this delegate, if they still exist.
+    pub fn granted_by(&self) -> Option<i32> {
+        self.granted_by
+    }
+
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "actor_delegations"]
+pub struct NewActorDelegation {
+    base_actor: i32,
+    user_id: i32,
+    granted_by: Option<i32>,
+    created_at: DateTime<Utc>,
+}
+
+impl NewActorDelegation {
+    pub fn new<U: UserLike>(base_actor: &BaseActor, user: &U, granted_by: i32) -> Self {
+        NewActorDelegation {
+            base_actor: base_actor.id(),
+            user_id: user.id(),
+            granted_by: Some(granted_by),
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// Chooses which other users may act as an actor.
+pub struct Delegator<'a>(&'a BaseActor);
+
+impl<'a> Delegator<'a> {
+    pub(crate) fn new(base_actor: &BaseActor) -> Delegator {
+        Delegator(base_actor)
+    }
+
+    pub fn delegates(
+        &self,
+        conn: &PgConnection,
+    ) -> Result<Vec<ActorDelegation>, diesel::result::Error> {
+        use schema::actor_delegations;
+        use diesel::prelude::*;
+
+        actor_delegations::table
+            .filter(actor_delegations::dsl::base_actor.eq(self.0.id()))
+            .order(actor_delegations::dsl::created_at.asc())
+            .load(conn)
+    }
+
+    /// Let another user post and comment as this actor. Adding a user who is already a delegate
+    /// does nothing.
+    pub fn add_delegate<U: UserLike>(
+        &self,
+        user: &U,
+        conn: &PgConnection,
+    ) -> Result<(), DelegationError> {
+        use schema::actor_delegations;
+        use diesel::prelude::*;
+
+        let owner = self.0.local_user().ok_or(DelegationError::RemoteActor)?;
+
+        if user.id() == owner {
+            return Err(DelegationError::Owner);
+        }
+
+        diesel::insert_into(actor_delegations::table)
+            .values(&NewActorDelegation::new(self.0, user, owner))
+            .on_conflict_do_nothing()
+            .execute(conn)
+            .map(|_| ())
+            .map_err(From::from)
+    }
+
+    pub fn remove_delegate<U: UserLike>(
+        &self,
+        user: &U,
+        conn: &PgConnection,
+    ) -> Result<(), diesel::result::Error> {
+        use schema::actor_delegations;
+        use diesel::prelude::*;
+
+        diesel::delete(
+            actor_delegations::table
+                .filter(actor_delegations::dsl::base_actor.eq(self.0.id()))
+                .filter(actor_delegations::dsl::user_id.eq(user.id())),
+        ).execute(conn)
+            .map(|_| ())
+    }
+}
+
+#[derive(Debug, Fail)]
+pub enum DelegationError {
+    #[fail(display = "Error managing delegates")]
+    Diesel(#[cause] diesel::result::Error),
+    #[fail(display = "Remote actors can't be delegated")]
+    RemoteActor,
+    #[fail(display = "An actor's owner can't be its delegate")]
+    Owner,
+}
+
+impl From<diesel::result::Error> for DelegationError {
+    fn from(e: diesel::result::Error) -> Self {
+        DelegationError::Diesel(e)
+    }
+}
@@ -1,10 +1,23 @@
+use diesel;
+use diesel::dsl::sql;
+use diesel::pg::PgConnection;
+use diesel::sql_types::Bool;
+
 use base_actor::BaseActor;
+use base_actor::group_actor::{GroupActor, NewGroupActor};
+use base_actor::group_ban::{GroupBan, NewGroupBan};
+use base_actor::group_invitation::{GroupInvitation, NewGroupInvitation};
+use base_actor::group_join_request::{GroupJoinRequest, NewGroupJoinRequest};
 use schema::groups;
+use sql_types::{GroupJoinPolicy, GroupRole};
+use user::ban::not_banned_sql;
 
-#[derive(Debug, Queryable)]
+#[derive(Debug, Identifiable, Queryable)]
+#[table_name = "groups"]
 pub struct Group {
     id: i32,
     base_actor_id: i32,
+    join_policy: GroupJoinPolicy,
 }
 
 impl Group {
@@ -15,18 +28,443 @@ impl Group {
     pub fn base_actor_id(&self) -> i32 {
         self.base_actor_id
     }
+
+    /// How actors become members of this group.
+    pub fn join_policy(&self) -> GroupJoinPolicy {
+        self.join_policy
+    }
+
+    /// Fetch the given actor's membership in this group, if they are a member.
+    pub fn membership(
+        &self,
+        base_actor: &BaseActor,
+        conn: &PgConnection,
+    ) -> Result<Option<GroupActor>, diesel::result::Error> {
+        use schema::group_actors;
+        use diesel::prelude::*;
+
+        group_actors::table
+            .filter(group_actors::dsl::group_id.eq(self.id))
+            .filter(group_actors::dsl::base_actor_id.eq(base_actor.id()))
+            .get_result(conn)
+            .optional()
+    }
+
+    /// Fetch this group's members, leaving out banned users.
+    pub fn members(&self, conn: &PgConnection) -> Result<Vec<GroupActor>, diesel::result::Error> {
+        use schema::group_actors;
+        use diesel::prelude::*;
+
+        group_actors::table
+            .filter(group_actors::dsl::group_id.eq(self.id))
+            .filter(sql::<Bool>(&not_banned_sql("group_actors.base_actor_id")))
+            .order(group_actors::dsl::created_at.asc())
+            .load(conn)
+    }
+
+    /// Fetch the actors of this group's members, so posts in the group can be delivered to them.
+    ///
+    /// Banned users are left out.
+    pub fn member_actors(
+        &self,
+        conn: &PgConnection,
+    ) -> Result<Vec<BaseActor>, diesel::result::Error> {
+        use schema::{base_actors, group_actors};
+        use diesel::prelude::*;
+
+        base_actors::table
+            .inner_join(group_actors::table)
+            .filter(group_actors::dsl::group_id.eq(self.id))
+            .filter(sql::<Bool>(&not_banned_sql("base_actors.id")))
+            .select(base_actors::all_columns)
+            .load(conn)
+    }
+
+    /// Whether the given actor is banned from this group.
+    pub fn is_banned(
+        &self,
+        base_actor: &BaseActor,
+        conn: &PgConnection,
+    ) -> Result<bool, diesel::result::Error> {
+        use schema::group_bans;
+        use diesel::prelude::*;
+
+        group_bans::table
+            .filter(group_bans::dsl::group_id.eq(self.id))
+            .filter(group_bans::dsl::base_actor_id.eq(base_actor.id()))
+            .count()
+            .get_result(conn)
+            .map(|count: i64| count > 0)
+    }
 }
 
 #[derive(Debug, Insertable)]
 #[table_name = "groups"]
 pub struct NewGroup {
     base_actor_id: i32,
+    join_policy: GroupJoinPolicy,
 }
 
 impl NewGroup {
-    pub fn new(actor: &BaseActor) -> Self {
+    pub fn new(actor: &BaseActor, join_policy: GroupJoinPolicy) -> Self {
         NewGroup {
             base_actor_id: actor.id(),
+            join_policy,
+        }
+    }
+}
+
+/// The result of asking to join a group.
+#[derive(Debug)]
+pub enum GroupJoin {
+    /// The actor is now a member of the group.
+    Joined(GroupActor),
+    /// The group's moderators need to approve the actor's request.
+    Requested(GroupJoinRequest),
+}
+
+pub struct GroupJoiner<'a>(&'a BaseActor);
+
+impl<'a> GroupJoiner<'a> {
+    pub(crate) fn new(base_actor: &BaseActor) -> GroupJoiner {
+        GroupJoiner(base_actor)
+    }
+
+    /// Join a group, or ask to join it if the group requires approval.
+    ///
+    /// Actors who have been invited join immediately, whatever the group's join policy is.
+    pub fn join_group(
+        &self,
+        group: &Group,
+        conn: &PgConnection,
+    ) -> Result<GroupJoin, GroupJoinError> {
+        use schema::{group_actors, group_invitations, group_join_requests};
+        use diesel::prelude::*;
+
+        if group.is_banned(self.0, conn)? {
+            return Err(GroupJoinError::Banned);
+        }
+
+        if let Some(membership) = group.membership(self.0, conn)? {
+            return Ok(GroupJoin::Joined(membership));
         }
+
+        conn.transaction(|| {
+            let invitations = diesel::delete(
+                group_invitations::table
+                    .filter(group_invitations::dsl::group_id.eq(group.id()))
+                    .filter(group_invitations::dsl::invited_actor.eq(self.0.id())),
+            ).execute(conn)?;
+
+            if invitations > 0 || group.join_policy() == GroupJoinPolicy::Open {
+                return diesel::insert_into(group_actors::table)
+                    .values(&NewGroupActor::new(group, self.0, GroupRole::Member))
+                    .get_result(conn)
+                    .map(GroupJoin::Joined)
+                    .map_err(From::from);
+            }
+
+            match group.join_policy() {
+                GroupJoinPolicy::Approval => diesel::insert_into(group_join_requests::table)
+                    .values(&NewGroupJoinRequest::new(group, self.0))
+                    .get_result(conn)
+                    .map(GroupJoin::Requested)
+                    .map_err(From::from),
+                _ => Err(GroupJoinError::InvitationRequired),
+            }
+        })
+    }
+
+    pub fn decline_invitation(
+        &self,
+        invitation: GroupInvitation,
+        conn: &PgConnection,
+    ) -> Result<(), GroupJoinError> {
+        use diesel::prelude::*;
+
+        if invitation.invited_actor() != self.0.id() {
+            return Err(GroupJoinError::IdMismatch);
+        }
+
+        diesel::delete(&invitation)
+            .execute(conn)
+            .map(|_| ())
+            .map_err(From::from)
+    }
+
+    /// Leave a group. A group's last owner can't leave it.
+    pub fn leave_group(&self, group: &Group, conn: &PgConnection) -> Result<(), GroupJoinError> {
+        use schema::group_actors;
+        use diesel::prelude::*;
+
+        let membership = match group.membership(self.0, conn)? {
+            Some(membership) => membership,
+            None => return Ok(()),
+        };
+
+        if membership.role() == GroupRole::Owner {
+            let owners: i64 = group_actors::table
+                .filter(group_actors::dsl::group_id.eq(group.id()))
+                .filter(group_actors::dsl::role.eq(GroupRole::Owner))
+                .count()
+                .get_result(conn)?;
+
+            if owners < 2 {
+                return Err(GroupJoinError::LastOwner);
+            }
+        }
+
+        diesel::delete(&membership)
+            .execute(conn)
+            .map(|_| ())
+            .map_err(From::from)
+    }
+}
+
+#[derive(Debug, Fail)]
+pub enum GroupJoinError {
+    #[fail(display = "Error joining group")]
+    Diesel(#[cause] diesel::result::Error),
+    #[fail(display = "Actor is banned from this group")]
+    Banned,
+    #[fail(display = "Group can only be joined by invitation")]
+    InvitationRequired,
+    #[fail(display = "Cannot manage other actor's invitations")]
+    IdMismatch,
+    #[fail(display = "Group's last owner cannot leave")]
+    LastOwner,
+}
+
+impl From<diesel::result::Error> for GroupJoinError {
+    fn from(e: diesel::result::Error) -> Self {
+        GroupJoinError::Diesel(e)
+    }
+}
+
+pub struct GroupAdministrator<'a>(&'a BaseActor, &'a Group, GroupRole);
+
+impl<'a> GroupAdministrator<'a> {
+    pub(crate) fn new(
+        base_actor: &'a BaseActor,
+        group: &'a Group,
+        role: GroupRole,
+    ) -> GroupAdministrator<'a> {
+        GroupAdministrator(base_actor, group, role)
+    }
+
+    pub fn invite(
+        &self,
+        target_actor: &BaseActor,
+        conn: &PgConnection,
+    ) -> Result<GroupInvitation, GroupAdminError> {
+        use schema::group_invitations;
+        use diesel::prelude::*;
+
+        if self.1.is_banned(target_actor, conn)? {
+            return Err(GroupAdminError::Banned);
+        }
+
+        diesel::insert_into(group_invitations::table)
+            .values(&NewGroupInvitation::new(self.1, target_actor, self.0))
+            .get_result(conn)
+            .map_err(From::from)
+    }
+
+    pub fn join_requests(
+        &self,
+        conn: &PgConnection,
+    ) -> Result<Vec<GroupJoinRequest>, GroupAdminError> {
+        use schema::group_join_requests;
+        use diesel::prelude::*;
+
+        group_join_requests::table
+            .filter(group_join_requests::dsl::group_id.eq(self.1.id()))
+            .order(group_join_requests::dsl::created_at.asc())
+            .load(conn)
+            .map_err(From::from)
+    }
+
+    pub fn accept_join_request(
+        &self,
+        join_request: GroupJoinRequest,
+        conn: &PgConnection,
+    ) -> Result<GroupActor, GroupAdminError> {
+        use schema::group_actors;
+        use diesel::prelude::*;
+
+        if join_request.group_id() != self.1.id() {
+            return Err(GroupAdminError::IdMismatch);
+        }
+
+        conn.transaction(|| {
+            diesel::delete(&join_request)
+                .execute(conn)
+                .and_then(|_| {
+                    diesel::insert_into(group_actors::table)
+                        .values(&NewGroupActor::from(join_request))
+                        .get_result(conn)
+                })
+                .map_err(From::from)
+        })
+    }
+
+    pub fn reject_join_request(
+        &self,
+        join_request: GroupJoinRequest,
+        conn: &PgConnection,
+    ) -> Result<(), GroupAdminError> {
+        use diesel::prelude::*;
+
+        if join_request.group_id() != self.1.id() {
+            return Err(GroupAdminError::IdMismatch);
+        }
+
+        diesel::delete(&join_request)
+            .execute(conn)
+            .map(|_| ())
+            .map_err(From::from)
+    }
+
+    /// Remove a member from the group. Only members ranked below the administrator can be
+    /// removed.
+    pub fn remove_member(
+        &self,
+        member: GroupActor,
+        conn: &PgConnection,
+    ) -> Result<(), GroupAdminError> {
+        use diesel::prelude::*;
+
+        self.check_target(&member)?;
+
+        diesel::delete(&member)
+            .execute(conn)
+            .map(|_| ())
+            .map_err(From::from)
+    }
+
+    /// Ban an actor from the group, removing their membership and any pending invitations or
+    /// requests to join.
+    pub fn ban(
+        &self,
+        target_actor: &BaseActor,
+        reason: Option<String>,
+        conn: &PgConnection,
+    ) -> Result<GroupBan, GroupAdminError> {
+        use schema::{group_actors, group_bans, group_invitations, group_join_requests};
+        use diesel::prelude::*;
+
+        if let Some(membership) = self.1.membership(target_actor, conn)? {
+            self.check_target(&membership)?;
+        }
+
+        conn.transaction(|| {
+            diesel::delete(
+                group_actors::table
+                    .filter(group_actors::dsl::group_id.eq(self.1.id()))
+                    .filter(group_actors::dsl::base_actor_id.eq(target_actor.id())),
+            ).execute(conn)?;
+
+            diesel::delete(
+                group_invitations::table
+                    .filter(group_invitations::dsl::group_id.eq(self.1.id()))
+                    .filter(group_invitations::dsl::invited_actor.eq(target_actor.id())),
+            ).execute(conn)?;
+
+            diesel::delete(
+                group_join_requests::table
+                    .filter(group_join_requests::dsl::group_id.eq(self.1.id()))
+                    .filter(group_join_requests::dsl::base_actor_id.eq(target_actor.id())),
+            ).execute(conn)?;
+
+            diesel::insert_into(group_bans::table)
+                .values(&NewGroupBan::new(self.1, target_actor, self.0, reason))
+                .get_result(conn)
+                .map_err(From::from)
+        })
+    }
+
+    pub fn unban(&self, ban: GroupBan, conn: &PgConnection) -> Result<(), GroupAdminError> {
+        use diesel::prelude::*;
+
+        if ban.group_id() != self.1.id() {
+            return Err(GroupAdminError::IdMismatch);
+        }
+
+        diesel::delete(&ban)
+            .execute(conn)
+            .map(|_| ())
+            .map_err(From::from)
+    }
+
+    /// Change a member's role. Only owners can change roles, and they can't change the roles of
+    /// other owners.
+    pub fn set_role(
+        &self,
+        member: GroupActor,
+        role: GroupRole,
+        conn: &PgConnection,
+    ) -> Result<GroupActor, GroupAdminError> {
+        use schema::group_actors;
+        use diesel::prelude::*;
+
+        if self.2 != GroupRole::Owner {
+            return Err(GroupAdminError::Rank);
+        }
+
+        self.check_target(&member)?;
+
+        diesel::update(&member)
+            .set(group_actors::dsl::role.eq(role))
+            .get_result(conn)
+            .map_err(From::from)
+    }
+
+    /// Change how actors become members of the group. Only owners can change the join policy.
+    pub fn set_join_policy(
+        &self,
+        join_policy: GroupJoinPolicy,
+        conn: &PgConnection,
+    ) -> Result<Group, GroupAdminError> {
+        use schema::groups;
+        use diesel::prelude::*;
+
+        if self.2 != GroupRole::Owner {
+            return Err(GroupAdminError::Rank);
+        }
+
+        diesel::update(self.1)
+            .set(groups::dsl::join_policy.eq(join_policy))
+            .get_result(conn)
+            .map_err(From::from)
+    }
+
+    fn check_target(&self, member: &GroupActor) -> Result<(), GroupAdminError> {
+        if member.group_id() != self.1.id() {
+            return Err(GroupAdminError::IdMismatch);
+        }
+
+        if !self.2.outranks(member.role()) {
+            return Err(GroupAdminError::Rank);
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Fail)]
+pub enum GroupAdminError {
+    #[fail(display = "Error administering group")]
+    Diesel(#[cause] diesel::result::Error),
+    #[fail(display = "Cannot administer other groups")]
+    IdMismatch,
+    #[fail(display = "Not ranked highly enough to perform this action")]
+    Rank,
+    #[fail(display = "Actor is banned from this group")]
+    Banned,
+}
+
+impl From<diesel::result::Error> for GroupAdminError {
+    fn from(e: diesel::result::Error) -> Self {
+        GroupAdminError::Diesel(e)
     }
 }
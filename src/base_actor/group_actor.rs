@@ -1,12 +1,20 @@
+use chrono::DateTime;
+use chrono::offset::Utc;
+
 use base_actor::BaseActor;
 use base_actor::group::Group;
+use base_actor::group_join_request::GroupJoinRequest;
 use schema::group_actors;
+use sql_types::GroupRole;
 
-#[derive(Debug, Queryable)]
+#[derive(Debug, Identifiable, Queryable)]
+#[table_name = "group_actors"]
 pub struct GroupActor {
     id: i32,
     group_id: i32,      // foreign key to Group
     base_actor_id: i32, // foriegn key to BaseActor
+    role: GroupRole,
+    created_at: DateTime<Utc>,
 }
 
 impl GroupActor {
@@ -21,6 +29,14 @@ impl GroupActor {
     pub fn base_actor_id(&self) -> i32 {
         self.base_actor_id
     }
+
+    pub fn role(&self) -> GroupRole {
+        self.role
+    }
+
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
 }
 
 #[derive(Debug, Insertable)]
@@ -28,13 +44,28 @@ impl GroupActor {
 pub struct NewGroupActor {
     group_id: i32,
     base_actor_id: i32,
+    role: GroupRole,
+    created_at: DateTime<Utc>,
 }
 
 impl NewGroupActor {
-    pub fn new(group: &Group, base_actor: &BaseActor) -> Self {
+    pub fn new(group: &Group, base_actor: &BaseActor, role: GroupRole) -> Self {
         NewGroupActor {
             group_id: group.id(),
             base_actor_id: base_actor.id(),
+            role,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+impl From<GroupJoinRequest> for NewGroupActor {
+    fn from(join_request: GroupJoinRequest) -> Self {
+        NewGroupActor {
+            group_id: join_request.group_id(),
+            base_actor_id: join_request.base_actor_id(),
+            role: GroupRole::Member,
+            created_at: Utc::now(),
         }
     }
 }
@@ -0,0 +1,71 @@
+use chrono::DateTime;
+use chrono::offset::Utc;
+
+use base_actor::BaseActor;
+use base_actor::group::Group;
+use schema::group_bans;
+
+#[derive(Debug, Identifiable, Queryable)]
+#[table_name = "group_bans"]
+pub struct GroupBan {
+    id: i32,
+    group_id: i32,          // foreign key to Group
+    base_actor_id: i32,     // foreign key to BaseActor
+    banned_by: Option<i32>, // foreign key to BaseActor
+    reason: Option<String>,
+    created_at: DateTime<Utc>,
+}
+
+impl GroupBan {
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+
+    pub fn group_id(&self) -> i32 {
+        self.group_id
+    }
+
+    pub fn base_actor_id(&self) -> i32 {
+        self.base_actor_id
+    }
+
+    /// The moderator who issued this ban, if their actor still exists.
+    pub fn banned_by(&self) -> Option<i32> {
+        self.banned_by
+    }
+
+    pub fn reason(&self) -> Option<&str> {
+        self.reason.as_ref().map(|s| s.as_ref())
+    }
+
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "group_bans"]
+pub struct NewGroupBan {
+    group_id: i32,
+    base_actor_id: i32,
+    banned_by: Option<i32>,
+    reason: Option<String>,
+    created_at: DateTime<Utc>,
+}
+
+impl NewGroupBan {
+    pub fn new(
+        group: &Group,
+        base_actor: &BaseActor,
+        banned_by: &BaseActor,
+        reason: Option<String>,
+    ) -> Self {
+        NewGroupBan {
+            group_id: group.id(),
+            base_actor_id: base_actor.id(),
+            banned_by: Some(banned_by.id()),
+            reason,
+            created_at: Utc::now(),
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use chrono::DateTime;
+use chrono::offset::Utc;
+
+use base_actor::BaseActor;
+use base_actor::group::Group;
+use schema::group_invitations;
+
+#[derive(Debug, Identifiable, Queryable)]
+#[table_name = "group_invitations"]
+pub struct GroupInvitation {
+    id: i32,
+    group_id: i32,      // foreign key to Group
+    invited_actor: i32, // foreign key to BaseActor
+    invited_by: i32,    // foreign key to BaseActor
+    created_at: DateTime<Utc>,
+}
+
+impl GroupInvitation {
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+
+    pub fn group_id(&self) -> i32 {
+        self.group_id
+    }
+
+    pub fn invited_actor(&self) -> i32 {
+        self.invited_actor
+    }
+
+    pub fn invited_by(&self) -> i32 {
+        self.invited_by
+    }
+
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "group_invitations"]
+pub struct NewGroupInvitation {
+    group_id: i32,
+    invited_actor: i32,
+    invited_by: i32,
+    created_at: DateTime<Utc>,
+}
+
+impl NewGroupInvitation {
+    pub fn new(group: &Group, invited_actor: &BaseActor, invited_by: &BaseActor) -> Self {
+        NewGroupInvitation {
+            group_id: group.id(),
+            invited_actor: invited_actor.id(),
+            invited_by: invited_by.id(),
+            created_at: Utc::now(),
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use chrono::DateTime;
+use chrono::offset::Utc;
+
+use base_actor::BaseActor;
+use base_actor::group::Group;
+use schema::group_join_requests;
+
+#[derive(Debug, Identifiable, Queryable)]
+#[table_name = "group_join_requests"]
+pub struct GroupJoinRequest {
+    id: i32,
+    group_id: i32,      // foreign key to Group
+    base_actor_id: i32, // foreign key to BaseActor
+    created_at: DateTime<Utc>,
+}
+
+impl GroupJoinRequest {
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+
+    pub fn group_id(&self) -> i32 {
+        self.group_id
+    }
+
+    pub fn base_actor_id(&self) -> i32 {
+        self.base_actor_id
+    }
+
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "group_join_requests"]
+pub struct NewGroupJoinRequest {
+    group_id: i32,
+    base_actor_id: i32,
+    created_at: DateTime<Utc>,
+}
+
+impl NewGroupJoinRequest {
+    pub fn new(group: &Group, base_actor: &BaseActor) -> Self {
+        NewGroupJoinRequest {
+            group_id: group.id(),
+            base_actor_id: base_actor.id(),
+            created_at: Utc::now(),
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use chrono::DateTime;
+use chrono::offset::Utc;
+use diesel;
+use diesel::pg::PgConnection;
+
+use base_actor::BaseActor;
+use base_actor::list_member::NewListMember;
+use schema::lists;
+
+/// A named set of actors, used to address posts to and to read posts from.
+#[derive(Debug, Identifiable, Queryable)]
+#[table_name = "lists"]
+pub struct List {
+    id: i32,
+    owner: i32,   // foreign key to BaseActor
+    name: String, // max_length: 256
+    created_at: DateTime<Utc>,
+}
+
+impl List {
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+
+    pub fn owner(&self) -> i32 {
+        self.owner
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+
+    /// Fetch the actors on this list.
+    pub fn member_actors(
+        &self,
+        conn: &PgConnection,
+    ) -> Result<Vec<BaseActor>, diesel::result::Error> {
+        use schema::{base_actors, list_members};
+        use diesel::prelude::*;
+
+        base_actors::table
+            .inner_join(list_members::table)
+            .filter(list_members::dsl::list_id.eq(self.id))
+            .select(base_actors::all_columns)
+            .load(conn)
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "lists"]
+pub struct NewList {
+    owner: i32,
+    name: String,
+    created_at: DateTime<Utc>,
+}
+
+impl NewList {
+    pub fn new(owner: &BaseActor, name: String) -> Self {
+        NewList {
+            owner: owner.id(),
+            name,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+pub struct ListManager<'a>(&'a BaseActor);
+
+impl<'a> ListManager<'a> {
+    pub(crate) fn new(base_actor: &BaseActor) -> ListManager {
+        ListManager(base_actor)
+    }
+
+    pub fn lists(&self, conn: &PgConnection) -> Result<Vec<List>, ListError> {
+        use schema::lists;
+        use diesel::prelude::*;
+
+        lists::table
+            .filter(lists::dsl::owner.eq(self.0.id()))
+            .order(lists::dsl::name.asc())
+            .load(conn)
+            .map_err(From::from)
+    }
+
+    pub fn create_list(&self, name: String, conn: &PgConnection) -> Result<List, ListError> {
+        use schema::lists;
+        use diesel::prelude::*;
+
+        diesel::insert_into(lists::table)
+            .values(&NewList::new(self.0, name))
+            .get_result(conn)
+            .map_err(From::from)
+    }
+
+    pub fn rename_list(
+        &self,
+        list: List,
+        name: String,
+        conn: &PgConnection,
+    ) -> Result<List, ListError> {
+        use schema::lists;
+        use diesel::prelude::*;
+
+        self.check_owner(&list)?;
+
+        diesel::update(&list)
+            .set(lists::dsl::name.eq(name))
+            .get_result(conn)
+            .map_err(From::from)
+    }
+
+    pub fn delete_list(&self, list: List, conn: &PgConnection) -> Result<(), ListError> {
+        use diesel::prelude::*;
+
+        self.check_owner(&list)?;
+
+        diesel::delete(&list)
+            .execute(conn)
+            .map(|_| ())
+            .map_err(From::from)
+    }
+
+    /// Add an actor to a list. Adding an actor who is already on the list does nothing.
+    pub fn add_to_list(
+        &self,
+        list: &List,
+        base_actor: &BaseActor,
+        conn: &PgConnection,
+    ) -> Result<(), ListError> {
+        use schema::list_members;
+        use diesel::prelude::*;
+
+        self.check_owner(list)?;
+
+        diesel::insert_into(list_members::table)
+            .values(&NewListMember::new(list, base_actor))
+            .on_conflict_do_nothing()
+            .execute(conn)
+            .map(|_| ())
+            .map_err(From::from)
+    }
+
+    pub fn remove_from_list(
+        &self,
+        list: &List,
+        base_actor: &BaseActor,
+        conn: &PgConnection,
+    ) -> Result<(), ListError> {
+        use schema::list_members;
+        use diesel::prelude::*;
+
+        self.check_owner(list)?;
+
+        diesel::delete(
+            list_members::table
+                .filter(list_members::dsl::list_id.eq(list.id()))
+                .filter(list_members::dsl::base_actor_id.eq(base_actor.id())),
+        ).execute(conn)
+            .map(|_| ())
+            .map_err(From::from)
+    }
+
+    fn check_owner(&self, list: &List) -> Result<(), ListError> {
+        if list.owner() != self.0.id() {
+            return Err(ListError::IdMismatch);
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Fail)]
+pub enum ListError {
+    #[fail(display = "Error managing list")]
+    Diesel(#[cause] diesel::result::Error),
+    #[fail(display = "Cannot use other actor's lists")]
+    IdMismatch,
+}
+
+impl From<diesel::result::Error> for ListError {
+    fn from(e: diesel::result::Error) -> Self {
+        ListError::Diesel(e)
+    }
+}
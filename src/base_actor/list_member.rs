@@ -0,0 +1,51 @@
+use chrono::DateTime;
+use chrono::offset::Utc;
+
+use base_actor::BaseActor;
+use base_actor::list::List;
+use schema::list_members;
+
+#[derive(Debug, Identifiable, Queryable)]
+#[table_name = "list_members"]
+pub struct ListMember {
+    id: i32,
+    list_id: i32,       // foreign key to List
+    base_actor_id: i32, // foreign key to BaseActor
+    created_at: DateTime<Utc>,
+}
+
+impl ListMember {
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+
+    pub fn list_id(&self) -> i32 {
+        self.list_id
+    }
+
+    pub fn base_actor_id(&self) -> i32 {
+        self.base_actor_id
+    }
+
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "list_members"]
+pub struct NewListMember {
+    list_id: i32,
+    base_actor_id: i32,
+    created_at: DateTime<Utc>,
+}
+
+impl NewListMember {
+    pub fn new(list: &List, base_actor: &BaseActor) -> Self {
+        NewListMember {
+            list_id: list.id(),
+            base_actor_id: base_actor.id(),
+            created_at: Utc::now(),
+        }
+    }
+}
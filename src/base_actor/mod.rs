@@ -1,18 +1,32 @@
+use chrono::offset::Utc;
 use diesel;
+use diesel::dsl::sql;
 use diesel::pg::PgConnection;
+use diesel::sql_types::Bool;
 use serde_json::Value;
 
-use sql_types::{FollowPolicy, Url};
+use sql_types::{FollowPolicy, Permission, Url};
 
+pub mod block;
+pub mod delegation;
 pub mod follow_request;
 pub mod follower;
 pub mod group;
 pub mod group_actor;
+pub mod group_ban;
+pub mod group_invitation;
+pub mod group_join_request;
+pub mod list;
+pub mod list_member;
+pub mod mute;
 pub mod persona;
+pub mod restriction;
 
+use domain_block::{check_ingest, domain_of, DomainBlockError};
 use schema::base_actors;
 use self::follower::Follower;
 use user::UserLike;
+use user::ban::not_banned_sql;
 
 #[derive(Debug, AsChangeset)]
 #[table_name = "base_actors"]
@@ -66,6 +80,7 @@ pub struct BaseActor {
     local_user: Option<i32>,     // foreign key to User
     follow_policy: FollowPolicy, // max_length: 8
     original_json: Value,        // original json
+    domain: Option<String>,      // max_length: 256
 }
 
 impl BaseActor {
@@ -112,6 +127,133 @@ impl BaseActor {
             })
     }
 
+    /// Whether the given user may post as this actor, either as its owner or as a delegate.
+    ///
+    /// Delegates can't post as actors belonging to banned users.
+    pub fn is_delegated_to(
+        &self,
+        user_id: i32,
+        conn: &PgConnection,
+    ) -> Result<bool, diesel::result::Error> {
+        use schema::actor_delegations;
+        use diesel::prelude::*;
+
+        if self.local_user == Some(user_id) {
+            return Ok(true);
+        }
+
+        actor_delegations::table
+            .inner_join(base_actors::table)
+            .filter(actor_delegations::dsl::base_actor.eq(self.id))
+            .filter(sql::<Bool>(&not_banned_sql("base_actors.id")))
+            .filter(actor_delegations::dsl::user_id.eq(user_id))
+            .count()
+            .get_result(conn)
+            .map(|count: i64| count > 0)
+    }
+
+    /// Whether a permission has been taken away from this actor.
+    pub fn is_restricted(
+        &self,
+        permission: Permission,
+        conn: &PgConnection,
+    ) -> Result<bool, diesel::result::Error> {
+        use schema::{actor_restrictions, permissions};
+        use diesel::prelude::*;
+
+        actor_restrictions::table
+            .inner_join(permissions::table)
+            .filter(actor_restrictions::dsl::base_actor.eq(self.id))
+            .filter(permissions::dsl::name.eq(permission))
+            .count()
+            .get_result(conn)
+            .map(|count: i64| count > 0)
+    }
+
+    /// Fetch the local actors a user may post as without owning them.
+    ///
+    /// Actors belonging to banned users are left out.
+    pub fn delegated_to<U: UserLike>(
+        user: &U,
+        conn: &PgConnection,
+    ) -> Result<Vec<BaseActor>, diesel::result::Error> {
+        use schema::actor_delegations;
+        use diesel::prelude::*;
+
+        base_actors::table
+            .inner_join(actor_delegations::table)
+            .filter(actor_delegations::dsl::user_id.eq(user.id()))
+            .filter(sql::<Bool>(&not_banned_sql("base_actors.id")))
+            .select(base_actors::all_columns)
+            .order(base_actors::dsl::display_name.asc())
+            .load(conn)
+    }
+
+    /// Whether either this actor or the given actor has blocked the other.
+    pub fn is_blocked_with_id(
+        &self,
+        other: i32,
+        conn: &PgConnection,
+    ) -> Result<bool, diesel::result::Error> {
+        use schema::blocks;
+        use diesel::prelude::*;
+
+        blocks::table
+            .filter(
+                blocks::dsl::blocker
+                    .eq(self.id)
+                    .and(blocks::dsl::blocked.eq(other))
+                    .or(blocks::dsl::blocker
+                        .eq(other)
+                        .and(blocks::dsl::blocked.eq(self.id))),
+            )
+            .count()
+            .get_result(conn)
+            .map(|count: i64| count > 0)
+    }
+
+    /// The ids of actors whose posts should be hidden from this actor.
+    ///
+    /// This includes actors this actor has blocked, actors who have blocked this actor, and
+    /// actors this actor has muted, as long as the mute hasn't expired.
+    pub fn hidden_actor_ids(
+        &self,
+        conn: &PgConnection,
+    ) -> Result<Vec<i32>, diesel::result::Error> {
+        use schema::{blocks, mutes, timers};
+        use diesel::prelude::*;
+
+        let mut ids: Vec<i32> = blocks::table
+            .filter(blocks::dsl::blocker.eq(self.id))
+            .select(blocks::dsl::blocked)
+            .load(conn)?;
+
+        ids.extend(
+            blocks::table
+                .filter(blocks::dsl::blocked.eq(self.id))
+                .select(blocks::dsl::blocker)
+                .load::<i32>(conn)?,
+        );
+
+        ids.extend(
+            mutes::table
+                .left_outer_join(timers::table)
+                .filter(mutes::dsl::muter.eq(self.id))
+                .filter(
+                    mutes::dsl::timer_id
+                        .is_null()
+                        .or(timers::dsl::fire_time.gt(Utc::now())),
+                )
+                .select(mutes::dsl::muted)
+                .load::<i32>(conn)?,
+        );
+
+        ids.sort();
+        ids.dedup();
+
+        Ok(ids)
+    }
+
     pub fn display_name(&self) -> &str {
         &self.display_name
     }
@@ -139,6 +281,11 @@ impl BaseActor {
     pub fn original_json(&self) -> &Value {
         &self.original_json
     }
+
+    /// The domain of the instance this actor lives on, or `None` for local actors.
+    pub fn domain(&self) -> Option<&str> {
+        self.domain.as_ref().map(|s| s.as_ref())
+    }
 }
 
 #[derive(Insertable)]
@@ -150,6 +297,7 @@ pub struct NewBaseActor {
     local_user: Option<i32>,
     follow_policy: FollowPolicy,
     original_json: Value,
+    domain: Option<String>,
 }
 
 impl NewBaseActor {
@@ -161,6 +309,11 @@ impl NewBaseActor {
         follow_policy: FollowPolicy,
         original_json: Value,
     ) -> Self {
+        let domain = match local_user {
+            Some(_) => None,
+            None => domain_of(&profile_url),
+        };
+
         NewBaseActor {
             display_name,
             profile_url,
@@ -168,6 +321,19 @@ impl NewBaseActor {
             local_user: local_user.map(|lu| lu.id()),
             follow_policy,
             original_json,
+            domain,
         }
     }
+
+    /// Store a remote actor, unless the instance it lives on is suspended.
+    pub fn ingest(self, conn: &PgConnection) -> Result<BaseActor, DomainBlockError> {
+        use diesel::prelude::*;
+
+        check_ingest(&self.profile_url, conn)?;
+
+        diesel::insert_into(base_actors::table)
+            .values(&self)
+            .get_result(conn)
+            .map_err(From::from)
+    }
 }
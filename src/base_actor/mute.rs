@@ -0,0 +1,134 @@
+use chrono::DateTime;
+use chrono::offset::Utc;
+use diesel;
+use diesel::pg::PgConnection;
+
+use base_actor::BaseActor;
+use schema::mutes;
+use timer::Timer;
+
+/// A mute hides an actor's posts from the muter without the muted actor knowing.
+///
+/// Mutes with a timer stop applying once the timer's fire time has passed.
+#[derive(Debug, Identifiable, Queryable)]
+#[table_name = "mutes"]
+pub struct Mute {
+    id: i32,
+    muter: i32,            // foreign key to BaseActor
+    muted: i32,            // foreign key to BaseActor
+    timer_id: Option<i32>, // foreign key to Timer
+    created_at: DateTime<Utc>,
+}
+
+impl Mute {
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+
+    pub fn muter(&self) -> i32 {
+        self.muter
+    }
+
+    pub fn muted(&self) -> i32 {
+        self.muted
+    }
+
+    pub fn timer_id(&self) -> Option<i32> {
+        self.timer_id
+    }
+
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "mutes"]
+pub struct NewMute {
+    muter: i32,
+    muted: i32,
+    timer_id: Option<i32>,
+    created_at: DateTime<Utc>,
+}
+
+impl NewMute {
+    pub fn new(muter: &BaseActor, muted: &BaseActor, until: Option<&Timer>) -> Self {
+        NewMute {
+            muter: muter.id(),
+            muted: muted.id(),
+            timer_id: until.map(|timer| timer.id()),
+            created_at: Utc::now(),
+        }
+    }
+}
+
+pub struct Muter<'a>(&'a BaseActor);
+
+impl<'a> Muter<'a> {
+    pub(crate) fn new(base_actor: &BaseActor) -> Muter {
+        Muter(base_actor)
+    }
+
+    pub fn mutes(&self, conn: &PgConnection) -> Result<Vec<Mute>, diesel::result::Error> {
+        use schema::mutes;
+        use diesel::prelude::*;
+
+        mutes::table
+            .filter(mutes::dsl::muter.eq(self.0.id()))
+            .order(mutes::dsl::created_at.desc())
+            .load(conn)
+    }
+
+    /// Mute an actor, hiding their posts from timelines and conversations.
+    ///
+    /// If `until` is given, the mute stops applying once the timer's fire time has passed.
+    /// Muting an actor who is already muted replaces the existing mute.
+    pub fn mute(
+        &self,
+        target_actor: &BaseActor,
+        until: Option<&Timer>,
+        conn: &PgConnection,
+    ) -> Result<Mute, diesel::result::Error> {
+        use schema::mutes;
+        use diesel::prelude::*;
+
+        conn.transaction(|| {
+            diesel::delete(
+                mutes::table
+                    .filter(mutes::dsl::muter.eq(self.0.id()))
+                    .filter(mutes::dsl::muted.eq(target_actor.id())),
+            ).execute(conn)?;
+
+            diesel::insert_into(mutes::table)
+                .values(&NewMute::new(self.0, target_actor, until))
+                .get_result(conn)
+        })
+    }
+
+    pub fn unmute(&self, mute: Mute, conn: &PgConnection) -> Result<(), MuteError> {
+        use diesel::prelude::*;
+
+        if mute.muter() != self.0.id() {
+            return Err(MuteError::IdMismatch);
+        }
+
+        diesel::delete(&mute)
+            .execute(conn)
+            .map(|_| ())
+            .map_err(From::from)
+    }
+}
+
+#[derive(Debug, Fail)]
+pub enum MuteError {
+    #[fail(display = "Error managing mute")]
+    Diesel(#[cause] diesel::result::Error),
+    #[fail(display = "Cannot manage other actor's mutes")]
+    IdMismatch,
+}
+
+impl From<diesel::result::Error> for MuteError {
+    fn from(e: diesel::result::Error) -> Self {
+        MuteError::Diesel(e)
+    }
+}
@@ -1,7 +1,7 @@
 use base_actor::BaseActor;
 use file::image::Image;
 use schema::personas;
-use sql_types::PostVisibility;
+use sql_types::{Lang, PostVisibility};
 
 #[derive(Debug, Identifiable, Queryable)]
 #[table_name = "personas"]
@@ -12,6 +12,8 @@ pub struct Persona {
     avatar: Option<i32>, // foreign key to Image
     shortname: String,   // wtf is a SlugField
     base_actor: i32,     // foreign key to BaseActor
+    preferred_languages: Vec<Lang>,
+    default_sensitive: bool,
 }
 
 impl Persona {
@@ -38,6 +40,18 @@ impl Persona {
     pub fn base_actor(&self) -> i32 {
         self.base_actor
     }
+
+    /// The languages this persona would like to read posts in.
+    ///
+    /// An empty list means posts in every language are shown.
+    pub fn preferred_languages(&self) -> &[Lang] {
+        &self.preferred_languages
+    }
+
+    /// Whether this persona's posts should be marked as sensitive unless they say otherwise.
+    pub fn default_sensitive(&self) -> bool {
+        self.default_sensitive
+    }
 }
 
 #[derive(Insertable)]
@@ -48,6 +62,8 @@ pub struct NewPersona {
     avatar: Option<i32>,
     shortname: String,
     base_actor: i32,
+    preferred_languages: Vec<Lang>,
+    default_sensitive: bool,
 }
 
 impl NewPersona {
@@ -57,6 +73,8 @@ impl NewPersona {
         avatar: Option<&Image>,
         shortname: String,
         base_actor: &BaseActor,
+        preferred_languages: Vec<Lang>,
+        default_sensitive: bool,
     ) -> Self {
         NewPersona {
             default_visibility,
@@ -64,6 +82,8 @@ impl NewPersona {
             avatar: avatar.map(|a| a.id()),
             shortname,
             base_actor: base_actor.id(),
+            preferred_languages,
+            default_sensitive,
         }
     }
 }
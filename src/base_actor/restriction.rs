@@ -0,0 +1,179 @@
+use chrono::DateTime;
+use chrono::offset::Utc;
+use diesel;
+use diesel::pg::PgConnection;
+use serde_json::Value;
+
+use audit_log::AuditLogEntry;
+use base_actor::BaseActor;
+use schema::actor_restrictions;
+use sql_types::{AuditAction, Permission as PermissionSql};
+use user::role::permission::Permission;
+
+/// Takes a permission away from an actor, whoever is acting as it.
+#[derive(Debug, Identifiable, Queryable)]
+#[table_name = "actor_restrictions"]
+pub struct ActorRestriction {
+    id: i32,
+    base_actor: i32,            // foreign key to BaseActor
+    permission_id: i32,         // foreign key to Permission
+    restricted_by: Option<i32>, // foreign key to User
+    reason: Option<String>,
+    created_at: DateTime<Utc>,
+}
+
+impl ActorRestriction {
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+
+    pub fn base_actor(&self) -> i32 {
+        self.base_actor
+    }
+
+    pub fn permission_id(&self) -> i32 {
+        self.permission_id
+    }
+
+    /// The moderator who added this restriction, if they still exist.
+    pub fn restricted_by(&self) -> Option<i32> {
+        self.restricted_by
+    }
+
+    pub fn reason(&self) -> Option<&str> {
+        self.reason.as_ref().map(|s| s.as_ref())
+    }
+
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+
+    /// The state of this restriction, as recorded in the audit log.
+    pub(crate) fn audit_json(&self, permission: &Permission) -> Value {
+        json!({
+            "base_actor": self.base_actor,
+            "permission": permission.name(),
+            "reason": self.reason,
+        })
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "actor_restrictions"]
+pub struct NewActorRestriction {
+    base_actor: i32,
+    permission_id: i32,
+    restricted_by: Option<i32>,
+    reason: Option<String>,
+    created_at: DateTime<Utc>,
+}
+
+impl NewActorRestriction {
+    pub fn new(
+        base_actor: &BaseActor,
+        permission: &Permission,
+        restricted_by: i32,
+        reason: Option<String>,
+    ) -> Self {
+        NewActorRestriction {
+            base_actor: base_actor.id(),
+            permission_id: permission.id(),
+            restricted_by: Some(restricted_by),
+            reason,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// Takes permissions away from individual actors, such as keeping one persona from posting media.
+pub struct ActorRestricter(i32);
+
+impl ActorRestricter {
+    pub(crate) fn new(moderator: i32) -> ActorRestricter {
+        ActorRestricter(moderator)
+    }
+
+    pub fn restrictions(
+        &self,
+        base_actor: &BaseActor,
+        conn: &PgConnection,
+    ) -> Result<Vec<ActorRestriction>, diesel::result::Error> {
+        use schema::actor_restrictions;
+        use diesel::prelude::*;
+
+        actor_restrictions::table
+            .filter(actor_restrictions::dsl::base_actor.eq(base_actor.id()))
+            .order(actor_restrictions::dsl::created_at.asc())
+            .load(conn)
+    }
+
+    pub fn restrict(
+        &self,
+        base_actor: &BaseActor,
+        permission: PermissionSql,
+        reason: Option<String>,
+        conn: &PgConnection,
+    ) -> Result<ActorRestriction, diesel::result::Error> {
+        use schema::actor_restrictions;
+        use diesel::prelude::*;
+
+        conn.transaction(|| {
+            let permission = Permission::builtin_permission(permission, conn)?;
+
+            let restriction: ActorRestriction = diesel::insert_into(actor_restrictions::table)
+                .values(&NewActorRestriction::new(
+                    base_actor,
+                    &permission,
+                    self.0,
+                    reason.clone(),
+                ))
+                .on_conflict((
+                    actor_restrictions::dsl::base_actor,
+                    actor_restrictions::dsl::permission_id,
+                ))
+                .do_update()
+                .set((
+                    actor_restrictions::dsl::restricted_by.eq(self.0),
+                    actor_restrictions::dsl::reason.eq(reason),
+                ))
+                .get_result(conn)?;
+
+            AuditLogEntry::record(
+                Some(self.0),
+                AuditAction::RestrictActor,
+                Some(restriction.id()),
+                None,
+                Some(restriction.audit_json(&permission)),
+                conn,
+            )?;
+
+            Ok(restriction)
+        })
+    }
+
+    pub fn unrestrict(
+        &self,
+        restriction: ActorRestriction,
+        conn: &PgConnection,
+    ) -> Result<(), diesel::result::Error> {
+        use schema::permissions;
+        use diesel::prelude::*;
+
+        conn.transaction(|| {
+            let permission: Permission = permissions::table
+                .find(restriction.permission_id())
+                .get_result(conn)?;
+
+            diesel::delete(&restriction).execute(conn)?;
+
+            AuditLogEntry::record(
+                Some(self.0),
+                AuditAction::UnrestrictActor,
+                Some(restriction.id()),
+                Some(restriction.audit_json(&permission)),
+                None,
+                conn,
+            )
+        })
+    }
+}
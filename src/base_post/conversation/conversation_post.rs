@@ -0,0 +1,51 @@
+use chrono::DateTime;
+use chrono::offset::Utc;
+
+use base_post::BasePost;
+use base_post::conversation::Conversation;
+use schema::conversation_posts;
+
+#[derive(Debug, Identifiable, Queryable)]
+#[table_name = "conversation_posts"]
+pub struct ConversationPost {
+    id: i32,
+    conversation_id: i32, // foreign key to Conversation
+    base_post_id: i32,    // foreign key to BasePost
+    created_at: DateTime<Utc>,
+}
+
+impl ConversationPost {
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+
+    pub fn conversation_id(&self) -> i32 {
+        self.conversation_id
+    }
+
+    pub fn base_post_id(&self) -> i32 {
+        self.base_post_id
+    }
+
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "conversation_posts"]
+pub struct NewConversationPost {
+    conversation_id: i32,
+    base_post_id: i32,
+    created_at: DateTime<Utc>,
+}
+
+impl NewConversationPost {
+    pub fn new(conversation: &Conversation, base_post: &BasePost) -> Self {
+        NewConversationPost {
+            conversation_id: conversation.id(),
+            base_post_id: base_post.id(),
+            created_at: Utc::now(),
+        }
+    }
+}
@@ -0,0 +1,443 @@
+use std::collections::HashMap;
+
+use chrono::DateTime;
+use chrono::offset::Utc;
+use diesel;
+use diesel::dsl::sql;
+use diesel::pg::PgConnection;
+use diesel::sql_types::{Array, BigInt, Bool, Integer};
+
+use base_actor::BaseActor;
+use base_post::BasePost;
+use base_post::post::Post;
+use schema::conversations;
+use self::conversation_post::NewConversationPost;
+use self::participant::{ConversationParticipant, NewConversationParticipant};
+use user::ban::not_banned_sql;
+
+pub mod conversation_post;
+pub mod participant;
+
+/// A thread of direct posts between a fixed set of actors.
+#[derive(Debug, Identifiable, Queryable)]
+#[table_name = "conversations"]
+pub struct Conversation {
+    id: i32,
+    created_at: DateTime<Utc>,
+    last_post_at: DateTime<Utc>,
+}
+
+impl Conversation {
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+
+    pub fn last_post_at(&self) -> DateTime<Utc> {
+        self.last_post_at
+    }
+
+    /// Fetch the conversation a post belongs to, if it was a direct post.
+    pub fn for_post(
+        base_post: &BasePost,
+        conn: &PgConnection,
+    ) -> Result<Option<Conversation>, diesel::result::Error> {
+        use schema::conversation_posts;
+        use diesel::prelude::*;
+
+        conversations::table
+            .inner_join(conversation_posts::table)
+            .filter(conversation_posts::dsl::base_post_id.eq(base_post.id()))
+            .select(conversations::all_columns)
+            .get_result(conn)
+            .optional()
+    }
+
+    /// Fetch the actors taking part in this conversation, including any who have left it.
+    pub fn participants(
+        &self,
+        conn: &PgConnection,
+    ) -> Result<Vec<BaseActor>, diesel::result::Error> {
+        use schema::{base_actors, conversation_participants};
+        use diesel::prelude::*;
+
+        base_actors::table
+            .inner_join(conversation_participants::table)
+            .filter(conversation_participants::dsl::conversation_id.eq(self.id))
+            .select(base_actors::all_columns)
+            .load(conn)
+    }
+
+    /// Fetch the given actor's membership in this conversation.
+    pub fn participant(
+        &self,
+        base_actor: &BaseActor,
+        conn: &PgConnection,
+    ) -> Result<Option<ConversationParticipant>, diesel::result::Error> {
+        use schema::conversation_participants;
+        use diesel::prelude::*;
+
+        conversation_participants::table
+            .filter(conversation_participants::dsl::conversation_id.eq(self.id))
+            .filter(conversation_participants::dsl::base_actor_id.eq(base_actor.id()))
+            .get_result(conn)
+            .optional()
+    }
+
+    /// Fetch a page of this conversation's posts, from newest to oldest.
+    pub fn posts(
+        &self,
+        limit: i64,
+        offset: i64,
+        conn: &PgConnection,
+    ) -> Result<Vec<(BasePost, Post)>, diesel::result::Error> {
+        use schema::{base_posts, conversation_posts, posts};
+        use diesel::prelude::*;
+
+        base_posts::table
+            .inner_join(posts::table)
+            .inner_join(conversation_posts::table)
+            .filter(conversation_posts::dsl::conversation_id.eq(self.id))
+            .order(conversation_posts::dsl::created_at.desc())
+            .select((base_posts::all_columns, posts::all_columns))
+            .limit(limit)
+            .offset(offset)
+            .load(conn)
+    }
+
+    /// Record a direct post in a conversation.
+    ///
+    /// If `in_reply_to` belongs to a conversation that the sender takes part in, or that they
+    /// were sent `in_reply_to` in, the post joins it. Otherwise a new conversation is started, so
+    /// replying to a post can't be used to join someone else's conversation.
+    ///
+    /// The sender and recipients are added as participants, and anyone who had left the
+    /// conversation gets it back in their inbox. The sender's own post counts as read.
+    pub(crate) fn record(
+        base_post: &BasePost,
+        sender: &BaseActor,
+        recipients: &[BaseActor],
+        in_reply_to: Option<&BasePost>,
+        conn: &PgConnection,
+    ) -> Result<Conversation, diesel::result::Error> {
+        use schema::{conversation_participants, conversation_posts, direct_posts};
+        use diesel::prelude::*;
+
+        conn.transaction(|| {
+            let now = Utc::now();
+
+            let existing = match in_reply_to {
+                Some(parent) => match Conversation::for_post(parent, conn)? {
+                    Some(conversation) => {
+                        let is_participant = conversation.participant(sender, conn)?.is_some();
+
+                        let is_addressee = direct_posts::table
+                            .filter(direct_posts::dsl::base_post_id.eq(parent.id()))
+                            .filter(direct_posts::dsl::base_actor_id.eq(sender.id()))
+                            .count()
+                            .get_result(conn)
+                            .map(|count: i64| count > 0)?;
+
+                        if is_participant || is_addressee {
+                            Some(conversation)
+                        } else {
+                            None
+                        }
+                    }
+                    None => None,
+                },
+                None => None,
+            };
+
+            let conversation: Conversation = match existing {
+                Some(conversation) => diesel::update(&conversation)
+                    .set(conversations::dsl::last_post_at.eq(now))
+                    .get_result(conn)?,
+                None => diesel::insert_into(conversations::table)
+                    .values(&NewConversation::new(now))
+                    .get_result(conn)?,
+            };
+
+            diesel::insert_into(conversation_posts::table)
+                .values(&NewConversationPost::new(&conversation, base_post))
+                .execute(conn)?;
+
+            let participants = Some(sender)
+                .into_iter()
+                .chain(recipients.iter())
+                .map(|actor| NewConversationParticipant::new(&conversation, actor))
+                .collect::<Vec<_>>();
+
+            diesel::insert_into(conversation_participants::table)
+                .values(&participants)
+                .on_conflict_do_nothing()
+                .execute(conn)?;
+
+            let ids = Some(sender)
+                .into_iter()
+                .chain(recipients.iter())
+                .map(|actor| actor.id())
+                .collect::<Vec<_>>();
+
+            diesel::update(
+                conversation_participants::table
+                    .filter(conversation_participants::dsl::conversation_id.eq(conversation.id))
+                    .filter(conversation_participants::dsl::base_actor_id.eq_any(ids)),
+            ).set(conversation_participants::dsl::left_conversation.eq(false))
+                .execute(conn)?;
+
+            diesel::update(
+                conversation_participants::table
+                    .filter(conversation_participants::dsl::conversation_id.eq(conversation.id))
+                    .filter(conversation_participants::dsl::base_actor_id.eq(sender.id())),
+            ).set(conversation_participants::dsl::last_read_at.eq(now))
+                .execute(conn)?;
+
+            Ok(conversation)
+        })
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "conversations"]
+pub struct NewConversation {
+    created_at: DateTime<Utc>,
+    last_post_at: DateTime<Utc>,
+}
+
+impl NewConversation {
+    pub fn new(started_at: DateTime<Utc>) -> Self {
+        NewConversation {
+            created_at: started_at,
+            last_post_at: started_at,
+        }
+    }
+}
+
+#[derive(QueryableByName)]
+struct UnreadCount {
+    #[sql_type = "Integer"]
+    conversation_id: i32,
+    #[sql_type = "BigInt"]
+    unread_count: i64,
+}
+
+/// A conversation as it appears in a participant's inbox.
+#[derive(Debug)]
+pub struct ConversationSummary {
+    pub conversation: Conversation,
+    /// The other actors in the conversation.
+    pub participants: Vec<BaseActor>,
+    /// The most recent post in the conversation.
+    pub last_post: Option<(BasePost, Post)>,
+    /// The number of posts from other participants since the viewer last read the conversation.
+    /// This is always zero for muted conversations.
+    pub unread_count: i64,
+    pub muted: bool,
+}
+
+/// Fetch a page of the conversations the viewer is taking part in, most recently active first.
+///
+/// Conversations the viewer has left are not included. Banned users aren't listed as
+/// participants, and their posts aren't shown as the last post. Neither they nor posts by actors
+/// the viewer has blocked, been blocked by, or muted count towards the unread count.
+///
+/// The participants, last posts, and unread counts for the whole page are each fetched with a
+/// single query.
+pub fn inbox(
+    viewer: &BaseActor,
+    limit: i64,
+    offset: i64,
+    conn: &PgConnection,
+) -> Result<Vec<ConversationSummary>, diesel::result::Error> {
+    use schema::{base_actors, base_posts, conversation_participants, conversation_posts, posts};
+    use diesel::prelude::*;
+
+    let conversations: Vec<(Conversation, ConversationParticipant)> = conversations::table
+        .inner_join(conversation_participants::table)
+        .filter(conversation_participants::dsl::base_actor_id.eq(viewer.id()))
+        .filter(conversation_participants::dsl::left_conversation.eq(false))
+        .order(conversations::dsl::last_post_at.desc())
+        .select((
+            conversations::all_columns,
+            conversation_participants::all_columns,
+        ))
+        .limit(limit)
+        .offset(offset)
+        .load(conn)?;
+
+    if conversations.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let ids = conversations
+        .iter()
+        .map(|(conversation, _)| conversation.id)
+        .collect::<Vec<_>>();
+
+    let mut participants: HashMap<i32, Vec<BaseActor>> = HashMap::new();
+
+    let others: Vec<(i32, BaseActor)> = base_actors::table
+        .inner_join(conversation_participants::table)
+        .filter(conversation_participants::dsl::conversation_id.eq_any(&ids))
+        .filter(base_actors::dsl::id.ne(viewer.id()))
+        .filter(sql::<Bool>(&not_banned_sql("base_actors.id")))
+        .select((
+            conversation_participants::dsl::conversation_id,
+            base_actors::all_columns,
+        ))
+        .load(conn)?;
+
+    for (conversation_id, base_actor) in others {
+        participants
+            .entry(conversation_id)
+            .or_default()
+            .push(base_actor);
+    }
+
+    let mut last_posts: HashMap<i32, (BasePost, Post)> = base_posts::table
+        .inner_join(posts::table)
+        .inner_join(conversation_posts::table)
+        .filter(conversation_posts::dsl::conversation_id.eq_any(&ids))
+        .filter(sql::<Bool>(&format!(
+            "conversation_posts.id = (SELECT latest.id FROM conversation_posts AS latest \
+             INNER JOIN base_posts AS latest_posts ON latest_posts.id = latest.base_post_id \
+             WHERE latest.conversation_id = conversation_posts.conversation_id AND {} \
+             ORDER BY latest.created_at DESC, latest.id DESC LIMIT 1)",
+            not_banned_sql("latest_posts.posted_by")
+        )))
+        .select((
+            conversation_posts::dsl::conversation_id,
+            (base_posts::all_columns, posts::all_columns),
+        ))
+        .load::<(i32, (BasePost, Post))>(conn)?
+        .into_iter()
+        .collect();
+
+    let unread_counts: HashMap<i32, i64> = diesel::sql_query(format!(
+        "SELECT conversation_posts.conversation_id AS conversation_id, \
+         COUNT(*) AS unread_count FROM conversation_posts \
+         INNER JOIN base_posts ON base_posts.id = conversation_posts.base_post_id \
+         INNER JOIN conversation_participants \
+         ON conversation_participants.conversation_id = conversation_posts.conversation_id \
+         AND conversation_participants.base_actor_id = $1 \
+         WHERE conversation_posts.conversation_id = ANY($2) \
+         AND NOT conversation_participants.muted \
+         AND base_posts.posted_by <> $1 AND NOT (base_posts.posted_by = ANY($3)) \
+         AND (conversation_participants.last_read_at IS NULL \
+         OR conversation_posts.created_at > conversation_participants.last_read_at) \
+         AND {} GROUP BY conversation_posts.conversation_id",
+        not_banned_sql("base_posts.posted_by")
+    )).bind::<Integer, _>(viewer.id())
+        .bind::<Array<Integer>, _>(&ids)
+        .bind::<Array<Integer>, _>(viewer.hidden_actor_ids(conn)?)
+        .load::<UnreadCount>(conn)?
+        .into_iter()
+        .map(|count| (count.conversation_id, count.unread_count))
+        .collect();
+
+    Ok(conversations
+        .into_iter()
+        .map(|(conversation, participant)| ConversationSummary {
+            participants: participants.remove(&conversation.id).unwrap_or_default(),
+            last_post: last_posts.remove(&conversation.id),
+            unread_count: unread_counts.get(&conversation.id).cloned().unwrap_or(0),
+            muted: participant.muted(),
+            conversation,
+        })
+        .collect())
+}
+
+pub struct ConversationManager<'a>(&'a BaseActor);
+
+impl<'a> ConversationManager<'a> {
+    pub(crate) fn new(base_actor: &BaseActor) -> ConversationManager {
+        ConversationManager(base_actor)
+    }
+
+    /// Mark every post in a conversation as read.
+    pub fn mark_read(
+        &self,
+        conversation: &Conversation,
+        conn: &PgConnection,
+    ) -> Result<(), ConversationError> {
+        use schema::conversation_participants;
+        use diesel::prelude::*;
+
+        self.update(conversation, conn, |participant| {
+            diesel::update(participant)
+                .set(conversation_participants::dsl::last_read_at.eq(Utc::now()))
+                .execute(conn)
+        })
+    }
+
+    /// Mute or unmute a conversation. Muted conversations stay in the inbox, but never have
+    /// unread posts.
+    pub fn set_muted(
+        &self,
+        conversation: &Conversation,
+        muted: bool,
+        conn: &PgConnection,
+    ) -> Result<(), ConversationError> {
+        use schema::conversation_participants;
+        use diesel::prelude::*;
+
+        self.update(conversation, conn, |participant| {
+            diesel::update(participant)
+                .set(conversation_participants::dsl::muted.eq(muted))
+                .execute(conn)
+        })
+    }
+
+    /// Remove a conversation from the inbox until someone posts in it again.
+    pub fn leave(
+        &self,
+        conversation: &Conversation,
+        conn: &PgConnection,
+    ) -> Result<(), ConversationError> {
+        use schema::conversation_participants;
+        use diesel::prelude::*;
+
+        self.update(conversation, conn, |participant| {
+            diesel::update(participant)
+                .set((
+                    conversation_participants::dsl::left_conversation.eq(true),
+                    conversation_participants::dsl::last_read_at.eq(Utc::now()),
+                ))
+                .execute(conn)
+        })
+    }
+
+    fn update<F>(
+        &self,
+        conversation: &Conversation,
+        conn: &PgConnection,
+        f: F,
+    ) -> Result<(), ConversationError>
+    where
+        F: FnOnce(&ConversationParticipant) -> Result<usize, diesel::result::Error>,
+    {
+        let participant = conversation
+            .participant(self.0, conn)?
+            .ok_or(ConversationError::IdMismatch)?;
+
+        f(&participant).map(|_| ()).map_err(From::from)
+    }
+}
+
+#[derive(Debug, Fail)]
+pub enum ConversationError {
+    #[fail(display = "Error managing conversation")]
+    Diesel(#[cause] diesel::result::Error),
+    #[fail(display = "Actor is not part of this conversation")]
+    IdMismatch,
+}
+
+impl From<diesel::result::Error> for ConversationError {
+    fn from(e: diesel::result::Error) -> Self {
+        ConversationError::Diesel(e)
+    }
+}
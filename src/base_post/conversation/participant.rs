@@ -0,0 +1,66 @@
+use chrono::DateTime;
+use chrono::offset::Utc;
+
+use base_actor::BaseActor;
+use base_post::conversation::Conversation;
+use schema::conversation_participants;
+
+#[derive(Debug, Identifiable, Queryable)]
+#[table_name = "conversation_participants"]
+pub struct ConversationParticipant {
+    id: i32,
+    conversation_id: i32, // foreign key to Conversation
+    base_actor_id: i32,   // foreign key to BaseActor
+    last_read_at: Option<DateTime<Utc>>,
+    muted: bool,
+    left_conversation: bool,
+}
+
+impl ConversationParticipant {
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+
+    pub fn conversation_id(&self) -> i32 {
+        self.conversation_id
+    }
+
+    pub fn base_actor_id(&self) -> i32 {
+        self.base_actor_id
+    }
+
+    /// When this participant last read the conversation, if they ever have.
+    pub fn last_read_at(&self) -> Option<DateTime<Utc>> {
+        self.last_read_at
+    }
+
+    /// Whether new posts in the conversation should be left out of this participant's unread
+    /// counts.
+    pub fn muted(&self) -> bool {
+        self.muted
+    }
+
+    /// Whether this participant has removed the conversation from their inbox. It comes back when
+    /// someone posts in it again.
+    pub fn left_conversation(&self) -> bool {
+        self.left_conversation
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "conversation_participants"]
+pub struct NewConversationParticipant {
+    conversation_id: i32,
+    base_actor_id: i32,
+    last_read_at: Option<DateTime<Utc>>,
+}
+
+impl NewConversationParticipant {
+    pub fn new(conversation: &Conversation, base_actor: &BaseActor) -> Self {
+        NewConversationParticipant {
+            conversation_id: conversation.id(),
+            base_actor_id: base_actor.id(),
+            last_read_at: None,
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use chrono::DateTime;
+use chrono::offset::Utc;
+use diesel;
+use diesel::pg::PgConnection;
+use serde_json::Value;
+
+use base_actor::BaseActor;
+use base_actor::group::Group;
+use base_post::BasePost;
+use base_post::post::Post;
+use base_post::post::render::LinkResolver;
+use file::image::Image;
+use schema::group_posts;
+use sql_types::{Lang, Mime, PostVisibility, SourceFormat};
+use user::PostMaker;
+
+#[derive(Debug, Identifiable, Queryable)]
+#[table_name = "group_posts"]
+pub struct GroupPost {
+    id: i32,
+    group_id: i32,     // foreign key to Group
+    base_post_id: i32, // foreign key to BasePost
+    created_at: DateTime<Utc>,
+}
+
+impl GroupPost {
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+
+    pub fn group_id(&self) -> i32 {
+        self.group_id
+    }
+
+    pub fn base_post_id(&self) -> i32 {
+        self.base_post_id
+    }
+
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "group_posts"]
+pub struct NewGroupPost {
+    group_id: i32,
+    base_post_id: i32,
+    created_at: DateTime<Utc>,
+}
+
+impl NewGroupPost {
+    pub fn new(group: &Group, post: &BasePost) -> Self {
+        NewGroupPost {
+            group_id: group.id(),
+            base_post_id: post.id(),
+            created_at: Utc::now(),
+        }
+    }
+}
+
+pub struct GroupPostMaker<'a>(&'a BaseActor, &'a Group);
+
+impl<'a> GroupPostMaker<'a> {
+    pub(crate) fn new(base_actor: &'a BaseActor, group: &'a Group) -> GroupPostMaker<'a> {
+        GroupPostMaker(base_actor, group)
+    }
+
+    /// Create a post in the group, to be announced to the group's members.
+    ///
+    /// Group posts must be `PostVisibility::Public` or `PostVisibility::GroupOnly`. Group-only
+    /// posts can only be read by the group's members.
+    pub fn make_group_post<R: LinkResolver>(
+        &self,
+        name: Option<String>,
+        summary: Option<String>,
+        sensitive: Option<bool>,
+        media_type: Mime,
+        icon: Option<&Image>,
+        visibility: PostVisibility,
+        original_json: Value,
+        source: String,
+        source_format: SourceFormat,
+        language: Lang,
+        resolver: &R,
+        conn: &PgConnection,
+    ) -> Result<(BasePost, Post, GroupPost), GroupPostError> {
+        use schema::group_posts;
+        use diesel::prelude::*;
+
+        match visibility {
+            PostVisibility::Public | PostVisibility::GroupOnly => (),
+            _ => return Err(GroupPostError::Visibility(visibility)),
+        }
+
+        conn.transaction(|| {
+            PostMaker::new(self.0)
+                .make_post(
+                    name,
+                    summary,
+                    sensitive,
+                    media_type,
+                    icon,
+                    visibility,
+                    original_json,
+                    source,
+                    source_format,
+                    language,
+                    resolver,
+                    conn,
+                )
+                .and_then(|(base_post, post)| {
+                    diesel::insert_into(group_posts::table)
+                        .values(&NewGroupPost::new(self.1, &base_post))
+                        .get_result(conn)
+                        .map(|group_post: GroupPost| (base_post, post, group_post))
+                })
+        }).map_err(From::from)
+    }
+}
+
+#[derive(Debug, Fail)]
+pub enum GroupPostError {
+    #[fail(display = "Error creating group post")]
+    Diesel(#[cause] diesel::result::Error),
+    #[fail(display = "Group posts can't have visibility {}", _0)]
+    Visibility(PostVisibility),
+}
+
+impl From<diesel::result::Error> for GroupPostError {
+    fn from(e: diesel::result::Error) -> Self {
+        GroupPostError::Diesel(e)
+    }
+}
@@ -2,11 +2,15 @@ use diesel;
 use diesel::pg::PgConnection;
 use serde_json::Value;
 
+pub mod conversation;
 pub mod direct_post;
+pub mod group_post;
 pub mod post;
 pub mod reaction;
+pub mod timeline;
 
 use base_actor::BaseActor;
+use domain_block::{check_domain_ingest, DomainBlockError};
 use file::image::Image;
 use schema::base_posts;
 use self::direct_post::DirectPost;
@@ -21,6 +25,8 @@ pub struct BasePost {
     icon: Option<i32>,    // foreign key to Image
     visibility: PostVisibility,
     original_json: Value, // original json
+    summary: Option<String>,
+    sensitive: bool,
 }
 
 impl BasePost {
@@ -52,14 +58,42 @@ impl BasePost {
         &self.original_json
     }
 
+    /// The content warning shown in place of this post's content until a reader chooses to
+    /// view it.
+    pub fn summary(&self) -> Option<&str> {
+        self.summary.as_ref().map(|s| s.as_ref())
+    }
+
+    /// Whether this post's media should be hidden until a reader chooses to view it.
+    pub fn sensitive(&self) -> bool {
+        self.sensitive
+    }
+
+    /// Whether the given actor was addressed by this post.
+    ///
+    /// For group posts, this means being a member of one of the groups the post was made in.
+    /// Otherwise, it means the post was sent directly to the actor.
     pub fn is_viewable_by(
         &self,
         base_actor: &BaseActor,
         conn: &PgConnection,
     ) -> Result<bool, diesel::result::Error> {
-        use schema::direct_posts;
+        use schema::{direct_posts, group_actors, group_posts};
         use diesel::prelude::*;
 
+        if self.visibility == PostVisibility::GroupOnly {
+            return group_posts::table
+                .inner_join(
+                    group_actors::table
+                        .on(group_actors::dsl::group_id.eq(group_posts::dsl::group_id)),
+                )
+                .filter(group_posts::dsl::base_post_id.eq(self.id))
+                .filter(group_actors::dsl::base_actor_id.eq(base_actor.id()))
+                .count()
+                .get_result(conn)
+                .map(|count: i64| count > 0);
+        }
+
         direct_posts::table
             .filter(direct_posts::dsl::base_post_id.eq(self.id))
             .filter(direct_posts::dsl::base_actor_id.eq(base_actor.id()))
@@ -81,11 +115,15 @@ pub struct NewBasePost {
     icon: Option<i32>,
     visibility: PostVisibility,
     original_json: Value,
+    summary: Option<String>,
+    sensitive: bool,
 }
 
 impl NewBasePost {
     pub fn new(
         name: Option<String>,
+        summary: Option<String>,
+        sensitive: bool,
         media_type: Mime,
         posted_by: &BaseActor,
         icon: Option<&Image>,
@@ -99,6 +137,29 @@ impl NewBasePost {
             icon: icon.map(|i| i.id()),
             visibility,
             original_json,
+            summary,
+            sensitive,
+        }
+    }
+
+    /// Store a post from a remote actor, unless the instance the actor lives on, or any parent
+    /// domain of it, is suspended.
+    pub fn ingest(self, conn: &PgConnection) -> Result<BasePost, DomainBlockError> {
+        use schema::base_actors;
+        use diesel::prelude::*;
+
+        let domain: Option<String> = base_actors::table
+            .find(self.posted_by)
+            .select(base_actors::dsl::domain)
+            .get_result(conn)?;
+
+        if let Some(domain) = domain {
+            check_domain_ingest(&domain, conn)?;
         }
+
+        diesel::insert_into(base_posts::table)
+            .values(&self)
+            .get_result(conn)
+            .map_err(From::from)
     }
 }
@@ -1,6 +1,10 @@
+use diesel;
+use diesel::pg::PgConnection;
+
 use file::File;
 use super::Post;
 use schema::media_posts;
+use sql_types::AttachmentType;
 
 #[derive(Debug, Identifiable, Queryable)]
 #[table_name = "media_posts"]
@@ -8,6 +12,12 @@ pub struct MediaPost {
     id: i32,
     file_id: i32, // foreign key to File
     post_id: i32, // foreign key to Post
+    position: i32,
+    attachment_type: AttachmentType,
+    description: Option<String>,
+    focal_x: Option<f32>,
+    focal_y: Option<f32>,
+    blurhash: Option<String>, // max_length: 128
 }
 
 impl MediaPost {
@@ -22,6 +32,77 @@ impl MediaPost {
     pub fn post_id(&self) -> i32 {
         self.post_id
     }
+
+    /// Where this attachment appears in its post's list of attachments, starting at zero.
+    pub fn position(&self) -> i32 {
+        self.position
+    }
+
+    pub fn attachment_type(&self) -> AttachmentType {
+        self.attachment_type
+    }
+
+    /// The alt text describing this attachment.
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_ref().map(|s| s.as_ref())
+    }
+
+    /// The point to keep in view when this attachment is cropped, with each coordinate between
+    /// -1.0 and 1.0.
+    pub fn focus(&self) -> Option<(f32, f32)> {
+        match (self.focal_x, self.focal_y) {
+            (Some(x), Some(y)) => Some((x, y)),
+            _ => None,
+        }
+    }
+
+    pub fn blurhash(&self) -> Option<&str> {
+        self.blurhash.as_ref().map(|s| s.as_ref())
+    }
+
+    /// Fetch a post's attachments, in order.
+    pub fn for_post(post: &Post, conn: &PgConnection) -> Result<Vec<Self>, diesel::result::Error> {
+        use diesel::prelude::*;
+
+        media_posts::table
+            .filter(media_posts::dsl::post_id.eq(post.id()))
+            .order(media_posts::dsl::position.asc())
+            .load(conn)
+    }
+}
+
+/// The details of a single attachment to be added to a post.
+pub struct MediaAttachment<'a> {
+    file: &'a File,
+    attachment_type: AttachmentType,
+    description: Option<String>,
+    focus: Option<(f32, f32)>,
+    blurhash: Option<String>,
+}
+
+impl<'a> MediaAttachment<'a> {
+    /// Create a `MediaAttachment`.
+    ///
+    /// Focal point coordinates are clamped between -1.0 and 1.0.
+    pub fn new(
+        file: &'a File,
+        attachment_type: AttachmentType,
+        description: Option<String>,
+        focus: Option<(f32, f32)>,
+        blurhash: Option<String>,
+    ) -> Self {
+        MediaAttachment {
+            file,
+            attachment_type,
+            description,
+            focus: focus.map(|(x, y)| (x.max(-1.0).min(1.0), y.max(-1.0).min(1.0))),
+            blurhash,
+        }
+    }
+
+    pub fn file(&self) -> &File {
+        self.file
+    }
 }
 
 #[derive(Insertable)]
@@ -29,13 +110,25 @@ impl MediaPost {
 pub struct NewMediaPost {
     file_id: i32,
     post_id: i32,
+    position: i32,
+    attachment_type: AttachmentType,
+    description: Option<String>,
+    focal_x: Option<f32>,
+    focal_y: Option<f32>,
+    blurhash: Option<String>,
 }
 
 impl NewMediaPost {
-    pub fn new(file: &File, post: &Post) -> Self {
+    pub fn new(attachment: &MediaAttachment, position: i32, post: &Post) -> Self {
         NewMediaPost {
-            file_id: file.id(),
+            file_id: attachment.file.id(),
             post_id: post.id(),
+            position,
+            attachment_type: attachment.attachment_type,
+            description: attachment.description.clone(),
+            focal_x: attachment.focus.map(|(x, _)| x),
+            focal_y: attachment.focus.map(|(_, y)| y),
+            blurhash: attachment.blurhash.clone(),
         }
     }
 }
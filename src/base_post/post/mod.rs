@@ -1,8 +1,11 @@
 use base_post::BasePost;
 use schema::posts;
+use self::render::{render, sanitize, LinkResolver};
+use sql_types::{Lang, SourceFormat};
 
 pub mod comment;
 pub mod media_post;
+pub mod render;
 
 #[derive(Debug, Queryable)]
 pub struct Post {
@@ -10,6 +13,8 @@ pub struct Post {
     content: String,
     source: Option<String>,
     base_post: i32, // foreign key to BasePost
+    language: Lang,
+    source_format: Option<SourceFormat>,
 }
 
 impl Post {
@@ -28,6 +33,14 @@ impl Post {
     pub fn base_post(&self) -> i32 {
         self.base_post
     }
+
+    pub fn language(&self) -> &Lang {
+        &self.language
+    }
+
+    pub fn source_format(&self) -> Option<SourceFormat> {
+        self.source_format
+    }
 }
 
 #[derive(Insertable)]
@@ -36,14 +49,46 @@ pub struct NewPost {
     content: String,
     source: Option<String>,
     base_post: i32,
+    language: Lang,
+    source_format: Option<SourceFormat>,
 }
 
 impl NewPost {
-    pub fn new(content: String, source: Option<String>, base_post: &BasePost) -> Self {
+    /// Create a `NewPost` from existing HTML content, such as content received from a remote
+    /// server.
+    ///
+    /// The content is sanitized before it is stored.
+    pub fn new(
+        content: String,
+        source: Option<String>,
+        language: Lang,
+        base_post: &BasePost,
+    ) -> Self {
         NewPost {
-            content,
+            content: sanitize(&content),
             source,
             base_post: base_post.id(),
+            language,
+            source_format: None,
+        }
+    }
+
+    /// Create a `NewPost` by rendering the given source into HTML content.
+    ///
+    /// Both the source and the rendered content are stored.
+    pub fn from_source<R: LinkResolver>(
+        source: String,
+        source_format: SourceFormat,
+        language: Lang,
+        base_post: &BasePost,
+        resolver: &R,
+    ) -> Self {
+        NewPost {
+            content: render(&source, source_format, resolver),
+            source: Some(source),
+            base_post: base_post.id(),
+            language,
+            source_format: Some(source_format),
         }
     }
 }
@@ -0,0 +1,310 @@
+use std::collections::{HashMap, HashSet};
+
+use ammonia::Builder;
+use pulldown_cmark::{html, Event, Parser, Tag};
+use regex::{Captures, Regex};
+
+use sql_types::SourceFormat;
+
+lazy_static! {
+    static ref TOKEN: Regex = Regex::new(
+        r"(?P<url>https?://[^\s<>]+)|\B@(?P<user>[A-Za-z0-9_]+)(?:@(?P<domain>[A-Za-z0-9.-]+\.[A-Za-z]{2,}))?|\B#(?P<tag>\w+)"
+    ).unwrap();
+}
+
+/// Characters that are very likely to be punctuation following a URL, rather than part of it.
+const URL_TRAILING_PUNCTUATION: &[char] = &['.', ',', ':', ';', '!', '?', ')', '\'', '"'];
+
+/// Resolves the targets of mentions and hashtags found while rendering a post.
+pub trait LinkResolver {
+    /// Produce the profile URL for a mentioned actor.
+    ///
+    /// `domain` is `None` for mentions of the form `@user`. If `None` is returned, the mention is
+    /// left as plain text.
+    fn mention(&self, username: &str, domain: Option<&str>) -> Option<String>;
+
+    /// Produce the URL of the page listing posts with the given hashtag.
+    fn hashtag(&self, tag: &str) -> String;
+}
+
+/// Render a post's source into sanitized HTML content.
+///
+/// URLs, mentions, and hashtags found in the text are turned into links. Links and code that the
+/// author wrote themselves are left untouched.
+pub fn render<R: LinkResolver>(source: &str, format: SourceFormat, resolver: &R) -> String {
+    let html = match format {
+        SourceFormat::Markdown => render_markdown(source, resolver),
+        SourceFormat::PlainText => render_plain_text(source, resolver),
+    };
+
+    sanitize(&html)
+}
+
+/// Sanitize HTML, removing any tags, attributes, and URL schemes not on the allowlist.
+///
+/// This must be used on any HTML content that arrives from a remote server before it is stored.
+pub fn sanitize(html: &str) -> String {
+    let tags: HashSet<&str> = [
+        "a",
+        "blockquote",
+        "br",
+        "code",
+        "del",
+        "em",
+        "li",
+        "ol",
+        "p",
+        "pre",
+        "span",
+        "strong",
+        "ul",
+    ].iter()
+        .cloned()
+        .collect();
+
+    let mut tag_attributes = HashMap::new();
+    tag_attributes.insert("a", ["href", "class"].iter().cloned().collect());
+    tag_attributes.insert("span", ["class"].iter().cloned().collect());
+
+    let url_schemes = ["http", "https", "mailto"].iter().cloned().collect();
+
+    Builder::default()
+        .tags(tags)
+        .tag_attributes(tag_attributes)
+        .generic_attributes(HashSet::new())
+        .url_schemes(url_schemes)
+        .link_rel(Some("nofollow noopener noreferrer"))
+        .clean(html)
+        .to_string()
+}
+
+fn render_markdown<R: LinkResolver>(source: &str, resolver: &R) -> String {
+    // Depth of links and code spans we're inside of, where text must not be auto-linked
+    let mut verbatim_depth = 0;
+
+    let events = Parser::new(source).map(|event| match event {
+        Event::Start(tag) => {
+            match tag {
+                Tag::Link(_, _) | Tag::Image(_, _) | Tag::Code | Tag::CodeBlock(_) => {
+                    verbatim_depth += 1;
+                }
+                _ => (),
+            }
+
+            Event::Start(tag)
+        }
+        Event::End(tag) => {
+            match tag {
+                Tag::Link(_, _) | Tag::Image(_, _) | Tag::Code | Tag::CodeBlock(_) => {
+                    verbatim_depth -= 1;
+                }
+                _ => (),
+            }
+
+            Event::End(tag)
+        }
+        Event::Text(ref text) if verbatim_depth == 0 => {
+            Event::InlineHtml(linkify(text, resolver).into())
+        }
+        event => event,
+    });
+
+    let mut html_output = String::new();
+    html::push_html(&mut html_output, events);
+    html_output
+}
+
+fn render_plain_text<R: LinkResolver>(source: &str, resolver: &R) -> String {
+    source
+        .split("\n\n")
+        .map(|paragraph| paragraph.trim())
+        .filter(|paragraph| !paragraph.is_empty())
+        .map(|paragraph| {
+            let lines = paragraph
+                .lines()
+                .map(|line| linkify(line, resolver))
+                .collect::<Vec<_>>()
+                .join("<br>");
+
+            format!("<p>{}</p>", lines)
+        })
+        .collect::<Vec<_>>()
+        .join("")
+}
+
+/// Escape text, turning any URLs, mentions, and hashtags into links.
+fn linkify<R: LinkResolver>(text: &str, resolver: &R) -> String {
+    let mut output = String::new();
+    let mut last = 0;
+
+    for captures in TOKEN.captures_iter(text) {
+        let whole = captures.get(0).unwrap();
+
+        if let Some((link, end)) = token_link(&captures, resolver) {
+            output.push_str(&escape(&text[last..whole.start()]));
+            output.push_str(&link);
+            last = end;
+        }
+    }
+
+    output.push_str(&escape(&text[last..]));
+    output
+}
+
+/// Produce the link for a matched token, along with the index in the text where the token ends.
+fn token_link<R: LinkResolver>(captures: &Captures, resolver: &R) -> Option<(String, usize)> {
+    if let Some(url) = captures.name("url") {
+        let trimmed = url.as_str().trim_right_matches(URL_TRAILING_PUNCTUATION);
+
+        if trimmed.len() <= "https://".len() {
+            return None;
+        }
+
+        return Some((
+            format!("<a href=\"{}\">{}</a>", escape(trimmed), escape(trimmed)),
+            url.start() + trimmed.len(),
+        ));
+    }
+
+    if let Some(user) = captures.name("user") {
+        let domain = captures.name("domain").map(|domain| domain.as_str());
+        let whole = captures.get(0).unwrap();
+
+        return resolver.mention(user.as_str(), domain).map(|href| {
+            (
+                format!(
+                    "<span class=\"h-card\"><a href=\"{}\" class=\"u-url mention\">{}</a></span>",
+                    escape(&href),
+                    escape(whole.as_str())
+                ),
+                whole.end(),
+            )
+        });
+    }
+
+    captures.name("tag").map(|tag| {
+        let whole = captures.get(0).unwrap();
+
+        (
+            format!(
+                "<a href=\"{}\" class=\"mention hashtag\">{}</a>",
+                escape(&resolver.hashtag(tag.as_str())),
+                escape(whole.as_str())
+            ),
+            whole.end(),
+        )
+    })
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{render, sanitize, LinkResolver};
+    use sql_types::SourceFormat;
+
+    struct TestResolver;
+
+    impl LinkResolver for TestResolver {
+        fn mention(&self, username: &str, domain: Option<&str>) -> Option<String> {
+            match domain {
+                Some(domain) => Some(format!("https://{}/users/{}", domain, username)),
+                None if username == "nobody" => None,
+                None => Some(format!("https://local.example/users/{}", username)),
+            }
+        }
+
+        fn hashtag(&self, tag: &str) -> String {
+            format!("https://local.example/tags/{}", tag)
+        }
+    }
+
+    #[test]
+    fn render_plain_text_paragraphs() {
+        let html = render("one\ntwo\n\nthree", SourceFormat::PlainText, &TestResolver);
+
+        assert_eq!(html, "<p>one<br>two</p><p>three</p>");
+    }
+
+    #[test]
+    fn escape_plain_text_html() {
+        let html = render(
+            "<script>alert('hi')</script>",
+            SourceFormat::PlainText,
+            &TestResolver,
+        );
+
+        assert!(!html.contains("<script>"), "Script tag should have been escaped");
+    }
+
+    #[test]
+    fn link_urls_mentions_and_hashtags() {
+        let html = render(
+            "hey @friend@remote.example, see https://example.com/page. #rust",
+            SourceFormat::PlainText,
+            &TestResolver,
+        );
+
+        assert!(html.contains("href=\"https://remote.example/users/friend\""));
+        assert!(html.contains("href=\"https://example.com/page\""));
+        assert!(html.contains("href=\"https://local.example/tags/rust\""));
+        assert!(html.contains("page</a>."), "Trailing period should not be linked");
+    }
+
+    #[test]
+    fn dont_link_unknown_mentions_or_emails() {
+        let html = render(
+            "@nobody mail me at me@example.com",
+            SourceFormat::PlainText,
+            &TestResolver,
+        );
+
+        assert!(!html.contains("<a"), "Nothing should have been linked");
+    }
+
+    #[test]
+    fn dont_link_markdown_code() {
+        let html = render("`#notatag` #tag", SourceFormat::Markdown, &TestResolver);
+
+        assert!(html.contains("<code>#notatag</code>"));
+        assert!(html.contains("href=\"https://local.example/tags/tag\""));
+    }
+
+    #[test]
+    fn sanitize_markdown_html() {
+        let html = render(
+            "[click](javascript:alert(1)) <img src=x onerror=alert(1)>",
+            SourceFormat::Markdown,
+            &TestResolver,
+        );
+
+        assert!(!html.contains("javascript:"), "Unsafe scheme should be removed");
+        assert!(!html.contains("<img"), "Images should be removed");
+    }
+
+    #[test]
+    fn sanitize_remote_html() {
+        let html = sanitize(
+            "<p onclick=\"steal()\">hi <a href=\"https://example.com\" style=\"x\">there</a></p>",
+        );
+
+        assert!(!html.contains("onclick"), "Event handlers should be removed");
+        assert!(!html.contains("style"), "Styles should be removed");
+        assert!(html.contains("rel=\"nofollow noopener noreferrer\""));
+    }
+}
@@ -0,0 +1,246 @@
+use diesel;
+use diesel::dsl::sql;
+use diesel::pg::PgConnection;
+use diesel::sql_types::{Bool, Text};
+
+use base_actor::BaseActor;
+use base_actor::group::Group;
+use base_actor::list::List;
+use base_post::BasePost;
+use base_post::post::Post;
+use domain_block::not_blocked_sql;
+use sql_types::{DomainBlockSeverity, Lang, PostVisibility};
+use user::ban::not_banned_sql;
+
+/// The primary language subtags to filter a timeline by.
+///
+/// Posts with an undetermined language are always included, since they could be in any language.
+fn primary_languages(languages: &[Lang]) -> Vec<String> {
+    let mut primary_languages: Vec<String> = languages
+        .iter()
+        .filter_map(|lang| lang.primary_language().map(|s| s.to_owned()))
+        .collect();
+
+    if let Some(und) = Lang::undetermined().primary_language() {
+        primary_languages.push(und.to_owned());
+    }
+
+    primary_languages
+}
+
+/// Fetch a page of public posts, newest first.
+///
+/// If `languages` is not empty, only posts whose primary language matches one of the given
+/// languages are returned. Posts by banned users, or by actors on instances that are silenced or
+/// suspended themselves or through a parent domain, are left out. If a `viewer` is given, posts
+/// by actors they have blocked, been blocked by, or muted are left out too.
+pub fn public_timeline(
+    viewer: Option<&BaseActor>,
+    languages: &[Lang],
+    limit: i64,
+    offset: i64,
+    conn: &PgConnection,
+) -> Result<Vec<(BasePost, Post)>, diesel::result::Error> {
+    use schema::{base_posts, posts};
+    use diesel::prelude::*;
+
+    let mut query = base_posts::table
+        .inner_join(posts::table)
+        .filter(base_posts::dsl::visibility.eq(PostVisibility::Public))
+        .filter(sql::<Bool>(&not_blocked_sql(
+            "base_posts.posted_by",
+            &[DomainBlockSeverity::Silence, DomainBlockSeverity::Suspend],
+        )))
+        .filter(sql::<Bool>(&not_banned_sql("base_posts.posted_by")))
+        .into_boxed();
+
+    if let Some(viewer) = viewer {
+        query = query.filter(base_posts::dsl::posted_by.ne_all(viewer.hidden_actor_ids(conn)?));
+    }
+
+    if !languages.is_empty() {
+        query = query.filter(
+            sql::<Text>("split_part(posts.language, '-', 1)")
+                .eq_any(primary_languages(languages)),
+        );
+    }
+
+    query
+        .order(base_posts::dsl::id.desc())
+        .limit(limit)
+        .offset(offset)
+        .load(conn)
+}
+
+/// Fetch a page of posts for the given actor's home timeline, newest first.
+///
+/// This includes the actor's own posts, posts by actors they follow that they are allowed to see,
+/// and posts made in groups they are a member of. Posts by banned users, and by actors the viewer
+/// has blocked, been blocked by, or muted, are left out. If `languages` is not empty, only posts
+/// whose primary language matches one of the given languages are returned.
+pub fn home_timeline(
+    viewer: &BaseActor,
+    languages: &[Lang],
+    limit: i64,
+    offset: i64,
+    conn: &PgConnection,
+) -> Result<Vec<(BasePost, Post)>, diesel::result::Error> {
+    viewable_timeline(viewer, None, languages, limit, offset, conn)
+}
+
+/// Fetch a page of posts by the actors on a list, newest first.
+///
+/// Lists are private, so nothing is returned unless the `viewer` owns the list. Only posts the
+/// viewer is allowed to see are returned.
+pub fn list_timeline(
+    viewer: &BaseActor,
+    list: &List,
+    languages: &[Lang],
+    limit: i64,
+    offset: i64,
+    conn: &PgConnection,
+) -> Result<Vec<(BasePost, Post)>, diesel::result::Error> {
+    if list.owner() != viewer.id() {
+        return Ok(Vec::new());
+    }
+
+    viewable_timeline(viewer, Some(list), languages, limit, offset, conn)
+}
+
+fn viewable_timeline(
+    viewer: &BaseActor,
+    list: Option<&List>,
+    languages: &[Lang],
+    limit: i64,
+    offset: i64,
+    conn: &PgConnection,
+) -> Result<Vec<(BasePost, Post)>, diesel::result::Error> {
+    use schema::{base_posts, direct_posts, followers, group_actors, group_posts, list_members,
+                 posts};
+    use diesel::prelude::*;
+
+    let follows = || {
+        followers::table
+            .filter(followers::dsl::follower.eq(viewer.id()))
+            .select(followers::dsl::follows)
+    };
+
+    let followed_by = followers::table
+        .filter(followers::dsl::follows.eq(viewer.id()))
+        .select(followers::dsl::follower);
+
+    let addressed_to = direct_posts::table
+        .filter(direct_posts::dsl::base_actor_id.eq(viewer.id()))
+        .select(direct_posts::dsl::base_post_id);
+
+    let in_groups = || {
+        let groups = group_actors::table
+            .filter(group_actors::dsl::base_actor_id.eq(viewer.id()))
+            .select(group_actors::dsl::group_id);
+
+        group_posts::table
+            .filter(group_posts::dsl::group_id.eq_any(groups))
+            .select(group_posts::dsl::base_post_id)
+    };
+
+    let mut query = base_posts::table
+        .inner_join(posts::table)
+        .filter(
+            base_posts::dsl::posted_by
+                .eq(viewer.id())
+                .or(base_posts::dsl::posted_by.eq_any(follows()))
+                .or(base_posts::dsl::id.eq_any(in_groups())),
+        )
+        .filter(
+            base_posts::dsl::posted_by
+                .eq(viewer.id())
+                .or(base_posts::dsl::visibility.eq(PostVisibility::Public))
+                .or(base_posts::dsl::visibility
+                    .eq(PostVisibility::FollowersOnly)
+                    .and(base_posts::dsl::posted_by.eq_any(follows())))
+                .or(base_posts::dsl::visibility
+                    .eq(PostVisibility::FriendsOnly)
+                    .and(base_posts::dsl::posted_by.eq_any(follows()))
+                    .and(base_posts::dsl::posted_by.eq_any(followed_by)))
+                .or(base_posts::dsl::visibility
+                    .eq(PostVisibility::ListedPeopleOnly)
+                    .and(base_posts::dsl::id.eq_any(addressed_to)))
+                .or(base_posts::dsl::visibility
+                    .eq(PostVisibility::GroupOnly)
+                    .and(base_posts::dsl::id.eq_any(in_groups()))),
+        )
+        .filter(base_posts::dsl::posted_by.ne_all(viewer.hidden_actor_ids(conn)?))
+        .filter(sql::<Bool>(&not_banned_sql("base_posts.posted_by")))
+        .into_boxed();
+
+    if let Some(list) = list {
+        let on_list = list_members::table
+            .filter(list_members::dsl::list_id.eq(list.id()))
+            .select(list_members::dsl::base_actor_id);
+
+        query = query.filter(base_posts::dsl::posted_by.eq_any(on_list));
+    }
+
+    if !languages.is_empty() {
+        query = query.filter(
+            sql::<Text>("split_part(posts.language, '-', 1)")
+                .eq_any(primary_languages(languages)),
+        );
+    }
+
+    query
+        .order(base_posts::dsl::id.desc())
+        .limit(limit)
+        .offset(offset)
+        .load(conn)
+}
+
+/// Fetch a page of the posts made in a group, newest first.
+///
+/// Group-only posts are only returned when the `viewer` is a member of the group, and posts with
+/// any visibility other than public or group-only are never returned. Posts by banned users, and
+/// by actors the viewer has blocked, been blocked by, or muted, are left out.
+pub fn group_timeline(
+    group: &Group,
+    viewer: Option<&BaseActor>,
+    limit: i64,
+    offset: i64,
+    conn: &PgConnection,
+) -> Result<Vec<(BasePost, Post)>, diesel::result::Error> {
+    use schema::{base_posts, group_posts, posts};
+    use diesel::prelude::*;
+
+    let is_member = match viewer {
+        Some(viewer) => group.membership(viewer, conn)?.is_some(),
+        None => false,
+    };
+
+    let in_group = group_posts::table
+        .filter(group_posts::dsl::group_id.eq(group.id()))
+        .select(group_posts::dsl::base_post_id);
+
+    let mut query = base_posts::table
+        .inner_join(posts::table)
+        .filter(base_posts::dsl::id.eq_any(in_group))
+        .filter(sql::<Bool>(&not_banned_sql("base_posts.posted_by")))
+        .into_boxed();
+
+    if is_member {
+        query = query.filter(
+            base_posts::dsl::visibility
+                .eq_any(vec![PostVisibility::Public, PostVisibility::GroupOnly]),
+        );
+    } else {
+        query = query.filter(base_posts::dsl::visibility.eq(PostVisibility::Public));
+    }
+
+    if let Some(viewer) = viewer {
+        query = query.filter(base_posts::dsl::posted_by.ne_all(viewer.hidden_actor_ids(conn)?));
+    }
+
+    query
+        .order(base_posts::dsl::id.desc())
+        .limit(limit)
+        .offset(offset)
+        .load(conn)
+}
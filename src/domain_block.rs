@@ -0,0 +1,408 @@
+use std::cmp::Reverse;
+
+use chrono::DateTime;
+use chrono::offset::Utc;
+use diesel;
+use diesel::pg::PgConnection;
+use serde_json::Value;
+
+use audit_log::AuditLogEntry;
+use schema::domain_blocks;
+use sql_types::{AuditAction, DomainBlockSeverity, Url};
+
+#[derive(Debug, Fail)]
+pub enum DomainBlockError {
+    #[fail(display = "Error checking domain blocks")]
+    Diesel(#[cause] diesel::result::Error),
+    #[fail(display = "Domain {} is suspended", _0)]
+    Suspended(String),
+    #[fail(display = "Media from domain {} is rejected", _0)]
+    MediaRejected(String),
+}
+
+impl From<diesel::result::Error> for DomainBlockError {
+    fn from(e: diesel::result::Error) -> Self {
+        DomainBlockError::Diesel(e)
+    }
+}
+
+/// A moderation decision about another instance.
+///
+/// A block applies to its domain and every subdomain of it. When a domain and one of its parents
+/// are both blocked, both blocks apply, so a subdomain can't escape a stricter block on its
+/// parent. The `private_reason` is meant for moderators only, and should not be shown alongside
+/// the public list of blocked domains.
+#[derive(Debug, Identifiable, Queryable)]
+#[table_name = "domain_blocks"]
+pub struct DomainBlock {
+    id: i32,
+    domain: String, // max_length: 256
+    severity: DomainBlockSeverity,
+    public_reason: Option<String>,
+    private_reason: Option<String>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl DomainBlock {
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+
+    pub fn domain(&self) -> &str {
+        &self.domain
+    }
+
+    pub fn severity(&self) -> DomainBlockSeverity {
+        self.severity
+    }
+
+    pub fn public_reason(&self) -> Option<&str> {
+        self.public_reason.as_ref().map(|s| s.as_ref())
+    }
+
+    pub fn private_reason(&self) -> Option<&str> {
+        self.private_reason.as_ref().map(|s| s.as_ref())
+    }
+
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+
+    pub fn updated_at(&self) -> DateTime<Utc> {
+        self.updated_at
+    }
+
+    /// The state of this block, as recorded in the audit log.
+    pub(crate) fn audit_json(&self) -> Value {
+        json!({
+            "domain": self.domain,
+            "severity": format!("{}", self.severity),
+            "public_reason": self.public_reason,
+            "private_reason": self.private_reason,
+        })
+    }
+
+    /// Fetch the blocked domains with their public reasons, for showing to anyone.
+    pub fn public_list(
+        conn: &PgConnection,
+    ) -> Result<Vec<(String, DomainBlockSeverity, Option<String>)>, diesel::result::Error> {
+        use diesel::prelude::*;
+
+        domain_blocks::table
+            .select((
+                domain_blocks::dsl::domain,
+                domain_blocks::dsl::severity,
+                domain_blocks::dsl::public_reason,
+            ))
+            .order(domain_blocks::dsl::domain.asc())
+            .load(conn)
+    }
+
+    /// Fetch the strictest block that applies to a domain, if any.
+    ///
+    /// Suspending is stricter than silencing, which is stricter than rejecting media. When a
+    /// domain and one of its parents are blocked with the same severity, the block on the more
+    /// specific domain is returned.
+    pub fn for_domain(
+        domain: &str,
+        conn: &PgConnection,
+    ) -> Result<Option<DomainBlock>, diesel::result::Error> {
+        let mut blocks = DomainBlock::applying_to(domain, conn)?;
+
+        // Sorting is stable, so blocks of the same severity stay most specific first
+        blocks.sort_by_key(|block| Reverse(strictness(block.severity)));
+
+        Ok(blocks.into_iter().next())
+    }
+
+    /// Fetch every block on a domain or one of its parents, from most to least specific.
+    pub fn applying_to(
+        domain: &str,
+        conn: &PgConnection,
+    ) -> Result<Vec<DomainBlock>, diesel::result::Error> {
+        use diesel::prelude::*;
+
+        let candidates = domain_candidates(&normalize_domain(domain));
+
+        let mut blocks: Vec<DomainBlock> = domain_blocks::table
+            .filter(domain_blocks::dsl::domain.eq_any(candidates.clone()))
+            .load(conn)?;
+
+        blocks.sort_by_key(|block| {
+            candidates
+                .iter()
+                .position(|candidate| *candidate == block.domain)
+        });
+
+        Ok(blocks)
+    }
+
+    /// Fetch the block on exactly this domain, ignoring blocks on its parents.
+    pub(crate) fn for_exact_domain(
+        domain: &str,
+        conn: &PgConnection,
+    ) -> Result<Option<DomainBlock>, diesel::result::Error> {
+        use diesel::prelude::*;
+
+        domain_blocks::table
+            .filter(domain_blocks::dsl::domain.eq(normalize_domain(domain)))
+            .get_result(conn)
+            .optional()
+    }
+
+    /// Fetch the block that applies to the host of a URL, if any.
+    pub fn for_url(
+        url: &Url,
+        conn: &PgConnection,
+    ) -> Result<Option<DomainBlock>, diesel::result::Error> {
+        match domain_of(url) {
+            Some(domain) => DomainBlock::for_domain(&domain, conn),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Refuse content from the host of a URL if its domain, or any parent of it, is suspended.
+///
+/// This should be checked before storing any remote actor or post.
+pub fn check_ingest(url: &Url, conn: &PgConnection) -> Result<(), DomainBlockError> {
+    match domain_of(url) {
+        Some(domain) => check_domain_ingest(&domain, conn),
+        None => Ok(()),
+    }
+}
+
+/// Refuse content from a domain if it, or any parent of it, is suspended.
+pub(crate) fn check_domain_ingest(
+    domain: &str,
+    conn: &PgConnection,
+) -> Result<(), DomainBlockError> {
+    let suspended = DomainBlock::applying_to(domain, conn)?
+        .into_iter()
+        .find(|block| block.severity() == DomainBlockSeverity::Suspend);
+
+    match suspended {
+        Some(block) => Err(DomainBlockError::Suspended(block.domain)),
+        None => Ok(()),
+    }
+}
+
+/// Refuse media from the host of a URL if its domain, or any parent of it, is suspended or has
+/// its media rejected.
+pub fn check_media(url: &Url, conn: &PgConnection) -> Result<(), DomainBlockError> {
+    let domain = match domain_of(url) {
+        Some(domain) => domain,
+        None => return Ok(()),
+    };
+
+    let rejected = DomainBlock::applying_to(&domain, conn)?
+        .into_iter()
+        .find(|block| block.severity().rejects_media());
+
+    match rejected {
+        Some(block) => Err(DomainBlockError::MediaRejected(block.domain)),
+        None => Ok(()),
+    }
+}
+
+fn strictness(severity: DomainBlockSeverity) -> u8 {
+    match severity {
+        DomainBlockSeverity::Suspend => 2,
+        DomainBlockSeverity::Silence => 1,
+        DomainBlockSeverity::RejectMedia => 0,
+    }
+}
+
+/// The normalized domain of a URL's host, if it has one.
+pub fn domain_of(url: &Url) -> Option<String> {
+    url.0.host_str().map(normalize_domain)
+}
+
+fn normalize_domain(domain: &str) -> String {
+    domain.trim().trim_right_matches('.').to_lowercase()
+}
+
+/// The domain itself followed by each of its parents, from most to least specific.
+fn domain_candidates(domain: &str) -> Vec<String> {
+    let mut candidates = Vec::new();
+    let mut rest = domain;
+
+    while !rest.is_empty() {
+        candidates.push(rest.to_owned());
+
+        rest = match rest.find('.') {
+            Some(index) => &rest[index + 1..],
+            None => "",
+        };
+    }
+
+    candidates
+}
+
+/// SQL checking that the actor in the given column isn't on an instance blocked with one of the
+/// given severities, either itself or through a parent domain.
+///
+/// This matches domains the same way as `domain_candidates`: a block applies to its domain and to
+/// every domain ending in `.` followed by it.
+pub(crate) fn not_blocked_sql(actor_column: &str, severities: &[DomainBlockSeverity]) -> String {
+    let severities = severities
+        .iter()
+        .map(|severity| format!("'{}'", severity))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    format!(
+        "NOT EXISTS (SELECT 1 FROM base_actors AS blocked_actors INNER JOIN domain_blocks \
+         ON blocked_actors.domain = domain_blocks.domain \
+         OR right(blocked_actors.domain, length(domain_blocks.domain) + 1) \
+         = '.' || domain_blocks.domain \
+         WHERE blocked_actors.id = {} AND domain_blocks.severity IN ({}))",
+        actor_column, severities
+    )
+}
+
+#[derive(Insertable)]
+#[table_name = "domain_blocks"]
+pub struct NewDomainBlock {
+    domain: String,
+    severity: DomainBlockSeverity,
+    public_reason: Option<String>,
+    private_reason: Option<String>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl NewDomainBlock {
+    pub fn new(
+        domain: &str,
+        severity: DomainBlockSeverity,
+        public_reason: Option<String>,
+        private_reason: Option<String>,
+    ) -> Self {
+        let now = Utc::now();
+
+        NewDomainBlock {
+            domain: normalize_domain(domain),
+            severity,
+            public_reason,
+            private_reason,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
+pub struct InstanceBlocker(i32);
+
+impl InstanceBlocker {
+    pub(crate) fn new(moderator: i32) -> InstanceBlocker {
+        InstanceBlocker(moderator)
+    }
+
+    /// List every domain block, including private reasons.
+    pub fn domain_blocks(
+        &self,
+        conn: &PgConnection,
+    ) -> Result<Vec<DomainBlock>, diesel::result::Error> {
+        use schema::domain_blocks;
+        use diesel::prelude::*;
+
+        domain_blocks::table
+            .order(domain_blocks::dsl::domain.asc())
+            .load(conn)
+    }
+
+    /// Block a domain and all of its subdomains.
+    ///
+    /// If the domain is already blocked, its severity and reasons are replaced.
+    pub fn block_domain(
+        &self,
+        domain: &str,
+        severity: DomainBlockSeverity,
+        public_reason: Option<String>,
+        private_reason: Option<String>,
+        conn: &PgConnection,
+    ) -> Result<DomainBlock, diesel::result::Error> {
+        use schema::domain_blocks;
+        use diesel::prelude::*;
+
+        conn.transaction(|| {
+            let previous = DomainBlock::for_exact_domain(domain, conn)?;
+
+            let domain_block: DomainBlock = diesel::insert_into(domain_blocks::table)
+                .values(&NewDomainBlock::new(
+                    domain,
+                    severity,
+                    public_reason.clone(),
+                    private_reason.clone(),
+                ))
+                .on_conflict(domain_blocks::dsl::domain)
+                .do_update()
+                .set((
+                    domain_blocks::dsl::severity.eq(severity),
+                    domain_blocks::dsl::public_reason.eq(public_reason),
+                    domain_blocks::dsl::private_reason.eq(private_reason),
+                    domain_blocks::dsl::updated_at.eq(Utc::now()),
+                ))
+                .get_result(conn)?;
+
+            AuditLogEntry::record(
+                Some(self.0),
+                AuditAction::BlockDomain,
+                Some(domain_block.id()),
+                previous.map(|previous| previous.audit_json()),
+                Some(domain_block.audit_json()),
+                conn,
+            )?;
+
+            Ok(domain_block)
+        })
+    }
+
+    pub fn unblock_domain(
+        &self,
+        domain_block: DomainBlock,
+        conn: &PgConnection,
+    ) -> Result<(), diesel::result::Error> {
+        use diesel::prelude::*;
+
+        conn.transaction(|| {
+            diesel::delete(&domain_block).execute(conn)?;
+
+            AuditLogEntry::record(
+                Some(self.0),
+                AuditAction::UnblockDomain,
+                Some(domain_block.id()),
+                Some(domain_block.audit_json()),
+                None,
+                conn,
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn candidates_include_parent_domains() {
+        assert_eq!(
+            domain_candidates("a.b.example.com"),
+            vec!["a.b.example.com", "b.example.com", "example.com", "com"]
+        );
+    }
+
+    #[test]
+    fn normalizes_case_and_trailing_dot() {
+        assert_eq!(normalize_domain("Example.COM."), "example.com");
+    }
+
+    #[test]
+    fn reads_domain_from_url() {
+        let url = Url("https://Social.Example.com:8443/users/1".parse().unwrap());
+
+        assert_eq!(domain_of(&url), Some("social.example.com".to_owned()));
+    }
+}
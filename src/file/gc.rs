@@ -0,0 +1,112 @@
+use chrono::Duration;
+use chrono::offset::Utc;
+use diesel;
+use diesel::pg::PgConnection;
+use diesel::sql_types::{BigInt, Integer, Text, Timestamptz};
+
+use file::storage::{Storage, StorageError};
+
+#[derive(QueryableByName)]
+struct CollectedImage {
+    #[sql_type = "Integer"]
+    id: i32,
+}
+
+/// A file that was removed by the garbage collector.
+#[derive(Debug, QueryableByName)]
+pub struct CollectedFile {
+    #[sql_type = "Integer"]
+    pub id: i32,
+    #[sql_type = "Text"]
+    pub storage_key: String,
+    #[sql_type = "BigInt"]
+    pub size: i64,
+}
+
+/// What a garbage collection run removed.
+#[derive(Debug)]
+pub struct GarbageReport {
+    /// The ids of images that were removed, not including their resized variants.
+    pub images: Vec<i32>,
+    /// The files that were removed from the database.
+    pub files: Vec<CollectedFile>,
+    /// Files that were removed from the database, but couldn't be removed from storage.
+    pub storage_errors: Vec<(CollectedFile, StorageError)>,
+}
+
+impl GarbageReport {
+    /// The number of bytes removed from storage.
+    pub fn freed_bytes(&self) -> i64 {
+        self.files.iter().map(|file| file.size).sum()
+    }
+}
+
+/// Remove images and files that nothing refers to anymore.
+///
+/// Images are removed when they aren't used as an avatar, an icon, or a post attachment. Files are
+/// removed when they aren't used by an image, a post attachment, or the remote media cache. Only
+/// files last stored more than `grace_period` ago are considered, so uploads that haven't been
+/// attached to anything yet are left alone, even if identical bytes were stored long before. At
+/// most `limit` images and `limit` files are removed in one run.
+///
+/// Legacy files are never removed, since their bytes live at a path on disk rather than in
+/// `storage`. They're collected once `File::migrate_legacy` has moved them.
+///
+/// Database rows are removed before the stored bytes, so a file is never left in the database
+/// without its contents. If removing the bytes fails, the file is listed in the report's
+/// `storage_errors`.
+pub fn collect_garbage<S: Storage>(
+    grace_period: Duration,
+    limit: i64,
+    storage: &S,
+    conn: &PgConnection,
+) -> Result<GarbageReport, diesel::result::Error> {
+    use diesel::prelude::*;
+
+    let cutoff = Utc::now() - grace_period;
+
+    let images = diesel::sql_query(
+        "DELETE FROM images WHERE images.id IN (\
+         SELECT images.id FROM images INNER JOIN files ON files.id = images.file_id \
+         WHERE images.original_image IS NULL AND files.last_stored_at < $1 \
+         AND NOT EXISTS (SELECT 1 FROM personas WHERE personas.avatar = images.id) \
+         AND NOT EXISTS (SELECT 1 FROM base_posts WHERE base_posts.icon = images.id) \
+         AND NOT EXISTS (SELECT 1 FROM media_posts WHERE media_posts.file_id = images.file_id) \
+         ORDER BY files.last_stored_at ASC LIMIT $2) \
+         RETURNING images.id AS id",
+    ).bind::<Timestamptz, _>(cutoff)
+        .bind::<BigInt, _>(limit)
+        .load::<CollectedImage>(conn)?
+        .into_iter()
+        .map(|image| image.id)
+        .collect();
+
+    let collected = diesel::sql_query(
+        "DELETE FROM files WHERE files.id IN (\
+         SELECT files.id FROM files WHERE files.last_stored_at < $1 \
+         AND files.content_hash IS NOT NULL \
+         AND NOT EXISTS (SELECT 1 FROM images WHERE images.file_id = files.id) \
+         AND NOT EXISTS (SELECT 1 FROM media_posts WHERE media_posts.file_id = files.id) \
+         AND NOT EXISTS (SELECT 1 FROM remote_media WHERE remote_media.file_id = files.id) \
+         ORDER BY files.last_stored_at ASC LIMIT $2) \
+         RETURNING files.id AS id, files.storage_key AS storage_key, files.size AS size",
+    ).bind::<Timestamptz, _>(cutoff)
+        .bind::<BigInt, _>(limit)
+        .load::<CollectedFile>(conn)?;
+
+    let mut files = Vec::new();
+    let mut storage_errors = Vec::new();
+
+    for file in collected {
+        match storage.delete(&file.storage_key) {
+            Ok(()) | Err(StorageError::NotFound) => files.push(file),
+            Err(e) => storage_errors.push((file, e)),
+        }
+    }
+
+    Ok(GarbageReport {
+        images,
+        files,
+        storage_errors,
+    })
+}
@@ -1,13 +1,65 @@
+use std::io::Cursor;
+
+use diesel;
+use diesel::pg::PgConnection;
+use exif;
+use image::{self as image_lib, DynamicImage, FilterType, GenericImage, ImageFormat};
+use mime;
+
 use schema::images;
-use file::File;
+use file::{File, FileCreationError};
+use file::storage::{Storage, StorageError};
+use sql_types::{ImageVariant, Mime};
+
+#[derive(Debug, Fail)]
+pub enum ImageProcessingError {
+    #[fail(display = "Error reading image: {}", _0)]
+    Storage(#[cause] StorageError),
+    #[fail(display = "Error decoding image: {}", _0)]
+    Image(#[cause] image_lib::ImageError),
+    #[fail(display = "Error storing image: {}", _0)]
+    File(#[cause] FileCreationError),
+    #[fail(display = "Error recording image: {}", _0)]
+    Diesel(#[cause] diesel::result::Error),
+    #[fail(display = "Image format is not supported")]
+    Unsupported,
+    #[fail(display = "Image is too large: {}x{}", _0, _1)]
+    TooLarge(u32, u32),
+}
+
+impl From<StorageError> for ImageProcessingError {
+    fn from(e: StorageError) -> Self {
+        ImageProcessingError::Storage(e)
+    }
+}
+
+impl From<image_lib::ImageError> for ImageProcessingError {
+    fn from(e: image_lib::ImageError) -> Self {
+        ImageProcessingError::Image(e)
+    }
+}
+
+impl From<FileCreationError> for ImageProcessingError {
+    fn from(e: FileCreationError) -> Self {
+        ImageProcessingError::File(e)
+    }
+}
+
+impl From<diesel::result::Error> for ImageProcessingError {
+    fn from(e: diesel::result::Error) -> Self {
+        ImageProcessingError::Diesel(e)
+    }
+}
 
 #[derive(Debug, Identifiable, Queryable)]
 #[table_name = "images"]
 pub struct Image {
     id: i32,
-    width: u32,
-    height: u32,
+    width: i32,
+    height: i32,
     file_id: i32, // foreign key to File
+    variant: ImageVariant,
+    original_image: Option<i32>, // foreign key to Image
 }
 
 impl Image {
@@ -16,16 +68,227 @@ impl Image {
     }
 
     pub fn width(&self) -> u32 {
-        self.width
+        self.width as u32
     }
 
     pub fn height(&self) -> u32 {
-        self.height
+        self.height as u32
     }
 
     pub fn file_id(&self) -> i32 {
         self.file_id
     }
+
+    pub fn variant(&self) -> ImageVariant {
+        self.variant
+    }
+
+    /// The image this is a resized variant of, if it isn't an original.
+    pub fn original_image(&self) -> Option<i32> {
+        self.original_image
+    }
+
+    /// Fetch the resized variants of this image.
+    pub fn variants(&self, conn: &PgConnection) -> Result<Vec<Image>, diesel::result::Error> {
+        use diesel::prelude::*;
+
+        images::table
+            .filter(images::dsl::original_image.eq(self.id))
+            .load(conn)
+    }
+
+    /// Fetch the given variant of this image.
+    ///
+    /// Images that are already smaller than a variant don't have that variant generated, so in
+    /// that case the original is returned instead.
+    pub fn sized(
+        self,
+        variant: ImageVariant,
+        conn: &PgConnection,
+    ) -> Result<Image, diesel::result::Error> {
+        use diesel::prelude::*;
+
+        if variant == self.variant {
+            return Ok(self);
+        }
+
+        let sized = images::table
+            .filter(images::dsl::original_image.eq(self.id))
+            .filter(images::dsl::variant.eq(variant))
+            .get_result(conn)
+            .optional()?;
+
+        Ok(sized.unwrap_or(self))
+    }
+
+    /// Process an uploaded file into an `Image` and its resized variants.
+    ///
+    /// The file's format is detected from its contents, and images larger than `MAX_DIMENSION`
+    /// or `MAX_PIXELS` are refused before they're decoded. The image is rotated to match its EXIF
+    /// orientation, then re-encoded so that any EXIF or GPS metadata in the upload is dropped.
+    /// The re-encoded copy and the resized variants belong to the same user as the upload.
+    ///
+    /// The uploaded `File` is left in place, since identical bytes stored by someone else are
+    /// returned as the same `File`. Once nothing refers to it, `gc::collect_garbage` removes it.
+    pub fn ingest<S: Storage>(
+        file: File,
+        storage: &S,
+        conn: &PgConnection,
+    ) -> Result<(Image, Vec<Image>), ImageProcessingError> {
+        use diesel::prelude::*;
+
+        let bytes = file.read(storage)?;
+        let format = image_lib::guess_format(&bytes)?;
+
+        let (width, height) = dimensions(&bytes, format)?;
+        if width > MAX_DIMENSION
+            || height > MAX_DIMENSION
+            || u64::from(width) * u64::from(height) > MAX_PIXELS
+        {
+            return Err(ImageProcessingError::TooLarge(width, height));
+        }
+
+        let decoded = image_lib::load_from_memory_with_format(&bytes, format)?;
+        let decoded = orient(decoded, orientation(&bytes));
+        let (width, height) = decoded.dimensions();
+
+        let output_format = output_format(format);
+        let encoded = match format {
+            // Re-encoding a GIF would keep only its first frame, dropping any animation
+            ImageFormat::GIF => None,
+            _ => Some(store_encoded(&decoded, output_format, file.owner(), storage, conn)?),
+        };
+        let stripped = encoded.as_ref().unwrap_or(&file);
+
+        let mut resized = Vec::new();
+
+        for &variant in &[ImageVariant::Preview, ImageVariant::Thumbnail] {
+            let max = match variant.max_dimension() {
+                Some(max) => max,
+                None => continue,
+            };
+
+            if width <= max && height <= max {
+                continue;
+            }
+
+            let variant_image = decoded.resize(max, max, FilterType::Triangle);
+            let (variant_width, variant_height) = variant_image.dimensions();
+            let variant_file =
+                store_encoded(&variant_image, output_format, file.owner(), storage, conn)?;
+
+            resized.push((variant, variant_file, variant_width, variant_height));
+        }
+
+        conn.transaction::<_, diesel::result::Error, _>(|| {
+            let original: Image = diesel::insert_into(images::table)
+                .values(&NewImage::new(stripped, width, height))
+                .get_result(conn)?;
+
+            let new_variants = resized
+                .iter()
+                .map(|&(variant, ref file, width, height)| {
+                    NewImage::variant(file, width, height, variant, &original)
+                })
+                .collect::<Vec<_>>();
+
+            let variants = if new_variants.is_empty() {
+                Vec::new()
+            } else {
+                diesel::insert_into(images::table)
+                    .values(&new_variants)
+                    .get_results(conn)?
+            };
+
+            Ok((original, variants))
+        }).map_err(From::from)
+    }
+}
+
+/// The largest width or height an uploaded image may have.
+pub const MAX_DIMENSION: u32 = 10_000;
+
+/// The largest number of pixels an uploaded image may have.
+pub const MAX_PIXELS: u64 = 40_000_000;
+
+/// Read an image's dimensions from its header, without decoding it.
+fn dimensions(bytes: &[u8], format: ImageFormat) -> Result<(u32, u32), ImageProcessingError> {
+    use image::ImageDecoder;
+    use image::{bmp, gif, jpeg, png};
+
+    let reader = Cursor::new(bytes);
+
+    let dimensions = match format {
+        ImageFormat::PNG => png::PNGDecoder::new(reader).dimensions(),
+        ImageFormat::JPEG => jpeg::JPEGDecoder::new(reader).dimensions(),
+        ImageFormat::GIF => gif::Decoder::new(reader).dimensions(),
+        ImageFormat::BMP => bmp::BMPDecoder::new(reader).dimensions(),
+        _ => return Err(ImageProcessingError::Unsupported),
+    };
+
+    Ok(dimensions?)
+}
+
+/// Read the EXIF orientation of an image, if it has one.
+fn orientation(bytes: &[u8]) -> u32 {
+    exif::Reader::new(&mut Cursor::new(bytes))
+        .ok()
+        .and_then(|reader| {
+            reader
+                .get_field(exif::Tag::Orientation, false)
+                .and_then(|field| field.value.get_uint(0))
+        })
+        .unwrap_or(1)
+}
+
+/// Rotate and flip an image so it displays upright, given its EXIF orientation.
+fn orient(image: DynamicImage, orientation: u32) -> DynamicImage {
+    match orientation {
+        2 => image.fliph(),
+        3 => image.rotate180(),
+        4 => image.flipv(),
+        5 => image.rotate90().fliph(),
+        6 => image.rotate90(),
+        7 => image.rotate270().fliph(),
+        8 => image.rotate270(),
+        _ => image,
+    }
+}
+
+/// Formats that can't be encoded again are converted to PNG.
+fn output_format(format: ImageFormat) -> ImageFormat {
+    match format {
+        ImageFormat::JPEG => ImageFormat::JPEG,
+        _ => ImageFormat::PNG,
+    }
+}
+
+pub(crate) fn media_type(format: ImageFormat) -> Mime {
+    Mime(match format {
+        ImageFormat::JPEG => mime::IMAGE_JPEG,
+        ImageFormat::GIF => mime::IMAGE_GIF,
+        ImageFormat::BMP => mime::IMAGE_BMP,
+        _ => mime::IMAGE_PNG,
+    })
+}
+
+fn store_encoded<S: Storage>(
+    image: &DynamicImage,
+    format: ImageFormat,
+    owner: Option<i32>,
+    storage: &S,
+    conn: &PgConnection,
+) -> Result<File, ImageProcessingError> {
+    let mut bytes = Vec::new();
+    image.save(&mut bytes, format)?;
+
+    Ok(File::store_with_owner(
+        &bytes,
+        media_type(format),
+        owner,
+        storage,
+        conn,
+    )?)
 }
 
 #[derive(Insertable)]
@@ -34,6 +297,8 @@ pub struct NewImage {
     width: i32,
     height: i32,
     file_id: i32,
+    variant: ImageVariant,
+    original_image: Option<i32>,
 }
 
 impl NewImage {
@@ -42,6 +307,42 @@ impl NewImage {
             width: width as i32,
             height: height as i32,
             file_id: file.id(),
+            variant: ImageVariant::Original,
+            original_image: None,
+        }
+    }
+
+    /// Create a `NewImage` for a resized copy of an existing image.
+    pub fn variant(
+        file: &File,
+        width: u32,
+        height: u32,
+        variant: ImageVariant,
+        original: &Image,
+    ) -> Self {
+        NewImage {
+            width: width as i32,
+            height: height as i32,
+            file_id: file.id(),
+            variant,
+            original_image: Some(original.id()),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use image::{DynamicImage, GenericImage};
+
+    use super::orient;
+
+    #[test]
+    fn orientation_swaps_rotated_dimensions() {
+        let image = DynamicImage::new_rgb8(2, 1);
+
+        assert_eq!(orient(image.clone(), 1).dimensions(), (2, 1));
+        assert_eq!(orient(image.clone(), 3).dimensions(), (2, 1));
+        assert_eq!(orient(image.clone(), 6).dimensions(), (1, 2));
+        assert_eq!(orient(image, 8).dimensions(), (1, 2));
+    }
+}
@@ -1,22 +1,64 @@
-use std::path::{Path, PathBuf};
+use std::fs;
 
+use chrono::DateTime;
+use chrono::offset::Utc;
+use diesel;
+use diesel::pg::PgConnection;
+use image::{self as image_lib, ImageFormat};
+use mime;
+use sha2::{Digest, Sha256};
+
+pub mod gc;
 pub mod image;
+pub mod quota;
+pub mod remote_media;
+pub mod storage;
 
 use schema::files;
+use self::quota::{QuotaExceeded, StorageUsage};
+use self::storage::{Storage, StorageError};
+use sql_types::Mime;
+use user::UserLike;
 
-#[derive(Clone, Copy, Debug, Eq, Fail, PartialEq)]
+#[derive(Debug, Fail)]
 pub enum FileCreationError {
-    #[fail(display = "File is missing")]
-    Missing,
-    #[fail(display = "File path contains invalid utf8")]
-    Utf8,
+    #[fail(display = "Error storing file: {}", _0)]
+    Storage(#[cause] StorageError),
+    #[fail(display = "Error recording file: {}", _0)]
+    Diesel(#[cause] diesel::result::Error),
+    #[fail(display = "Not enough storage: {}", _0)]
+    Quota(#[cause] QuotaExceeded),
+}
+
+impl From<StorageError> for FileCreationError {
+    fn from(e: StorageError) -> Self {
+        FileCreationError::Storage(e)
+    }
+}
+
+impl From<diesel::result::Error> for FileCreationError {
+    fn from(e: diesel::result::Error) -> Self {
+        FileCreationError::Diesel(e)
+    }
+}
+
+impl From<QuotaExceeded> for FileCreationError {
+    fn from(e: QuotaExceeded) -> Self {
+        FileCreationError::Quota(e)
+    }
 }
 
 #[derive(Debug, Identifiable, Queryable)]
 #[table_name = "files"]
 pub struct File {
     id: i32,
-    file_path: PathBuf,
+    storage_key: String,
+    content_hash: Option<String>, // hex encoded sha256
+    size: i64,
+    media_type: Mime, // max_length: 120
+    created_at: DateTime<Utc>,
+    owner: Option<i32>, // foreign key to User
+    last_stored_at: DateTime<Utc>,
 }
 
 impl File {
@@ -24,31 +66,295 @@ impl File {
         self.id
     }
 
-    pub fn path(&self) -> &Path {
-        &self.file_path
+    /// The key this file's bytes are stored under in its `Storage`.
+    pub fn storage_key(&self) -> &str {
+        &self.storage_key
+    }
+
+    pub fn content_hash(&self) -> Option<&str> {
+        self.content_hash.as_ref().map(|s| s.as_ref())
+    }
+
+    /// The size of this file in bytes.
+    pub fn size(&self) -> i64 {
+        self.size
+    }
+
+    pub fn media_type(&self) -> &Mime {
+        &self.media_type
+    }
+
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+
+    /// The user who first stored this file, if any.
+    ///
+    /// Everyone else who stores or attaches the same bytes is charged for them too, see
+    /// `quota::StorageUsage`.
+    pub fn owner(&self) -> Option<i32> {
+        self.owner
+    }
+
+    /// The last time these bytes were stored, including when they were already stored and the
+    /// existing `File` was returned.
+    pub fn last_stored_at(&self) -> DateTime<Utc> {
+        self.last_stored_at
+    }
+
+    /// Whether this file was recorded before files were addressed by their contents.
+    ///
+    /// Such files are recorded by their path on disk rather than by a key in a `Storage`, until
+    /// `migrate_legacy` moves them. The path may be relative, in which case it's read relative to
+    /// the working directory, as it was when it was recorded.
+    pub fn is_legacy(&self) -> bool {
+        self.content_hash.is_none()
+    }
+
+    /// Fetch this file's bytes from the given storage.
+    ///
+    /// Legacy files are read from their path on disk instead.
+    pub fn read<S: Storage>(&self, storage: &S) -> Result<Vec<u8>, StorageError> {
+        if self.is_legacy() {
+            return fs::read(&self.storage_key).map_err(From::from);
+        }
+
+        storage.fetch(&self.storage_key)
+    }
+
+    /// Move files recorded before files were addressed by their contents into the given storage.
+    ///
+    /// Each legacy file is read from its path on disk, stored under the hash of its contents, and
+    /// has its hash and size filled in. Images recorded without a media type get one. The copy on
+    /// disk is left in place.
+    ///
+    /// A legacy file whose contents are already stored for another `File` can't be given the same
+    /// hash, so it's left as it is and still read from disk.
+    pub fn migrate_legacy<S: Storage>(
+        storage: &S,
+        conn: &PgConnection,
+    ) -> Result<LegacyMigration, diesel::result::Error> {
+        use diesel::prelude::*;
+
+        let legacy: Vec<(i32, String, Mime)> = files::table
+            .filter(files::dsl::content_hash.is_null())
+            .select((files::dsl::id, files::dsl::storage_key, files::dsl::media_type))
+            .load(conn)?;
+
+        let mut migration = LegacyMigration {
+            migrated: Vec::new(),
+            duplicates: Vec::new(),
+            storage_errors: Vec::new(),
+        };
+
+        for (id, path, media_type) in legacy {
+            let bytes = match fs::read(&path) {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    migration.storage_errors.push((id, From::from(e)));
+                    continue;
+                }
+            };
+
+            let content_hash = hash(&bytes);
+
+            if File::by_hash(&content_hash, conn)?.is_some() {
+                migration.duplicates.push(id);
+                continue;
+            }
+
+            let media_type = match image_lib::guess_format(&bytes) {
+                Ok(format @ ImageFormat::PNG)
+                | Ok(format @ ImageFormat::JPEG)
+                | Ok(format @ ImageFormat::GIF)
+                | Ok(format @ ImageFormat::BMP)
+                    if media_type.0 == mime::APPLICATION_OCTET_STREAM =>
+                {
+                    self::image::media_type(format)
+                }
+                _ => media_type,
+            };
+
+            let key = storage_key(&content_hash);
+
+            if let Err(e) = storage.store(&key, &bytes, media_type.0.as_ref()) {
+                migration.storage_errors.push((id, e));
+                continue;
+            }
+
+            diesel::update(files::table.find(id))
+                .set((
+                    files::dsl::storage_key.eq(key),
+                    files::dsl::content_hash.eq(content_hash),
+                    files::dsl::size.eq(bytes.len() as i64),
+                    files::dsl::media_type.eq(media_type),
+                ))
+                .execute(conn)?;
+
+            migration.migrated.push(id);
+        }
+
+        Ok(migration)
+    }
+
+    /// Store the given bytes and record them as a `File` that doesn't belong to any user, such
+    /// as media fetched from a remote server.
+    ///
+    /// Files are addressed by the hash of their contents. If identical bytes have already been
+    /// stored, the existing `File` is returned and nothing new is stored, but it's marked as
+    /// stored again so the garbage collector gives it a new grace period.
+    pub fn store<S: Storage>(
+        bytes: &[u8],
+        media_type: Mime,
+        storage: &S,
+        conn: &PgConnection,
+    ) -> Result<File, FileCreationError> {
+        File::store_with_owner(bytes, media_type, None, storage, conn)
+    }
+
+    /// Store bytes uploaded by a user, counting them against that user's storage quota.
+    ///
+    /// The upload is refused if it would take the user over their quota.
+    pub fn store_owned<S: Storage, U: UserLike>(
+        bytes: &[u8],
+        media_type: Mime,
+        owner: &U,
+        storage: &S,
+        conn: &PgConnection,
+    ) -> Result<File, FileCreationError> {
+        File::store_with_owner(bytes, media_type, Some(owner.id()), storage, conn)
+    }
+
+    /// Store bytes, charging them to `owner` if there is one.
+    ///
+    /// Everything stored for a user goes through here, including the re-encoded copies and
+    /// resized variants of their images, so the quota is checked here rather than by callers.
+    /// Bytes that are already stored aren't stored again, but the owner is still charged for
+    /// them unless they already were. Recording the file and charging for it happen in one
+    /// transaction, so a refused charge doesn't leave the file recorded.
+    pub(crate) fn store_with_owner<S: Storage>(
+        bytes: &[u8],
+        media_type: Mime,
+        owner: Option<i32>,
+        storage: &S,
+        conn: &PgConnection,
+    ) -> Result<File, FileCreationError> {
+        use diesel::prelude::*;
+
+        let content_hash = hash(bytes);
+
+        conn.transaction(|| {
+            let file = match File::by_hash(&content_hash, conn)? {
+                Some(file) => file.mark_stored(conn)?,
+                None => File::store_new(bytes, media_type, owner, &content_hash, storage, conn)?,
+            };
+
+            if let Some(owner) = owner {
+                StorageUsage::charge::<FileCreationError>(owner, &[(file.id, file.size)], conn)?;
+            }
+
+            Ok(file)
+        })
+    }
+
+    /// Store bytes that haven't been stored before, checking `owner` has room for them.
+    fn store_new<S: Storage>(
+        bytes: &[u8],
+        media_type: Mime,
+        owner: Option<i32>,
+        content_hash: &str,
+        storage: &S,
+        conn: &PgConnection,
+    ) -> Result<File, FileCreationError> {
+        use diesel::prelude::*;
+
+        if let Some(owner) = owner {
+            StorageUsage::for_user_id(owner, conn)?.check(bytes.len() as i64)?;
+        }
+
+        let now = Utc::now();
+
+        let new_file = Newfile {
+            storage_key: storage_key(content_hash),
+            content_hash: Some(content_hash.to_owned()),
+            size: bytes.len() as i64,
+            media_type,
+            created_at: now,
+            owner,
+            last_stored_at: now,
+        };
+
+        storage.store(&new_file.storage_key, bytes, new_file.media_type.0.as_ref())?;
+
+        let inserted: Option<File> = diesel::insert_into(files::table)
+            .values(&new_file)
+            .on_conflict_do_nothing()
+            .get_result(conn)
+            .optional()?;
+
+        match inserted {
+            Some(file) => Ok(file),
+            // Someone else stored the same bytes at the same time
+            None => File::by_hash(content_hash, conn)?
+                .ok_or(FileCreationError::Diesel(diesel::result::Error::NotFound))
+                .and_then(|file| file.mark_stored(conn).map_err(From::from)),
+        }
+    }
+
+    fn mark_stored(self, conn: &PgConnection) -> Result<File, diesel::result::Error> {
+        use diesel::prelude::*;
+
+        diesel::update(&self)
+            .set(files::dsl::last_stored_at.eq(Utc::now()))
+            .get_result(conn)
     }
+
+    fn by_hash(
+        content_hash: &str,
+        conn: &PgConnection,
+    ) -> Result<Option<File>, diesel::result::Error> {
+        use diesel::prelude::*;
+
+        files::table
+            .filter(files::dsl::content_hash.eq(content_hash))
+            .get_result(conn)
+            .optional()
+    }
+}
+
+/// What a `File::migrate_legacy` run did.
+#[derive(Debug)]
+pub struct LegacyMigration {
+    /// The ids of files that were moved into storage.
+    pub migrated: Vec<i32>,
+    /// The ids of files left on disk because their contents are already stored for another file.
+    pub duplicates: Vec<i32>,
+    /// Files that couldn't be read from disk or written to storage.
+    pub storage_errors: Vec<(i32, StorageError)>,
+}
+
+/// Hex encode the sha256 hash of the given bytes.
+fn hash(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Spread files across directories by the start of their hash, so no single directory gets too
+/// large.
+fn storage_key(content_hash: &str) -> String {
+    format!("{}/{}/{}", &content_hash[0..2], &content_hash[2..4], content_hash)
 }
 
 #[derive(Insertable)]
 #[table_name = "files"]
-pub struct Newfile {
-    file_path: String,
-}
-
-impl Newfile {
-    pub fn new<P>(path: P) -> Result<Self, FileCreationError>
-    where
-        P: AsRef<Path>,
-    {
-        if path.as_ref().is_file() {
-            path.as_ref()
-                .to_str()
-                .map(|p| Newfile {
-                    file_path: p.to_owned(),
-                })
-                .ok_or(FileCreationError::Utf8)
-        } else {
-            Err(FileCreationError::Missing)
-        }
-    }
+struct Newfile {
+    storage_key: String,
+    content_hash: Option<String>,
+    size: i64,
+    media_type: Mime,
+    created_at: DateTime<Utc>,
+    owner: Option<i32>,
+    last_stored_at: DateTime<Utc>,
 }
@@ -0,0 +1,295 @@
+use chrono::DateTime;
+use chrono::offset::Utc;
+use diesel;
+use diesel::pg::PgConnection;
+use diesel::sql_types::Integer;
+
+use schema::{file_owners, files, roles, user_roles};
+use user::UserLike;
+use user::role::user_role::active_grant;
+
+#[derive(QueryableByName)]
+struct OwnerHit {
+    #[sql_type = "Integer"]
+    owner: i32,
+}
+
+#[derive(Debug, Fail)]
+#[fail(display = "Storing {} more bytes would exceed the quota of {} bytes, {} are already used",
+       requested, quota, used)]
+pub struct QuotaExceeded {
+    pub used: i64,
+    pub requested: i64,
+    pub quota: i64,
+}
+
+/// How much storage a user has used, and how much they're allowed.
+///
+/// Usage is the total size of the files a user is charged for. Files are shared between everyone
+/// who stores the same bytes, and each of those users is charged for them, as is everyone who
+/// attaches them to a post.
+#[derive(Clone, Copy, Debug)]
+pub struct StorageUsage {
+    user_id: i32,
+    used: i64,
+    quota: Option<i64>,
+}
+
+impl StorageUsage {
+    pub fn user_id(&self) -> i32 {
+        self.user_id
+    }
+
+    /// The number of bytes this user has stored.
+    pub fn used(&self) -> i64 {
+        self.used
+    }
+
+    /// The number of bytes this user may store, or `None` if they have no limit.
+    ///
+    /// A user's quota is the largest quota of any of their roles. If any of their roles has no
+    /// limit, neither do they. Users without roles can't store anything.
+    pub fn quota(&self) -> Option<i64> {
+        self.quota
+    }
+
+    /// The number of bytes this user can still store, or `None` if they have no limit.
+    pub fn remaining(&self) -> Option<i64> {
+        self.quota.map(|quota| (quota - self.used).max(0))
+    }
+
+    /// Check that this user can store `requested` more bytes.
+    pub fn check(&self, requested: i64) -> Result<(), QuotaExceeded> {
+        match self.quota {
+            Some(quota) if self.used + requested > quota => Err(QuotaExceeded {
+                used: self.used,
+                requested,
+                quota,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Charge a user for the given files, as `(id, size)` pairs, skipping any they're already
+    /// charged for.
+    ///
+    /// The charge is refused if it would take the user over their quota.
+    pub(crate) fn charge<E>(
+        user_id: i32,
+        files: &[(i32, i64)],
+        conn: &PgConnection,
+    ) -> Result<StorageUsage, E>
+    where
+        E: From<diesel::result::Error> + From<QuotaExceeded>,
+    {
+        use diesel::prelude::*;
+
+        let file_ids = files.iter().map(|&(id, _)| id).collect::<Vec<_>>();
+
+        let charged: Vec<i32> = file_owners::table
+            .filter(file_owners::dsl::user_id.eq(user_id))
+            .filter(file_owners::dsl::file_id.eq_any(file_ids))
+            .select(file_owners::dsl::file_id)
+            .load(conn)?;
+
+        let (usage, uncharged) =
+            StorageUsage::for_user_id(user_id, conn)?.with_charges(files, &charged)?;
+
+        if !uncharged.is_empty() {
+            let now = Utc::now();
+
+            let new_file_owners = uncharged
+                .into_iter()
+                .map(|file_id| NewFileOwner {
+                    file_id,
+                    user_id,
+                    created_at: now,
+                })
+                .collect::<Vec<_>>();
+
+            diesel::insert_into(file_owners::table)
+                .values(&new_file_owners)
+                .on_conflict_do_nothing()
+                .execute(conn)?;
+        }
+
+        Ok(usage)
+    }
+
+    /// Add the files that aren't in `charged` to this usage, returning the new usage and the ids
+    /// of the files that were added.
+    fn with_charges(
+        self,
+        files: &[(i32, i64)],
+        charged: &[i32],
+    ) -> Result<(StorageUsage, Vec<i32>), QuotaExceeded> {
+        let mut uncharged = files
+            .iter()
+            .filter(|&&(id, _)| !charged.contains(&id))
+            .cloned()
+            .collect::<Vec<_>>();
+        uncharged.sort();
+        uncharged.dedup();
+
+        let requested = uncharged.iter().map(|&(_, size)| size).sum();
+        self.check(requested)?;
+
+        Ok((
+            StorageUsage {
+                used: self.used + requested,
+                ..self
+            },
+            uncharged.into_iter().map(|(id, _)| id).collect(),
+        ))
+    }
+
+    pub(crate) fn for_user_id(
+        user_id: i32,
+        conn: &PgConnection,
+    ) -> Result<StorageUsage, diesel::result::Error> {
+        use diesel::dsl::sql;
+        use diesel::prelude::*;
+        use diesel::sql_types::BigInt;
+
+        let used = files::table
+            .inner_join(file_owners::table)
+            .filter(file_owners::dsl::user_id.eq(user_id))
+            .select(sql::<BigInt>("COALESCE(SUM(files.size), 0)::BIGINT"))
+            .get_result(conn)?;
+
+        let quotas: Vec<Option<i64>> = roles::table
+            .inner_join(user_roles::table)
+            .filter(user_roles::dsl::user_id.eq(user_id))
+            .filter(active_grant())
+            .select(roles::dsl::storage_quota)
+            .load(conn)?;
+
+        let quota = if quotas.iter().any(|quota| quota.is_none()) {
+            None
+        } else {
+            Some(quotas.into_iter().filter_map(|quota| quota).max().unwrap_or(0))
+        };
+
+        Ok(StorageUsage {
+            user_id,
+            used,
+            quota,
+        })
+    }
+
+    /// Fetch the usage of the users storing the most bytes, from most to least.
+    pub(crate) fn heaviest(
+        limit: i64,
+        offset: i64,
+        conn: &PgConnection,
+    ) -> Result<Vec<StorageUsage>, diesel::result::Error> {
+        use diesel::prelude::*;
+        use diesel::sql_types::BigInt;
+
+        diesel::sql_query(
+            "SELECT file_owners.user_id AS owner FROM file_owners \
+             INNER JOIN files ON files.id = file_owners.file_id \
+             GROUP BY file_owners.user_id ORDER BY SUM(files.size) DESC, file_owners.user_id ASC \
+             LIMIT $1 OFFSET $2",
+        ).bind::<BigInt, _>(limit)
+            .bind::<BigInt, _>(offset)
+            .load::<OwnerHit>(conn)?
+            .into_iter()
+            .map(|hit| StorageUsage::for_user_id(hit.owner, conn))
+            .collect()
+    }
+
+    /// The number of bytes stored in files that aren't charged to any user, such as cached remote
+    /// media.
+    pub(crate) fn unowned(conn: &PgConnection) -> Result<i64, diesel::result::Error> {
+        use diesel::dsl::sql;
+        use diesel::prelude::*;
+        use diesel::sql_types::{BigInt, Bool};
+
+        files::table
+            .filter(sql::<Bool>(
+                "NOT EXISTS (SELECT 1 FROM file_owners WHERE file_owners.file_id = files.id)",
+            ))
+            .select(sql::<BigInt>("COALESCE(SUM(files.size), 0)::BIGINT"))
+            .get_result(conn)
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "file_owners"]
+struct NewFileOwner {
+    file_id: i32,
+    user_id: i32,
+    created_at: DateTime<Utc>,
+}
+
+pub struct StorageUsageViewer(());
+
+impl StorageUsageViewer {
+    pub(crate) fn new() -> StorageUsageViewer {
+        StorageUsageViewer(())
+    }
+
+    pub fn usage<U: UserLike>(
+        &self,
+        user: &U,
+        conn: &PgConnection,
+    ) -> Result<StorageUsage, diesel::result::Error> {
+        StorageUsage::for_user_id(user.id(), conn)
+    }
+
+    /// List the users storing the most bytes, from most to least.
+    pub fn heaviest_users(
+        &self,
+        limit: i64,
+        offset: i64,
+        conn: &PgConnection,
+    ) -> Result<Vec<StorageUsage>, diesel::result::Error> {
+        StorageUsage::heaviest(limit, offset, conn)
+    }
+
+    /// The number of bytes stored in files that don't count against any user's quota.
+    pub fn unowned_usage(&self, conn: &PgConnection) -> Result<i64, diesel::result::Error> {
+        StorageUsage::unowned(conn)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StorageUsage;
+
+    fn usage(used: i64) -> StorageUsage {
+        StorageUsage {
+            user_id: 2,
+            used,
+            quota: Some(1000),
+        }
+    }
+
+    #[test]
+    fn attaching_foreign_file_charges_attacher() {
+        // File 1 was stored by another user, so this user isn't charged for it yet
+        let (usage, charged) = usage(100).with_charges(&[(1, 300)], &[]).unwrap();
+
+        assert_eq!(usage.used(), 400);
+        assert_eq!(charged, vec![1]);
+    }
+
+    #[test]
+    fn charged_files_arent_charged_again() {
+        let (usage, charged) = usage(400)
+            .with_charges(&[(1, 300), (2, 50), (2, 50)], &[1])
+            .unwrap();
+
+        assert_eq!(usage.used(), 450);
+        assert_eq!(charged, vec![2]);
+    }
+
+    #[test]
+    fn charges_over_quota_are_refused() {
+        let exceeded = usage(900).with_charges(&[(1, 300)], &[]).unwrap_err();
+
+        assert_eq!(exceeded.used, 900);
+        assert_eq!(exceeded.requested, 300);
+    }
+}
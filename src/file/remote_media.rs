@@ -0,0 +1,224 @@
+use chrono::DateTime;
+use chrono::offset::Utc;
+use diesel;
+use diesel::pg::PgConnection;
+
+use domain_block::{check_media, DomainBlockError};
+use file::{File, FileCreationError};
+use file::storage::Storage;
+use schema::{files, remote_media};
+use sql_types::{Mime, Url};
+
+#[derive(Debug, Fail)]
+pub enum RemoteMediaError {
+    #[fail(display = "Error fetching remote media: {}", _0)]
+    Fetch(String),
+    #[fail(display = "Remote media is {} bytes, larger than the limit of {} bytes", _0, _1)]
+    TooLarge(i64, i64),
+    #[fail(display = "Error storing remote media: {}", _0)]
+    File(#[cause] FileCreationError),
+    #[fail(display = "Error recording remote media: {}", _0)]
+    Diesel(#[cause] diesel::result::Error),
+    #[fail(display = "Remote media refused: {}", _0)]
+    DomainBlock(#[cause] DomainBlockError),
+}
+
+impl From<FileCreationError> for RemoteMediaError {
+    fn from(e: FileCreationError) -> Self {
+        RemoteMediaError::File(e)
+    }
+}
+
+impl From<diesel::result::Error> for RemoteMediaError {
+    fn from(e: diesel::result::Error) -> Self {
+        RemoteMediaError::Diesel(e)
+    }
+}
+
+impl From<DomainBlockError> for RemoteMediaError {
+    fn from(e: DomainBlockError) -> Self {
+        RemoteMediaError::DomainBlock(e)
+    }
+}
+
+/// The bytes and media type of a fetched remote file.
+pub struct FetchedMedia {
+    pub bytes: Vec<u8>,
+    pub media_type: Mime,
+}
+
+/// Fetches media from remote servers.
+///
+/// This is kept separate from the cache so that the network can be swapped out, for instance
+/// for a stub that serves files from memory or from a local test server.
+pub trait RemoteFetcher {
+    /// Fetch the media at the given URL.
+    ///
+    /// Implementations should give up once more than `max_size` bytes have been received,
+    /// returning `RemoteMediaError::TooLarge`.
+    fn fetch(&self, url: &Url, max_size: i64) -> Result<FetchedMedia, RemoteMediaError>;
+}
+
+#[derive(Debug, Identifiable, Queryable)]
+#[table_name = "remote_media"]
+pub struct RemoteMedia {
+    id: i32,
+    source_url: Url,
+    file_id: i32, // foreign key to File
+    fetched_at: DateTime<Utc>,
+    byte_size: i64,
+    last_accessed_at: DateTime<Utc>,
+}
+
+impl RemoteMedia {
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+
+    pub fn source_url(&self) -> &Url {
+        &self.source_url
+    }
+
+    pub fn file_id(&self) -> i32 {
+        self.file_id
+    }
+
+    pub fn fetched_at(&self) -> DateTime<Utc> {
+        self.fetched_at
+    }
+
+    pub fn byte_size(&self) -> i64 {
+        self.byte_size
+    }
+
+    pub fn last_accessed_at(&self) -> DateTime<Utc> {
+        self.last_accessed_at
+    }
+
+    /// Get the cached copy of the media at the given URL, fetching it if it isn't cached yet.
+    ///
+    /// Media larger than `max_size` bytes is refused, as is media from domains that are suspended
+    /// or have their media rejected. Cache hits are marked as accessed so they are kept around by
+    /// `prune`.
+    pub fn fetch<F, S>(
+        url: Url,
+        max_size: i64,
+        fetcher: &F,
+        storage: &S,
+        conn: &PgConnection,
+    ) -> Result<(RemoteMedia, File), RemoteMediaError>
+    where
+        F: RemoteFetcher,
+        S: Storage,
+    {
+        use diesel::prelude::*;
+
+        check_media(&url, conn)?;
+
+        let cached = diesel::update(
+            remote_media::table.filter(remote_media::dsl::source_url.eq(&url)),
+        ).set(remote_media::dsl::last_accessed_at.eq(Utc::now()))
+            .get_result::<RemoteMedia>(conn)
+            .optional()?;
+
+        if let Some(cached) = cached {
+            let file = files::table.find(cached.file_id).get_result(conn)?;
+
+            return Ok((cached, file));
+        }
+
+        let fetched = fetcher.fetch(&url, max_size)?;
+        let byte_size = fetched.bytes.len() as i64;
+
+        if byte_size > max_size {
+            return Err(RemoteMediaError::TooLarge(byte_size, max_size));
+        }
+
+        let file = File::store(&fetched.bytes, fetched.media_type, storage, conn)?;
+        let now = Utc::now();
+
+        let inserted: Option<RemoteMedia> = diesel::insert_into(remote_media::table)
+            .values(&NewRemoteMedia {
+                source_url: Url(url.0.clone()),
+                file_id: file.id(),
+                fetched_at: now,
+                byte_size,
+                last_accessed_at: now,
+            })
+            .on_conflict_do_nothing()
+            .get_result(conn)
+            .optional()?;
+
+        let remote_media = match inserted {
+            Some(remote_media) => remote_media,
+            // Someone else cached the same URL at the same time
+            None => remote_media::table
+                .filter(remote_media::dsl::source_url.eq(&url))
+                .get_result(conn)?,
+        };
+
+        Ok((remote_media, file))
+    }
+
+    /// Evict cache entries that haven't been accessed since `accessed_before`, then evict the
+    /// least recently accessed entries until the cache holds at most `max_total_size` bytes.
+    ///
+    /// This only removes the cache entries. Files that are no longer referenced by anything
+    /// else are left for the file garbage collector.
+    pub fn prune(
+        accessed_before: DateTime<Utc>,
+        max_total_size: i64,
+        conn: &PgConnection,
+    ) -> Result<Vec<RemoteMedia>, diesel::result::Error> {
+        use diesel::prelude::*;
+
+        conn.transaction(|| {
+            let mut evicted: Vec<RemoteMedia> = diesel::delete(
+                remote_media::table
+                    .filter(remote_media::dsl::last_accessed_at.lt(accessed_before)),
+            ).get_results(conn)?;
+
+            let sizes: Vec<(i32, i64)> = remote_media::table
+                .select((remote_media::dsl::id, remote_media::dsl::byte_size))
+                .order((
+                    remote_media::dsl::last_accessed_at.desc(),
+                    remote_media::dsl::id.desc(),
+                ))
+                .load(conn)?;
+
+            let mut total_size = 0;
+            let oversized = sizes
+                .into_iter()
+                .filter_map(|(id, byte_size)| {
+                    total_size += byte_size;
+
+                    if total_size > max_total_size {
+                        Some(id)
+                    } else {
+                        None
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            if !oversized.is_empty() {
+                evicted.extend(
+                    diesel::delete(
+                        remote_media::table.filter(remote_media::dsl::id.eq_any(oversized)),
+                    ).get_results::<RemoteMedia>(conn)?,
+                );
+            }
+
+            Ok(evicted)
+        })
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "remote_media"]
+struct NewRemoteMedia {
+    source_url: Url,
+    file_id: i32,
+    fetched_at: DateTime<Utc>,
+    byte_size: i64,
+    last_accessed_at: DateTime<Utc>,
+}
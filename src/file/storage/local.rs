@@ -0,0 +1,74 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Component, Path, PathBuf};
+
+use super::{Storage, StorageError};
+
+/// Store files in a directory on the local filesystem.
+#[derive(Clone, Debug)]
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        LocalStorage {
+            root: root.as_ref().to_owned(),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Produce the path a key is stored at, refusing keys that would escape the root directory.
+    fn path(&self, key: &str) -> Result<PathBuf, StorageError> {
+        let key = Path::new(key);
+
+        if key.components().all(|component| match component {
+            Component::Normal(_) => true,
+            _ => false,
+        }) {
+            Ok(self.root.join(key))
+        } else {
+            Err(StorageError::InvalidKey)
+        }
+    }
+}
+
+impl Storage for LocalStorage {
+    fn store(&self, key: &str, bytes: &[u8], _media_type: &str) -> Result<(), StorageError> {
+        let path = self.path(key)?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        // Write to a temporary file first so a partially written file is never visible
+        let temp_path = path.with_extension("part");
+
+        {
+            let mut file = fs::File::create(&temp_path)?;
+            file.write_all(bytes)?;
+            file.sync_all()?;
+        }
+
+        fs::rename(&temp_path, &path).map_err(From::from)
+    }
+
+    fn fetch(&self, key: &str) -> Result<Vec<u8>, StorageError> {
+        let path = self.path(key)?;
+
+        fs::read(path).map_err(From::from)
+    }
+
+    fn delete(&self, key: &str) -> Result<(), StorageError> {
+        let path = self.path(key)?;
+
+        match fs::remove_file(path) {
+            Ok(_) => Ok(()),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(From::from(e)),
+        }
+    }
+}
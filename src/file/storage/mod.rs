@@ -0,0 +1,47 @@
+use std::io;
+
+mod local;
+#[cfg(feature = "s3")]
+mod s3;
+
+pub use self::local::LocalStorage;
+#[cfg(feature = "s3")]
+pub use self::s3::S3Storage;
+
+/// Somewhere the bytes of a `File` can be kept.
+///
+/// The `files` table only records the key a file was stored under, so the same `Storage` must be
+/// used to fetch or delete a file as was used to store it.
+pub trait Storage {
+    /// Store the given bytes under the given key, replacing anything already stored there.
+    fn store(&self, key: &str, bytes: &[u8], media_type: &str) -> Result<(), StorageError>;
+
+    /// Fetch the bytes stored under the given key.
+    fn fetch(&self, key: &str) -> Result<Vec<u8>, StorageError>;
+
+    /// Delete the bytes stored under the given key.
+    ///
+    /// Deleting a key that doesn't exist is not an error.
+    fn delete(&self, key: &str) -> Result<(), StorageError>;
+}
+
+#[derive(Debug, Fail)]
+pub enum StorageError {
+    #[fail(display = "Error accessing storage: {}", _0)]
+    Io(#[cause] io::Error),
+    #[fail(display = "Nothing is stored under the given key")]
+    NotFound,
+    #[fail(display = "The given key is not valid for this storage")]
+    InvalidKey,
+    #[fail(display = "Error from storage backend: {}", _0)]
+    Backend(String),
+}
+
+impl From<io::Error> for StorageError {
+    fn from(e: io::Error) -> Self {
+        match e.kind() {
+            io::ErrorKind::NotFound => StorageError::NotFound,
+            _ => StorageError::Io(e),
+        }
+    }
+}
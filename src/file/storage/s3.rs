@@ -0,0 +1,88 @@
+use futures::{Future, Stream};
+use rusoto_core::Region;
+use rusoto_s3::{DeleteObjectRequest, GetObjectError, GetObjectRequest, PutObjectRequest, S3,
+                S3Client};
+
+use super::{Storage, StorageError};
+
+/// Store files in an S3-compatible bucket.
+///
+/// Credentials are read from the environment, or from the usual AWS credentials files. Services
+/// that speak the S3 API, like MinIO, can be used through `S3Storage::with_endpoint`.
+pub struct S3Storage {
+    client: S3Client,
+    bucket: String,
+}
+
+impl S3Storage {
+    pub fn new(bucket: String, region: Region) -> Self {
+        S3Storage {
+            client: S3Client::new(region),
+            bucket,
+        }
+    }
+
+    /// Create an `S3Storage` that talks to a service at a custom endpoint, like
+    /// `http://localhost:9000`.
+    pub fn with_endpoint(bucket: String, region_name: String, endpoint: String) -> Self {
+        S3Storage::new(
+            bucket,
+            Region::Custom {
+                name: region_name,
+                endpoint,
+            },
+        )
+    }
+
+    pub fn bucket(&self) -> &str {
+        &self.bucket
+    }
+}
+
+impl Storage for S3Storage {
+    fn store(&self, key: &str, bytes: &[u8], media_type: &str) -> Result<(), StorageError> {
+        self.client
+            .put_object(PutObjectRequest {
+                bucket: self.bucket.clone(),
+                key: key.to_owned(),
+                body: Some(bytes.to_vec().into()),
+                content_length: Some(bytes.len() as i64),
+                content_type: Some(media_type.to_owned()),
+                ..Default::default()
+            })
+            .sync()
+            .map(|_| ())
+            .map_err(|e| StorageError::Backend(format!("{}", e)))
+    }
+
+    fn fetch(&self, key: &str) -> Result<Vec<u8>, StorageError> {
+        let output = self.client
+            .get_object(GetObjectRequest {
+                bucket: self.bucket.clone(),
+                key: key.to_owned(),
+                ..Default::default()
+            })
+            .sync()
+            .map_err(|e| match e {
+                GetObjectError::NoSuchKey(_) => StorageError::NotFound,
+                e => StorageError::Backend(format!("{}", e)),
+            })?;
+
+        match output.body {
+            Some(body) => body.concat2().wait().map_err(From::from),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn delete(&self, key: &str) -> Result<(), StorageError> {
+        self.client
+            .delete_object(DeleteObjectRequest {
+                bucket: self.bucket.clone(),
+                key: key.to_owned(),
+                ..Default::default()
+            })
+            .sync()
+            .map(|_| ())
+            .map_err(|e| StorageError::Backend(format!("{}", e)))
+    }
+}
@@ -1,25 +1,46 @@
+extern crate ammonia;
 extern crate bcrypt;
 extern crate chrono;
 extern crate chrono_tz;
 #[macro_use]
 extern crate diesel;
+extern crate exif;
 #[macro_use]
 extern crate failure;
+#[cfg(feature = "s3")]
+extern crate futures;
+extern crate image;
+extern crate language_tags;
+#[macro_use]
+extern crate lazy_static;
 #[macro_use]
 extern crate log;
 extern crate mime;
+extern crate pulldown_cmark;
 extern crate rand;
+extern crate regex;
+#[cfg(feature = "s3")]
+extern crate rusoto_core;
+#[cfg(feature = "s3")]
+extern crate rusoto_s3;
 extern crate serde;
 #[macro_use]
 extern crate serde_derive;
+#[macro_use]
 extern crate serde_json;
+extern crate sha2;
 extern crate url;
 
+pub mod activity;
+pub mod audit_log;
 pub mod base_actor;
 pub mod base_post;
+pub mod domain_block;
 pub mod file;
 pub mod link;
+pub mod report;
 pub mod schema;
+pub mod search;
 pub mod sql_types;
 pub mod timer;
 pub mod user;
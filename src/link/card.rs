@@ -0,0 +1,565 @@
+use std::collections::HashSet;
+
+use chrono::DateTime;
+use chrono::offset::Utc;
+use diesel;
+use diesel::pg::PgConnection;
+use regex::Regex;
+use serde_json::Value;
+use url::Url as OrigUrl;
+
+use base_post::BasePost;
+use base_post::post::Post;
+use link::{Link, NewLink};
+use schema::{link_cards, links};
+use sql_types::{Lang, Url};
+
+/// The longest URL a card or link can record.
+const MAX_URL_LENGTH: usize = 2048;
+// Longer text from remote pages is cut down to these many characters
+const MAX_PROVIDER_NAME_LENGTH: usize = 256;
+const MAX_TITLE_LENGTH: usize = 512;
+const MAX_DESCRIPTION_LENGTH: usize = 4096;
+const MAX_LANGUAGE_LENGTH: usize = 35;
+
+lazy_static! {
+    static ref ANCHOR: Regex = Regex::new(r"(?i)<a\s[^>]*>").unwrap();
+    static ref META: Regex = Regex::new(r"(?i)<meta\s[^>]*>").unwrap();
+    static ref HTML: Regex = Regex::new(r"(?i)<html\s[^>]*>").unwrap();
+    static ref ATTRIBUTE: Regex = Regex::new(
+        r#"(?i)\s([a-z:-]+)\s*=\s*(?:"([^"]*)"|'([^']*)')"#
+    ).unwrap();
+}
+
+#[derive(Debug, Fail)]
+pub enum LinkCardError {
+    #[fail(display = "Error fetching link card: {}", _0)]
+    Fetch(String),
+    #[fail(display = "Error recording link card: {}", _0)]
+    Diesel(#[cause] diesel::result::Error),
+}
+
+impl From<diesel::result::Error> for LinkCardError {
+    fn from(e: diesel::result::Error) -> Self {
+        LinkCardError::Diesel(e)
+    }
+}
+
+/// The metadata a page provides about itself.
+#[derive(Debug, Default)]
+pub struct CardMetadata {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub image_url: Option<Url>,
+    pub image_width: Option<u32>,
+    pub image_height: Option<u32>,
+    pub provider_name: Option<String>,
+    pub provider_url: Option<Url>,
+    /// The language the page is written in.
+    pub language: Option<Lang>,
+}
+
+impl CardMetadata {
+    /// Read the OpenGraph metadata from a page's HTML.
+    ///
+    /// Relative image URLs are resolved against the page's URL. The page's language is read from
+    /// `og:locale`, or from the `lang` of its `<html>` tag. Returns `None` if the page doesn't have
+    /// a title.
+    pub fn from_opengraph(page: &Url, html: &str) -> Option<CardMetadata> {
+        let mut metadata = CardMetadata::default();
+        let mut fallback_description = None;
+        let mut fallback_language = None;
+
+        if let Some(tag) = HTML.find(html) {
+            for attribute in ATTRIBUTE.captures_iter(tag.as_str()) {
+                if attribute[1].eq_ignore_ascii_case("lang") {
+                    fallback_language = attribute
+                        .get(2)
+                        .or_else(|| attribute.get(3))
+                        .and_then(|value| language(&unescape(value.as_str())));
+                }
+            }
+        }
+
+        for tag in META.find_iter(html) {
+            let mut key = None;
+            let mut content = None;
+
+            for attribute in ATTRIBUTE.captures_iter(tag.as_str()) {
+                let value = attribute
+                    .get(2)
+                    .or_else(|| attribute.get(3))
+                    .map(|value| unescape(value.as_str()));
+
+                match attribute[1].to_lowercase().as_ref() {
+                    "property" | "name" => key = value.map(|value| value.to_lowercase()),
+                    "content" => content = value,
+                    _ => (),
+                }
+            }
+
+            let (key, content) = match (key, content) {
+                (Some(key), Some(content)) => (key, content),
+                _ => continue,
+            };
+
+            match key.as_ref() {
+                "og:title" => metadata.title = Some(content),
+                "og:description" => metadata.description = Some(content),
+                "description" => fallback_description = Some(content),
+                "og:image" | "og:image:url" => {
+                    metadata.image_url = page.0.join(&content).ok().map(Url)
+                }
+                "og:image:width" => {
+                    metadata.image_width = content.parse().ok().and_then(fit_dimension)
+                }
+                "og:image:height" => {
+                    metadata.image_height = content.parse().ok().and_then(fit_dimension)
+                }
+                "og:site_name" => metadata.provider_name = Some(content),
+                // OpenGraph locales are written like `en_US`
+                "og:locale" => metadata.language = language(&content.replace('_', "-")),
+                _ => (),
+            }
+        }
+
+        metadata.title.as_ref()?;
+
+        if metadata.description.is_none() {
+            metadata.description = fallback_description;
+        }
+
+        if metadata.language.is_none() {
+            metadata.language = fallback_language;
+        }
+
+        metadata.provider_url = page.0
+            .join("/")
+            .ok()
+            .and_then(|origin| {
+                if origin.has_host() {
+                    Some(Url(origin))
+                } else {
+                    None
+                }
+            });
+
+        Some(metadata)
+    }
+
+    /// Read the metadata from an oEmbed response.
+    ///
+    /// Returns `None` if the response doesn't have a title.
+    pub fn from_oembed(response: &Value) -> Option<CardMetadata> {
+        let string = |key: &str| {
+            response
+                .get(key)
+                .and_then(|value| value.as_str())
+                .map(|value| value.to_owned())
+        };
+        let url = |key: &str| {
+            response
+                .get(key)
+                .and_then(|value| value.as_str())
+                .and_then(|value| value.parse::<OrigUrl>().ok())
+                .map(Url)
+        };
+        let dimension = |key: &str| {
+            response
+                .get(key)
+                .and_then(|value| value.as_u64())
+                .and_then(fit_dimension)
+        };
+
+        Some(CardMetadata {
+            title: Some(string("title")?),
+            description: string("author_name"),
+            image_url: url("thumbnail_url"),
+            image_width: dimension("thumbnail_width"),
+            image_height: dimension("thumbnail_height"),
+            provider_name: string("provider_name"),
+            provider_url: url("provider_url"),
+            language: None,
+        })
+    }
+}
+
+/// Fetches metadata about linked pages.
+///
+/// Implementations will usually fetch the page, use its oEmbed endpoint if it advertises one,
+/// and otherwise read its OpenGraph tags with `CardMetadata::from_opengraph`.
+pub trait CardFetcher {
+    /// Fetch the metadata for the page at the given URL, or `None` if it doesn't describe itself.
+    fn fetch(&self, url: &Url) -> Result<Option<CardMetadata>, LinkCardError>;
+}
+
+/// A preview of a linked page, shared by every post linking to it.
+#[derive(Debug, Identifiable, Queryable)]
+#[table_name = "link_cards"]
+pub struct LinkCard {
+    id: i32,
+    url: Url, // max_length: 2048
+    title: Option<String>,
+    description: Option<String>,
+    image_url: Option<Url>, // max_length: 2048
+    image_width: Option<i32>,
+    image_height: Option<i32>,
+    provider_name: Option<String>, // max_length: 256
+    provider_url: Option<Url>,     // max_length: 2048
+    fetched_at: DateTime<Utc>,
+    language: Lang, // max_length: 35
+}
+
+impl LinkCard {
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    pub fn title(&self) -> Option<&str> {
+        self.title.as_ref().map(|s| s.as_ref())
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_ref().map(|s| s.as_ref())
+    }
+
+    pub fn image_url(&self) -> Option<&Url> {
+        self.image_url.as_ref()
+    }
+
+    pub fn image_width(&self) -> Option<u32> {
+        self.image_width.map(|width| width as u32)
+    }
+
+    pub fn image_height(&self) -> Option<u32> {
+        self.image_height.map(|height| height as u32)
+    }
+
+    pub fn provider_name(&self) -> Option<&str> {
+        self.provider_name.as_ref().map(|s| s.as_ref())
+    }
+
+    pub fn provider_url(&self) -> Option<&Url> {
+        self.provider_url.as_ref()
+    }
+
+    pub fn fetched_at(&self) -> DateTime<Utc> {
+        self.fetched_at
+    }
+
+    /// The language of the linked page, or `und` if the page didn't say.
+    pub fn language(&self) -> &Lang {
+        &self.language
+    }
+
+    /// Get the card for the given URL, fetching it if no post has linked to it yet.
+    ///
+    /// Pages that don't describe themselves still get a card without any metadata, so they
+    /// aren't fetched again.
+    pub fn for_url<F: CardFetcher>(
+        url: Url,
+        fetcher: &F,
+        conn: &PgConnection,
+    ) -> Result<LinkCard, LinkCardError> {
+        use diesel::prelude::*;
+
+        let cached = link_cards::table
+            .filter(link_cards::dsl::url.eq(&url))
+            .get_result(conn)
+            .optional()?;
+
+        if let Some(cached) = cached {
+            return Ok(cached);
+        }
+
+        let metadata = fetcher.fetch(&url)?.unwrap_or_default();
+
+        let inserted: Option<LinkCard> = diesel::insert_into(link_cards::table)
+            .values(&NewLinkCard::new(Url(url.0.clone()), metadata))
+            .on_conflict_do_nothing()
+            .get_result(conn)
+            .optional()?;
+
+        match inserted {
+            Some(card) => Ok(card),
+            // Someone else fetched the same page at the same time
+            None => Ok(link_cards::table
+                .filter(link_cards::dsl::url.eq(&url))
+                .get_result(conn)?),
+        }
+    }
+}
+
+/// Find the pages linked from a post's content, and attach a card for each of them to the post.
+///
+/// Mentions and hashtags are not treated as links to pages.
+pub fn attach_cards<F: CardFetcher>(
+    base_post: &BasePost,
+    post: &Post,
+    fetcher: &F,
+    conn: &PgConnection,
+) -> Result<Vec<(Link, LinkCard)>, LinkCardError> {
+    use diesel::prelude::*;
+
+    extract_urls(post.content())
+        .into_iter()
+        .map(|url| -> Result<(Link, LinkCard), LinkCardError> {
+            let card = LinkCard::for_url(url, fetcher, conn)?;
+
+            let link = diesel::insert_into(links::table)
+                .values(&NewLink::from_card(&card, base_post))
+                .get_result(conn)?;
+
+            Ok((link, card))
+        })
+        .collect()
+}
+
+/// Find the URLs of the pages linked from sanitized HTML content, in order and without
+/// duplicates.
+///
+/// URLs too long to be recorded are skipped.
+pub fn extract_urls(content: &str) -> Vec<Url> {
+    let mut seen = HashSet::new();
+
+    ANCHOR
+        .find_iter(content)
+        .filter_map(|anchor| {
+            let mut href = None;
+            let mut class = String::new();
+
+            for attribute in ATTRIBUTE.captures_iter(anchor.as_str()) {
+                let value = attribute
+                    .get(2)
+                    .or_else(|| attribute.get(3))
+                    .map(|value| unescape(value.as_str()));
+
+                match attribute[1].to_lowercase().as_ref() {
+                    "href" => href = value,
+                    "class" => class = value.unwrap_or_default(),
+                    _ => (),
+                }
+            }
+
+            if class.split_whitespace().any(|class| class == "mention") {
+                return None;
+            }
+
+            href?.parse::<OrigUrl>().ok()
+        })
+        .filter(|url| url.scheme() == "http" || url.scheme() == "https")
+        .filter(|url| url.as_str().len() <= MAX_URL_LENGTH)
+        .filter(|url| seen.insert(url.as_str().to_owned()))
+        .map(Url)
+        .collect()
+}
+
+fn unescape(s: &str) -> String {
+    s.replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#x27;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+/// Cut a string down to at most `max` characters.
+fn truncate(mut s: String, max: usize) -> String {
+    if let Some((index, _)) = s.char_indices().nth(max) {
+        s.truncate(index);
+    }
+
+    s
+}
+
+/// Keep a width or height only if it fits in its column.
+fn fit_dimension(value: u64) -> Option<u32> {
+    if value <= i32::max_value() as u64 {
+        Some(value as u32)
+    } else {
+        None
+    }
+}
+
+/// Read a language tag, dropping it if it's invalid or too long to be recorded.
+fn language(tag: &str) -> Option<Lang> {
+    tag.trim()
+        .parse::<Lang>()
+        .ok()
+        .and_then(|lang| {
+            if format!("{}", lang).len() <= MAX_LANGUAGE_LENGTH {
+                Some(lang)
+            } else {
+                None
+            }
+        })
+}
+
+/// Drop a URL that's too long to be recorded.
+fn limit_url(url: Option<Url>) -> Option<Url> {
+    url.and_then(|url| {
+        if url.0.as_str().len() <= MAX_URL_LENGTH {
+            Some(url)
+        } else {
+            None
+        }
+    })
+}
+
+#[derive(Insertable)]
+#[table_name = "link_cards"]
+struct NewLinkCard {
+    url: Url,
+    title: Option<String>,
+    description: Option<String>,
+    image_url: Option<Url>,
+    image_width: Option<i32>,
+    image_height: Option<i32>,
+    provider_name: Option<String>,
+    provider_url: Option<Url>,
+    fetched_at: DateTime<Utc>,
+    language: Lang,
+}
+
+impl NewLinkCard {
+    /// Metadata comes from remote pages, so text is cut down to fit its columns, and URLs that
+    /// are too long are dropped.
+    fn new(url: Url, metadata: CardMetadata) -> Self {
+        NewLinkCard {
+            url,
+            title: metadata.title.map(|title| truncate(title, MAX_TITLE_LENGTH)),
+            description: metadata
+                .description
+                .map(|description| truncate(description, MAX_DESCRIPTION_LENGTH)),
+            image_url: limit_url(metadata.image_url),
+            image_width: metadata
+                .image_width
+                .and_then(|width| fit_dimension(u64::from(width)))
+                .map(|width| width as i32),
+            image_height: metadata
+                .image_height
+                .and_then(|height| fit_dimension(u64::from(height)))
+                .map(|height| height as i32),
+            provider_name: metadata
+                .provider_name
+                .map(|name| truncate(name, MAX_PROVIDER_NAME_LENGTH)),
+            provider_url: limit_url(metadata.provider_url),
+            fetched_at: Utc::now(),
+            language: metadata.language.unwrap_or_else(Lang::undetermined),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{extract_urls, truncate, CardMetadata};
+    use sql_types::Url;
+
+    fn page() -> Url {
+        Url("https://news.example/articles/1".parse().unwrap())
+    }
+
+    #[test]
+    fn read_opengraph_tags() {
+        let html = r#"<html><head>
+            <meta property="og:title" content="Aardwolves &amp; you">
+            <meta name="description" content="Fallback">
+            <meta property="og:image" content="/images/aardwolf.png">
+            <meta property="og:image:width" content="640">
+            <meta property="og:site_name" content='News'>
+            </head></html>"#;
+
+        let metadata = CardMetadata::from_opengraph(&page(), html).unwrap();
+
+        assert_eq!(metadata.title.as_ref().unwrap(), "Aardwolves & you");
+        assert_eq!(metadata.description.as_ref().unwrap(), "Fallback");
+        assert_eq!(
+            metadata.image_url.unwrap().0.as_str(),
+            "https://news.example/images/aardwolf.png"
+        );
+        assert_eq!(metadata.image_width, Some(640));
+        assert_eq!(metadata.provider_name.as_ref().unwrap(), "News");
+        assert_eq!(metadata.provider_url.unwrap().0.as_str(), "https://news.example/");
+    }
+
+    #[test]
+    fn dont_read_untitled_page() {
+        let html = r#"<meta name="description" content="No title">"#;
+
+        assert!(CardMetadata::from_opengraph(&page(), html).is_none());
+    }
+
+    #[test]
+    fn read_page_language() {
+        let localized = r#"<html lang="fr"><head>
+            <meta property="og:title" content="Protèles">
+            <meta property="og:locale" content="fr_CA">
+            </head></html>"#;
+        let unlocalized = r#"<html class="page" lang='de'><head>
+            <meta property="og:title" content="Erdwölfe">
+            </head></html>"#;
+
+        let metadata = CardMetadata::from_opengraph(&page(), localized).unwrap();
+        assert_eq!(format!("{}", metadata.language.unwrap()), "fr-CA");
+
+        let metadata = CardMetadata::from_opengraph(&page(), unlocalized).unwrap();
+        assert_eq!(format!("{}", metadata.language.unwrap()), "de");
+    }
+
+    #[test]
+    fn drop_oversized_dimensions() {
+        let response = json!({
+            "title": "Huge",
+            "thumbnail_width": 4_294_967_296u64,
+            "thumbnail_height": 2_147_483_648u64,
+        });
+
+        let metadata = CardMetadata::from_oembed(&response).unwrap();
+
+        assert!(metadata.image_width.is_none());
+        assert!(metadata.image_height.is_none());
+    }
+
+    #[test]
+    fn read_oembed_response() {
+        let response = json!({
+            "type": "video",
+            "title": "Aardwolf documentary",
+            "provider_name": "Videos",
+            "thumbnail_url": "https://videos.example/thumb.jpg",
+            "thumbnail_height": 360,
+        });
+
+        let metadata = CardMetadata::from_oembed(&response).unwrap();
+
+        assert_eq!(metadata.title.as_ref().unwrap(), "Aardwolf documentary");
+        assert_eq!(metadata.image_height, Some(360));
+        assert!(metadata.image_width.is_none());
+    }
+
+    #[test]
+    fn extract_page_links_only() {
+        let content = "<p><a href=\"https://news.example/?a=1&amp;b=2\" rel=\"nofollow\">news</a> \
+                       <span class=\"h-card\"><a href=\"https://social.example/users/riley\" \
+                       class=\"u-url mention\">@riley</a></span> \
+                       <a href=\"https://social.example/tags/rust\" class=\"mention hashtag\">#rust</a> \
+                       <a href=\"https://news.example/?a=1&amp;b=2\">again</a> \
+                       <a href=\"mailto:riley@example.com\">mail</a></p>";
+
+        let urls = extract_urls(content);
+
+        assert_eq!(urls.len(), 1);
+        assert_eq!(urls[0].0.as_str(), "https://news.example/?a=1&b=2");
+    }
+
+    #[test]
+    fn truncate_on_character_boundaries() {
+        assert_eq!(truncate("aardwölf".to_owned(), 5), "aardw");
+        assert_eq!(truncate("aardwölf".to_owned(), 6), "aardwö");
+        assert_eq!(truncate("aardwolf".to_owned(), 256), "aardwolf");
+    }
+}
@@ -0,0 +1,110 @@
+use sql_types::{Lang, Url};
+
+use base_post::BasePost;
+use schema::links;
+use self::card::LinkCard;
+
+pub mod card;
+
+#[derive(Debug, Identifiable, Queryable)]
+#[table_name = "links"]
+pub struct Link {
+    id: i32,
+    href: Url, // max_length: 2048
+    href_lang: Lang,
+    height: Option<i32>,
+    width: Option<i32>,
+    preview: Option<String>,
+    base_post: i32,         // foreign key to BasePost
+    link_card: Option<i32>, // foreign key to LinkCard
+}
+
+impl Link {
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+
+    pub fn href(&self) -> &Url {
+        &self.href
+    }
+
+    pub fn href_lang(&self) -> &Lang {
+        &self.href_lang
+    }
+
+    pub fn height(&self) -> Option<u32> {
+        self.height.map(|height| height as u32)
+    }
+
+    pub fn width(&self) -> Option<u32> {
+        self.width.map(|width| width as u32)
+    }
+
+    pub fn preview(&self) -> Option<&str> {
+        self.preview.as_ref().map(|s| s.as_ref())
+    }
+
+    pub fn base_post(&self) -> i32 {
+        self.base_post
+    }
+
+    pub fn link_card(&self) -> Option<i32> {
+        self.link_card
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "links"]
+pub struct NewLink {
+    href: Url,
+    href_lang: Lang,
+    height: Option<i32>,
+    width: Option<i32>,
+    preview: Option<String>,
+    base_post: i32,
+    link_card: Option<i32>,
+}
+
+impl NewLink {
+    pub fn new<U: Into<Url>>(
+        href: U,
+        href_lang: Lang,
+        height: Option<u32>,
+        width: Option<u32>,
+        preview: Option<String>,
+        base_post: &BasePost,
+    ) -> Self {
+        NewLink {
+            href: href.into(),
+            href_lang,
+            height: height.and_then(fit_dimension),
+            width: width.and_then(fit_dimension),
+            preview,
+            base_post: base_post.id(),
+            link_card: None,
+        }
+    }
+
+    /// Create a `NewLink` previewing the page described by a `LinkCard`, in the language of that
+    /// page.
+    pub fn from_card(card: &LinkCard, base_post: &BasePost) -> Self {
+        NewLink {
+            href: Url(card.url().0.clone()),
+            href_lang: card.language().clone(),
+            height: card.image_height().and_then(fit_dimension),
+            width: card.image_width().and_then(fit_dimension),
+            preview: card.description().map(|description| description.to_owned()),
+            base_post: base_post.id(),
+            link_card: Some(card.id()),
+        }
+    }
+}
+
+/// Keep a width or height only if it fits in its column.
+fn fit_dimension(value: u32) -> Option<i32> {
+    if value <= i32::max_value() as u32 {
+        Some(value as i32)
+    } else {
+        None
+    }
+}
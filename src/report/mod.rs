@@ -0,0 +1,574 @@
+use chrono::DateTime;
+use chrono::offset::Utc;
+use diesel;
+use diesel::pg::PgConnection;
+use serde_json::Value;
+
+use activity;
+use audit_log::AuditLogEntry;
+use base_actor::BaseActor;
+use base_post::BasePost;
+use domain_block::{DomainBlock, InstanceBlocker};
+use schema::reports;
+use self::note::{NewReportNote, ReportNote};
+use sql_types::{AuditAction, DomainBlockSeverity, ReportCategory, ReportStatus, UserBanKind};
+use timer::Timer;
+use user::QueriedUser;
+use user::ban::{UserBan, UserBanError, UserBanner};
+
+pub mod note;
+
+/// A complaint about an actor, or about one of their posts, for moderators to review.
+#[derive(Debug, Identifiable, Queryable)]
+#[table_name = "reports"]
+pub struct Report {
+    id: i32,
+    reporter: Option<i32>,    // foreign key to BaseActor
+    target_actor: i32,        // foreign key to BaseActor
+    target_post: Option<i32>, // foreign key to BasePost
+    category: ReportCategory,
+    comment: String,
+    forward: bool,
+    status: ReportStatus,
+    assigned_to: Option<i32>,  // foreign key to User
+    user_ban: Option<i32>,     // foreign key to UserBan
+    domain_block: Option<i32>, // foreign key to DomainBlock
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl Report {
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+
+    /// The actor who made this report, if they still exist.
+    pub fn reporter(&self) -> Option<i32> {
+        self.reporter
+    }
+
+    /// The reported actor. For reports about a post, this is the post's author.
+    pub fn target_actor(&self) -> i32 {
+        self.target_actor
+    }
+
+    pub fn target_post(&self) -> Option<i32> {
+        self.target_post
+    }
+
+    pub fn category(&self) -> ReportCategory {
+        self.category
+    }
+
+    pub fn comment(&self) -> &str {
+        &self.comment
+    }
+
+    /// Whether the reporter asked for this report to be sent to the reported actor's instance.
+    pub fn forward(&self) -> bool {
+        self.forward
+    }
+
+    pub fn status(&self) -> ReportStatus {
+        self.status
+    }
+
+    /// The moderator handling this report.
+    pub fn assigned_to(&self) -> Option<i32> {
+        self.assigned_to
+    }
+
+    /// The ban issued when this report was resolved.
+    pub fn user_ban(&self) -> Option<i32> {
+        self.user_ban
+    }
+
+    /// The domain block issued when this report was resolved.
+    pub fn domain_block(&self) -> Option<i32> {
+        self.domain_block
+    }
+
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+
+    pub fn updated_at(&self) -> DateTime<Utc> {
+        self.updated_at
+    }
+
+    /// The moderation state of this report, as recorded in the audit log.
+    pub(crate) fn audit_json(&self) -> Value {
+        json!({
+            "status": format!("{}", self.status),
+            "assigned_to": self.assigned_to,
+            "user_ban": self.user_ban,
+            "domain_block": self.domain_block,
+        })
+    }
+
+    /// Produce the `Flag` activity that forwards this report to the reported actor's instance.
+    ///
+    /// Returns `None` when the reporter didn't ask for the report to be forwarded, or when the
+    /// reported actor is local. The activity is sent as `instance_actor` so the reporter isn't
+    /// revealed to the remote instance.
+    pub fn flag_activity(
+        &self,
+        instance_actor: &BaseActor,
+        conn: &PgConnection,
+    ) -> Result<Option<Value>, diesel::result::Error> {
+        use schema::{base_actors, base_posts};
+        use diesel::prelude::*;
+
+        if !self.forward {
+            return Ok(None);
+        }
+
+        let target: BaseActor = base_actors::table.find(self.target_actor).get_result(conn)?;
+
+        if target.local_user().is_some() {
+            return Ok(None);
+        }
+
+        let post: Option<BasePost> = match self.target_post {
+            Some(id) => base_posts::table.find(id).get_result(conn).optional()?,
+            None => None,
+        };
+
+        Ok(Some(activity::flag(
+            instance_actor,
+            &target,
+            post.as_ref(),
+            &self.comment,
+        )))
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "reports"]
+pub struct NewReport {
+    reporter: Option<i32>,
+    target_actor: i32,
+    target_post: Option<i32>,
+    category: ReportCategory,
+    comment: String,
+    forward: bool,
+    status: ReportStatus,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl NewReport {
+    pub fn for_actor(
+        reporter: &BaseActor,
+        target_actor: &BaseActor,
+        category: ReportCategory,
+        comment: String,
+        forward: bool,
+    ) -> Self {
+        let now = Utc::now();
+
+        NewReport {
+            reporter: Some(reporter.id()),
+            target_actor: target_actor.id(),
+            target_post: None,
+            category,
+            comment,
+            forward,
+            status: ReportStatus::Open,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    pub fn for_post(
+        reporter: &BaseActor,
+        target_post: &BasePost,
+        category: ReportCategory,
+        comment: String,
+        forward: bool,
+    ) -> Self {
+        let now = Utc::now();
+
+        NewReport {
+            reporter: Some(reporter.id()),
+            target_actor: target_post.posted_by(),
+            target_post: Some(target_post.id()),
+            category,
+            comment,
+            forward,
+            status: ReportStatus::Open,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
+pub struct Reporter<'a>(&'a BaseActor);
+
+impl<'a> Reporter<'a> {
+    pub(crate) fn new(base_actor: &BaseActor) -> Reporter {
+        Reporter(base_actor)
+    }
+
+    /// Report an actor to the moderators.
+    ///
+    /// If `forward` is true and the actor is remote, the report can also be sent to their
+    /// instance with `Report::flag_activity`.
+    pub fn report_actor(
+        &self,
+        target_actor: &BaseActor,
+        category: ReportCategory,
+        comment: String,
+        forward: bool,
+        conn: &PgConnection,
+    ) -> Result<Report, diesel::result::Error> {
+        use schema::reports;
+        use diesel::prelude::*;
+
+        diesel::insert_into(reports::table)
+            .values(&NewReport::for_actor(
+                self.0,
+                target_actor,
+                category,
+                comment,
+                forward,
+            ))
+            .get_result(conn)
+    }
+
+    /// Report a post, and its author, to the moderators.
+    pub fn report_post(
+        &self,
+        target_post: &BasePost,
+        category: ReportCategory,
+        comment: String,
+        forward: bool,
+        conn: &PgConnection,
+    ) -> Result<Report, diesel::result::Error> {
+        use schema::reports;
+        use diesel::prelude::*;
+
+        diesel::insert_into(reports::table)
+            .values(&NewReport::for_post(
+                self.0,
+                target_post,
+                category,
+                comment,
+                forward,
+            ))
+            .get_result(conn)
+    }
+}
+
+pub struct ReportModerator(i32);
+
+impl ReportModerator {
+    pub(crate) fn new(moderator: i32) -> ReportModerator {
+        ReportModerator(moderator)
+    }
+
+    /// Fetch a page of reports, oldest first.
+    ///
+    /// When `status` is `None`, only reports that still need attention are returned.
+    pub fn queue(
+        &self,
+        status: Option<ReportStatus>,
+        limit: i64,
+        offset: i64,
+        conn: &PgConnection,
+    ) -> Result<Vec<Report>, diesel::result::Error> {
+        use schema::reports;
+        use diesel::prelude::*;
+
+        let statuses = match status {
+            Some(status) => vec![status],
+            None => vec![ReportStatus::Open, ReportStatus::InProgress],
+        };
+
+        reports::table
+            .filter(reports::dsl::status.eq_any(statuses))
+            .order(reports::dsl::created_at.asc())
+            .limit(limit)
+            .offset(offset)
+            .load(conn)
+    }
+
+    /// Fetch a page of the reports assigned to this moderator that still need attention.
+    pub fn assigned(
+        &self,
+        limit: i64,
+        offset: i64,
+        conn: &PgConnection,
+    ) -> Result<Vec<Report>, diesel::result::Error> {
+        use schema::reports;
+        use diesel::prelude::*;
+
+        reports::table
+            .filter(reports::dsl::assigned_to.eq(self.0))
+            .filter(reports::dsl::status.eq(ReportStatus::InProgress))
+            .order(reports::dsl::created_at.asc())
+            .limit(limit)
+            .offset(offset)
+            .load(conn)
+    }
+
+    /// Take responsibility for an open report.
+    pub fn assign(&self, report: Report, conn: &PgConnection) -> Result<Report, ReportError> {
+        use schema::reports;
+        use diesel::prelude::*;
+
+        self.check_transition(&report, ReportStatus::InProgress)?;
+
+        conn.transaction(|| {
+            let updated = diesel::update(&report)
+                .set((
+                    reports::dsl::status.eq(ReportStatus::InProgress),
+                    reports::dsl::assigned_to.eq(self.0),
+                    reports::dsl::updated_at.eq(Utc::now()),
+                ))
+                .get_result(conn)?;
+
+            self.audit(&report, &updated, conn)?;
+
+            Ok(updated)
+        })
+    }
+
+    /// Put a report back in the queue for another moderator to pick up.
+    pub fn unassign(&self, report: Report, conn: &PgConnection) -> Result<Report, ReportError> {
+        use schema::reports;
+        use diesel::prelude::*;
+
+        self.check_transition(&report, ReportStatus::Open)?;
+
+        conn.transaction(|| {
+            let updated = diesel::update(&report)
+                .set((
+                    reports::dsl::status.eq(ReportStatus::Open),
+                    reports::dsl::assigned_to.eq(None::<i32>),
+                    reports::dsl::updated_at.eq(Utc::now()),
+                ))
+                .get_result(conn)?;
+
+            self.audit(&report, &updated, conn)?;
+
+            Ok(updated)
+        })
+    }
+
+    /// Close a report without taking any action.
+    pub fn dismiss(&self, report: Report, conn: &PgConnection) -> Result<Report, ReportError> {
+        self.close(report, ReportStatus::Dismissed, conn)
+    }
+
+    /// Close a report after dealing with it outside of the moderation queue.
+    pub fn resolve(&self, report: Report, conn: &PgConnection) -> Result<Report, ReportError> {
+        self.close(report, ReportStatus::Resolved, conn)
+    }
+
+    /// Close a report by banning the local user the reported actor belongs to.
+    pub fn resolve_with_ban(
+        &self,
+        report: Report,
+        banner: &UserBanner,
+        kind: UserBanKind,
+        reason: String,
+        until: Option<&Timer>,
+        conn: &PgConnection,
+    ) -> Result<(Report, UserBan), ReportError> {
+        use schema::{base_actors, reports, users};
+        use diesel::prelude::*;
+
+        self.check_transition(&report, ReportStatus::Resolved)?;
+
+        conn.transaction(|| {
+            let user_id = base_actors::table
+                .find(report.target_actor())
+                .select(base_actors::dsl::local_user)
+                .get_result::<Option<i32>>(conn)?
+                .ok_or(ReportError::RemoteActor)?;
+
+            let user: QueriedUser = users::table.find(user_id).get_result(conn)?;
+
+            let ban = banner.ban(&user, kind, reason, until, conn)?;
+
+            let updated = diesel::update(&report)
+                .set((
+                    reports::dsl::status.eq(ReportStatus::Resolved),
+                    reports::dsl::user_ban.eq(ban.id()),
+                    reports::dsl::updated_at.eq(Utc::now()),
+                ))
+                .get_result(conn)?;
+
+            self.audit(&report, &updated, conn)?;
+
+            Ok((updated, ban))
+        })
+    }
+
+    /// Close a report by blocking the domain the reported remote actor lives on.
+    pub fn resolve_with_domain_block(
+        &self,
+        report: Report,
+        blocker: &InstanceBlocker,
+        severity: DomainBlockSeverity,
+        public_reason: Option<String>,
+        private_reason: Option<String>,
+        conn: &PgConnection,
+    ) -> Result<(Report, DomainBlock), ReportError> {
+        use schema::{base_actors, reports};
+        use diesel::prelude::*;
+
+        self.check_transition(&report, ReportStatus::Resolved)?;
+
+        conn.transaction(|| {
+            let domain = base_actors::table
+                .find(report.target_actor())
+                .select(base_actors::dsl::domain)
+                .get_result::<Option<String>>(conn)?
+                .ok_or(ReportError::LocalActor)?;
+
+            let domain_block =
+                blocker.block_domain(&domain, severity, public_reason, private_reason, conn)?;
+
+            let updated = diesel::update(&report)
+                .set((
+                    reports::dsl::status.eq(ReportStatus::Resolved),
+                    reports::dsl::domain_block.eq(domain_block.id()),
+                    reports::dsl::updated_at.eq(Utc::now()),
+                ))
+                .get_result(conn)?;
+
+            self.audit(&report, &updated, conn)?;
+
+            Ok((updated, domain_block))
+        })
+    }
+
+    /// Reopen a closed report.
+    pub fn reopen(&self, report: Report, conn: &PgConnection) -> Result<Report, ReportError> {
+        use schema::reports;
+        use diesel::prelude::*;
+
+        if report.status().is_pending() {
+            return Err(ReportError::Transition(report.status(), ReportStatus::Open));
+        }
+
+        conn.transaction(|| {
+            let updated = diesel::update(&report)
+                .set((
+                    reports::dsl::status.eq(ReportStatus::Open),
+                    reports::dsl::assigned_to.eq(None::<i32>),
+                    reports::dsl::updated_at.eq(Utc::now()),
+                ))
+                .get_result(conn)?;
+
+            self.audit(&report, &updated, conn)?;
+
+            Ok(updated)
+        })
+    }
+
+    pub fn notes(
+        &self,
+        report: &Report,
+        conn: &PgConnection,
+    ) -> Result<Vec<ReportNote>, diesel::result::Error> {
+        use schema::report_notes;
+        use diesel::prelude::*;
+
+        report_notes::table
+            .filter(report_notes::dsl::report_id.eq(report.id()))
+            .order(report_notes::dsl::created_at.asc())
+            .load(conn)
+    }
+
+    pub fn add_note(
+        &self,
+        report: &Report,
+        body: String,
+        conn: &PgConnection,
+    ) -> Result<ReportNote, diesel::result::Error> {
+        use schema::report_notes;
+        use diesel::prelude::*;
+
+        diesel::insert_into(report_notes::table)
+            .values(&NewReportNote::new(report, self.0, body))
+            .get_result(conn)
+    }
+
+    fn close(
+        &self,
+        report: Report,
+        status: ReportStatus,
+        conn: &PgConnection,
+    ) -> Result<Report, ReportError> {
+        use schema::reports;
+        use diesel::prelude::*;
+
+        self.check_transition(&report, status)?;
+
+        conn.transaction(|| {
+            let updated = diesel::update(&report)
+                .set((
+                    reports::dsl::status.eq(status),
+                    reports::dsl::updated_at.eq(Utc::now()),
+                ))
+                .get_result(conn)?;
+
+            self.audit(&report, &updated, conn)?;
+
+            Ok(updated)
+        })
+    }
+
+    fn audit(
+        &self,
+        before: &Report,
+        after: &Report,
+        conn: &PgConnection,
+    ) -> Result<(), diesel::result::Error> {
+        AuditLogEntry::record(
+            Some(self.0),
+            AuditAction::UpdateReport,
+            Some(after.id()),
+            Some(before.audit_json()),
+            Some(after.audit_json()),
+            conn,
+        )
+    }
+
+    fn check_transition(&self, report: &Report, next: ReportStatus) -> Result<(), ReportError> {
+        if !report.status().can_transition_to(next) {
+            return Err(ReportError::Transition(report.status(), next));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Fail)]
+pub enum ReportError {
+    #[fail(display = "Error managing report")]
+    Diesel(#[cause] diesel::result::Error),
+    #[fail(display = "Report cannot move from {} to {}", _0, _1)]
+    Transition(ReportStatus, ReportStatus),
+    #[fail(display = "Reported actor is remote and can't be banned")]
+    RemoteActor,
+    #[fail(display = "Reported actor is local and has no domain to block")]
+    LocalActor,
+    #[fail(display = "Error banning reported user: {}", _0)]
+    Ban(#[cause] UserBanError),
+}
+
+impl From<diesel::result::Error> for ReportError {
+    fn from(e: diesel::result::Error) -> Self {
+        ReportError::Diesel(e)
+    }
+}
+
+impl From<UserBanError> for ReportError {
+    fn from(e: UserBanError) -> Self {
+        ReportError::Ban(e)
+    }
+}
@@ -0,0 +1,59 @@
+use chrono::DateTime;
+use chrono::offset::Utc;
+
+use report::Report;
+use schema::report_notes;
+
+/// A moderator's note on a report, for coordinating with other moderators.
+#[derive(Debug, Identifiable, Queryable)]
+#[table_name = "report_notes"]
+pub struct ReportNote {
+    id: i32,
+    report_id: i32,      // foreign key to Report
+    author: Option<i32>, // foreign key to User
+    body: String,
+    created_at: DateTime<Utc>,
+}
+
+impl ReportNote {
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+
+    pub fn report_id(&self) -> i32 {
+        self.report_id
+    }
+
+    /// The moderator who wrote this note, if they still exist.
+    pub fn author(&self) -> Option<i32> {
+        self.author
+    }
+
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "report_notes"]
+pub struct NewReportNote {
+    report_id: i32,
+    author: Option<i32>,
+    body: String,
+    created_at: DateTime<Utc>,
+}
+
+impl NewReportNote {
+    pub fn new(report: &Report, author: i32, body: String) -> Self {
+        NewReportNote {
+            report_id: report.id(),
+            author: Some(author),
+            body,
+            created_at: Utc::now(),
+        }
+    }
+}
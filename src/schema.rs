@@ -1,3 +1,37 @@
+table! {
+    actor_delegations (id) {
+        id -> Int4,
+        base_actor -> Int4,
+        user_id -> Int4,
+        granted_by -> Nullable<Int4>,
+        created_at -> Timestamptz,
+    }
+}
+
+table! {
+    actor_restrictions (id) {
+        id -> Int4,
+        base_actor -> Int4,
+        permission_id -> Int4,
+        restricted_by -> Nullable<Int4>,
+        reason -> Nullable<Text>,
+        created_at -> Timestamptz,
+    }
+}
+
+table! {
+    audit_log (id) {
+        id -> Int4,
+        actor -> Nullable<Int4>,
+        action -> Varchar,
+        target_id -> Nullable<Int4>,
+        before -> Nullable<Jsonb>,
+        after -> Nullable<Jsonb>,
+        created_at -> Timestamptz,
+        target_type -> Varchar,
+    }
+}
+
 table! {
     base_actors (id) {
         id -> Int4,
@@ -8,6 +42,7 @@ table! {
         local_user -> Nullable<Int4>,
         follow_policy -> Varchar,
         original_json -> Jsonb,
+        domain -> Nullable<Varchar>,
     }
 }
 
@@ -20,6 +55,17 @@ table! {
         icon -> Nullable<Int4>,
         visibility -> Varchar,
         original_json -> Jsonb,
+        summary -> Nullable<Text>,
+        sensitive -> Bool,
+    }
+}
+
+table! {
+    blocks (id) {
+        id -> Int4,
+        blocker -> Int4,
+        blocked -> Int4,
+        created_at -> Timestamptz,
     }
 }
 
@@ -32,6 +78,34 @@ table! {
     }
 }
 
+table! {
+    conversation_participants (id) {
+        id -> Int4,
+        conversation_id -> Int4,
+        base_actor_id -> Int4,
+        last_read_at -> Nullable<Timestamptz>,
+        muted -> Bool,
+        left_conversation -> Bool,
+    }
+}
+
+table! {
+    conversation_posts (id) {
+        id -> Int4,
+        conversation_id -> Int4,
+        base_post_id -> Int4,
+        created_at -> Timestamptz,
+    }
+}
+
+table! {
+    conversations (id) {
+        id -> Int4,
+        created_at -> Timestamptz,
+        last_post_at -> Timestamptz,
+    }
+}
+
 table! {
     direct_posts (id) {
         id -> Int4,
@@ -40,6 +114,18 @@ table! {
     }
 }
 
+table! {
+    domain_blocks (id) {
+        id -> Int4,
+        domain -> Varchar,
+        severity -> Varchar,
+        public_reason -> Nullable<Text>,
+        private_reason -> Nullable<Text>,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
 table! {
     emails (id) {
         id -> Int4,
@@ -70,10 +156,25 @@ table! {
     }
 }
 
+table! {
+    file_owners (id) {
+        id -> Int4,
+        file_id -> Int4,
+        user_id -> Int4,
+        created_at -> Timestamptz,
+    }
+}
+
 table! {
     files (id) {
         id -> Int4,
-        file_path -> Varchar,
+        storage_key -> Varchar,
+        content_hash -> Nullable<Varchar>,
+        size -> Int8,
+        media_type -> Varchar,
+        created_at -> Timestamptz,
+        owner -> Nullable<Int4>,
+        last_stored_at -> Timestamptz,
     }
 }
 
@@ -98,6 +199,47 @@ table! {
         id -> Int4,
         group_id -> Int4,
         base_actor_id -> Int4,
+        role -> Varchar,
+        created_at -> Timestamptz,
+    }
+}
+
+table! {
+    group_bans (id) {
+        id -> Int4,
+        group_id -> Int4,
+        base_actor_id -> Int4,
+        banned_by -> Nullable<Int4>,
+        reason -> Nullable<Text>,
+        created_at -> Timestamptz,
+    }
+}
+
+table! {
+    group_invitations (id) {
+        id -> Int4,
+        group_id -> Int4,
+        invited_actor -> Int4,
+        invited_by -> Int4,
+        created_at -> Timestamptz,
+    }
+}
+
+table! {
+    group_join_requests (id) {
+        id -> Int4,
+        group_id -> Int4,
+        base_actor_id -> Int4,
+        created_at -> Timestamptz,
+    }
+}
+
+table! {
+    group_posts (id) {
+        id -> Int4,
+        group_id -> Int4,
+        base_post_id -> Int4,
+        created_at -> Timestamptz,
     }
 }
 
@@ -105,6 +247,7 @@ table! {
     groups (id) {
         id -> Int4,
         base_actor_id -> Int4,
+        join_policy -> Varchar,
     }
 }
 
@@ -114,6 +257,24 @@ table! {
         width -> Int4,
         height -> Int4,
         file_id -> Int4,
+        variant -> Varchar,
+        original_image -> Nullable<Int4>,
+    }
+}
+
+table! {
+    link_cards (id) {
+        id -> Int4,
+        url -> Varchar,
+        title -> Nullable<Text>,
+        description -> Nullable<Text>,
+        image_url -> Nullable<Varchar>,
+        image_width -> Nullable<Int4>,
+        image_height -> Nullable<Int4>,
+        provider_name -> Nullable<Varchar>,
+        provider_url -> Nullable<Varchar>,
+        fetched_at -> Timestamptz,
+        language -> Varchar,
     }
 }
 
@@ -126,6 +287,25 @@ table! {
         width -> Nullable<Int4>,
         preview -> Nullable<Text>,
         base_post -> Int4,
+        link_card -> Nullable<Int4>,
+    }
+}
+
+table! {
+    list_members (id) {
+        id -> Int4,
+        list_id -> Int4,
+        base_actor_id -> Int4,
+        created_at -> Timestamptz,
+    }
+}
+
+table! {
+    lists (id) {
+        id -> Int4,
+        owner -> Int4,
+        name -> Varchar,
+        created_at -> Timestamptz,
     }
 }
 
@@ -143,6 +323,22 @@ table! {
         id -> Int4,
         file_id -> Int4,
         post_id -> Int4,
+        position -> Int4,
+        attachment_type -> Varchar,
+        description -> Nullable<Text>,
+        focal_x -> Nullable<Float4>,
+        focal_y -> Nullable<Float4>,
+        blurhash -> Nullable<Varchar>,
+    }
+}
+
+table! {
+    mutes (id) {
+        id -> Int4,
+        muter -> Int4,
+        muted -> Int4,
+        timer_id -> Nullable<Int4>,
+        created_at -> Timestamptz,
     }
 }
 
@@ -162,6 +358,8 @@ table! {
         avatar -> Nullable<Int4>,
         shortname -> Varchar,
         base_actor -> Int4,
+        preferred_languages -> Array<Varchar>,
+        default_sensitive -> Bool,
     }
 }
 
@@ -171,6 +369,8 @@ table! {
         content -> Text,
         source -> Nullable<Text>,
         base_post -> Int4,
+        language -> Varchar,
+        source_format -> Nullable<Varchar>,
     }
 }
 
@@ -182,6 +382,45 @@ table! {
     }
 }
 
+table! {
+    remote_media (id) {
+        id -> Int4,
+        source_url -> Varchar,
+        file_id -> Int4,
+        fetched_at -> Timestamptz,
+        byte_size -> Int8,
+        last_accessed_at -> Timestamptz,
+    }
+}
+
+table! {
+    report_notes (id) {
+        id -> Int4,
+        report_id -> Int4,
+        author -> Nullable<Int4>,
+        body -> Text,
+        created_at -> Timestamptz,
+    }
+}
+
+table! {
+    reports (id) {
+        id -> Int4,
+        reporter -> Nullable<Int4>,
+        target_actor -> Int4,
+        target_post -> Nullable<Int4>,
+        category -> Varchar,
+        comment -> Text,
+        forward -> Bool,
+        status -> Varchar,
+        assigned_to -> Nullable<Int4>,
+        user_ban -> Nullable<Int4>,
+        domain_block -> Nullable<Int4>,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
 table! {
     role_permissions (id) {
         id -> Int4,
@@ -196,6 +435,21 @@ table! {
         id -> Int4,
         name -> Varchar,
         created_at -> Timestamptz,
+        storage_quota -> Nullable<Int8>,
+    }
+}
+
+table! {
+    sessions (id) {
+        id -> Int4,
+        user_id -> Int4,
+        token_hash -> Varchar,
+        ip_address -> Nullable<Varchar>,
+        user_agent -> Nullable<Text>,
+        created_at -> Timestamptz,
+        last_seen_at -> Timestamptz,
+        expires_at -> Timestamptz,
+        revoked_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -206,12 +460,28 @@ table! {
     }
 }
 
+table! {
+    user_bans (id) {
+        id -> Int4,
+        user_id -> Int4,
+        moderator -> Nullable<Int4>,
+        kind -> Varchar,
+        reason -> Text,
+        timer_id -> Nullable<Int4>,
+        created_at -> Timestamptz,
+        lifted_at -> Nullable<Timestamptz>,
+        lifted_by -> Nullable<Int4>,
+    }
+}
+
 table! {
     user_roles (id) {
         id -> Int4,
         user_id -> Int4,
         role_id -> Int4,
         created_at -> Timestamptz,
+        expires_at -> Nullable<Timestamptz>,
+        granted_by -> Nullable<Int4>,
     }
 }
 
@@ -223,55 +493,107 @@ table! {
     }
 }
 
+joinable!(actor_delegations -> base_actors (base_actor));
+joinable!(actor_restrictions -> base_actors (base_actor));
+joinable!(actor_restrictions -> permissions (permission_id));
+joinable!(actor_restrictions -> users (restricted_by));
 joinable!(base_actors -> users (local_user));
 joinable!(base_posts -> base_actors (posted_by));
 joinable!(base_posts -> images (icon));
+joinable!(conversation_participants -> base_actors (base_actor_id));
+joinable!(conversation_participants -> conversations (conversation_id));
+joinable!(conversation_posts -> base_posts (base_post_id));
+joinable!(conversation_posts -> conversations (conversation_id));
 joinable!(direct_posts -> base_actors (base_actor_id));
 joinable!(direct_posts -> base_posts (base_post_id));
 joinable!(event_notifications -> events (event_id));
 joinable!(event_notifications -> timers (timer_id));
 joinable!(events -> personas (owner));
+joinable!(file_owners -> files (file_id));
+joinable!(file_owners -> users (user_id));
+joinable!(files -> users (owner));
 joinable!(group_actors -> base_actors (base_actor_id));
 joinable!(group_actors -> groups (group_id));
+joinable!(group_bans -> groups (group_id));
+joinable!(group_invitations -> groups (group_id));
+joinable!(group_join_requests -> base_actors (base_actor_id));
+joinable!(group_join_requests -> groups (group_id));
+joinable!(group_posts -> base_posts (base_post_id));
+joinable!(group_posts -> groups (group_id));
 joinable!(groups -> base_actors (base_actor_id));
 joinable!(images -> files (file_id));
 joinable!(links -> base_posts (base_post));
+joinable!(links -> link_cards (link_card));
+joinable!(list_members -> base_actors (base_actor_id));
+joinable!(list_members -> lists (list_id));
+joinable!(lists -> base_actors (owner));
 joinable!(local_auth -> users (user_id));
 joinable!(media_posts -> files (file_id));
 joinable!(media_posts -> posts (post_id));
+joinable!(mutes -> timers (timer_id));
 joinable!(personas -> base_actors (base_actor));
 joinable!(personas -> images (avatar));
 joinable!(posts -> base_posts (base_post));
 joinable!(reactions -> comments (comment_id));
+joinable!(remote_media -> files (file_id));
+joinable!(report_notes -> reports (report_id));
+joinable!(report_notes -> users (author));
+joinable!(reports -> base_posts (target_post));
+joinable!(reports -> domain_blocks (domain_block));
+joinable!(reports -> user_bans (user_ban));
+joinable!(reports -> users (assigned_to));
 joinable!(role_permissions -> permissions (permission_id));
 joinable!(role_permissions -> roles (role_id));
+joinable!(sessions -> users (user_id));
+joinable!(user_bans -> timers (timer_id));
 joinable!(user_roles -> roles (role_id));
-joinable!(user_roles -> users (user_id));
 
 allow_tables_to_appear_in_same_query!(
+    actor_delegations,
+    actor_restrictions,
+    audit_log,
     base_actors,
     base_posts,
+    blocks,
     comments,
+    conversation_participants,
+    conversation_posts,
+    conversations,
     direct_posts,
+    domain_blocks,
     emails,
     event_notifications,
     events,
+    file_owners,
     files,
     follow_requests,
     followers,
     group_actors,
+    group_bans,
+    group_invitations,
+    group_join_requests,
+    group_posts,
     groups,
     images,
+    link_cards,
     links,
+    list_members,
+    lists,
     local_auth,
     media_posts,
+    mutes,
     permissions,
     personas,
     posts,
     reactions,
+    remote_media,
+    report_notes,
+    reports,
     role_permissions,
     roles,
+    sessions,
     timers,
+    user_bans,
     user_roles,
     users,
 );
@@ -0,0 +1,150 @@
+use diesel;
+use diesel::pg::PgConnection;
+use diesel::sql_types::{BigInt, Integer, Nullable, Text};
+
+use base_actor::BaseActor;
+use base_actor::persona::Persona;
+use base_post::BasePost;
+use base_post::post::Post;
+use sql_types::PostVisibility;
+use user::ban::not_banned_sql;
+
+/// The id of a row matched by a full-text search, in rank order.
+///
+/// Searches are run as raw SQL so the `to_tsvector` expressions can match the GIN indexes created
+/// in the `add_search_indexes` migration exactly. Only ids are returned from the ranking query,
+/// and the model types are loaded afterwards.
+#[derive(QueryableByName)]
+struct SearchHit {
+    #[sql_type = "Integer"]
+    id: i32,
+}
+
+/// Search posts by their `content` and their `BasePost`'s `name`.
+///
+/// Only posts the `searcher` is allowed to see are returned. When no `searcher` is provided,
+/// only public posts are returned. Results are ordered from most to least relevant.
+pub fn search_posts(
+    query: &str,
+    searcher: Option<&BaseActor>,
+    limit: i64,
+    offset: i64,
+    conn: &PgConnection,
+) -> Result<Vec<(BasePost, Post)>, diesel::result::Error> {
+    use schema::{base_posts, posts};
+    use diesel::prelude::*;
+
+    if query.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let sql = format!(
+        "SELECT posts.id AS id FROM posts \
+         INNER JOIN base_posts ON posts.base_post = base_posts.id \
+         WHERE (to_tsvector('english', posts.content) @@ plainto_tsquery('english', $1) \
+         OR to_tsvector('english', coalesce(base_posts.name, '')) @@ plainto_tsquery('english', $1)) \
+         AND (base_posts.visibility = '{public}' \
+         OR base_posts.posted_by = $2 \
+         OR (base_posts.visibility = '{followers}' AND EXISTS ( \
+         SELECT 1 FROM followers WHERE followers.follower = $2 \
+         AND followers.follows = base_posts.posted_by)) \
+         OR (base_posts.visibility = '{friends}' AND EXISTS ( \
+         SELECT 1 FROM followers WHERE followers.follower = $2 \
+         AND followers.follows = base_posts.posted_by) AND EXISTS ( \
+         SELECT 1 FROM followers WHERE followers.follower = base_posts.posted_by \
+         AND followers.follows = $2)) \
+         OR (base_posts.visibility = '{listed}' AND EXISTS ( \
+         SELECT 1 FROM direct_posts WHERE direct_posts.base_post_id = base_posts.id \
+         AND direct_posts.base_actor_id = $2)) \
+         OR (base_posts.visibility = '{group}' AND EXISTS ( \
+         SELECT 1 FROM group_posts INNER JOIN group_actors \
+         ON group_actors.group_id = group_posts.group_id \
+         WHERE group_posts.base_post_id = base_posts.id \
+         AND group_actors.base_actor_id = $2))) \
+         AND {not_banned} \
+         ORDER BY ts_rank(to_tsvector('english', posts.content), plainto_tsquery('english', $1)) \
+         + ts_rank(to_tsvector('english', coalesce(base_posts.name, '')), \
+         plainto_tsquery('english', $1)) DESC, posts.id DESC \
+         LIMIT $3 OFFSET $4",
+        public = PostVisibility::Public,
+        followers = PostVisibility::FollowersOnly,
+        friends = PostVisibility::FriendsOnly,
+        listed = PostVisibility::ListedPeopleOnly,
+        group = PostVisibility::GroupOnly,
+        not_banned = not_banned_sql("base_posts.posted_by"),
+    );
+
+    let ids: Vec<i32> = diesel::sql_query(sql)
+        .bind::<Text, _>(query)
+        .bind::<Nullable<Integer>, _>(searcher.map(|actor| actor.id()))
+        .bind::<BigInt, _>(limit)
+        .bind::<BigInt, _>(offset)
+        .load::<SearchHit>(conn)?
+        .into_iter()
+        .map(|hit| hit.id)
+        .collect();
+
+    let mut results: Vec<(Post, BasePost)> = posts::table
+        .inner_join(base_posts::table)
+        .filter(posts::dsl::id.eq_any(&ids))
+        .load(conn)?;
+
+    results.sort_by_key(|&(ref post, _)| ids.iter().position(|id| *id == post.id()));
+
+    Ok(results
+        .into_iter()
+        .map(|(post, base_post)| (base_post, post))
+        .collect())
+}
+
+/// Search searchable personas by their `shortname` and their `BaseActor`'s `display_name`.
+///
+/// Personas that have `is_searchable` set to false, or that belong to banned users, are never
+/// returned. Results are ordered from most to least relevant.
+pub fn search_personas(
+    query: &str,
+    limit: i64,
+    offset: i64,
+    conn: &PgConnection,
+) -> Result<Vec<(BaseActor, Persona)>, diesel::result::Error> {
+    use schema::{base_actors, personas};
+    use diesel::prelude::*;
+
+    if query.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let sql = format!(
+        "SELECT personas.id AS id FROM personas \
+         INNER JOIN base_actors ON personas.base_actor = base_actors.id \
+         WHERE personas.is_searchable AND {not_banned} \
+         AND (to_tsvector('simple', personas.shortname) @@ plainto_tsquery('simple', $1) \
+         OR to_tsvector('simple', base_actors.display_name) @@ plainto_tsquery('simple', $1)) \
+         ORDER BY ts_rank(to_tsvector('simple', personas.shortname), plainto_tsquery('simple', $1)) \
+         + ts_rank(to_tsvector('simple', base_actors.display_name), \
+         plainto_tsquery('simple', $1)) DESC, personas.id ASC \
+         LIMIT $2 OFFSET $3",
+        not_banned = not_banned_sql("base_actors.id"),
+    );
+
+    let ids: Vec<i32> = diesel::sql_query(sql)
+        .bind::<Text, _>(query)
+        .bind::<BigInt, _>(limit)
+        .bind::<BigInt, _>(offset)
+        .load::<SearchHit>(conn)?
+        .into_iter()
+        .map(|hit| hit.id)
+        .collect();
+
+    let mut results: Vec<(Persona, BaseActor)> = personas::table
+        .inner_join(base_actors::table)
+        .filter(personas::dsl::id.eq_any(&ids))
+        .load(conn)?;
+
+    results.sort_by_key(|&(ref persona, _)| ids.iter().position(|id| *id == persona.id()));
+
+    Ok(results
+        .into_iter()
+        .map(|(persona, base_actor)| (base_actor, persona))
+        .collect())
+}
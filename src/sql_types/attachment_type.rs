@@ -0,0 +1,81 @@
+use std::error::Error as StdError;
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+
+use diesel::backend::Backend;
+use diesel::deserialize;
+use diesel::serialize;
+use diesel::sql_types::Text;
+
+#[derive(AsExpression, Clone, Copy, Debug, Eq, FromSqlRow, Hash, PartialEq)]
+#[sql_type = "Text"]
+pub enum AttachmentType {
+    Image,
+    Video,
+    Audio,
+}
+
+impl fmt::Display for AttachmentType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AttachmentType::Image => write!(f, "image"),
+            AttachmentType::Video => write!(f, "video"),
+            AttachmentType::Audio => write!(f, "audio"),
+        }
+    }
+}
+
+impl FromStr for AttachmentType {
+    type Err = AttachmentTypeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "image" => Ok(AttachmentType::Image),
+            "video" => Ok(AttachmentType::Video),
+            "audio" => Ok(AttachmentType::Audio),
+            _ => Err(AttachmentTypeParseError),
+        }
+    }
+}
+
+impl<DB> serialize::ToSql<Text, DB> for AttachmentType
+where
+    DB: Backend,
+{
+    fn to_sql<W: Write>(&self, out: &mut serialize::Output<W, DB>) -> serialize::Result {
+        serialize::ToSql::<Text, DB>::to_sql(&format!("{}", self), out)
+    }
+}
+
+impl<DB> deserialize::FromSql<Text, DB> for AttachmentType
+where
+    DB: Backend<RawValue = [u8]>,
+{
+    fn from_sql(bytes: Option<&DB::RawValue>) -> deserialize::Result<Self> {
+        deserialize::FromSql::<Text, DB>::from_sql(bytes).and_then(|string: String| {
+            string
+                .parse::<AttachmentType>()
+                .map_err(|e| Box::new(e) as Box<StdError + Send + Sync>)
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct AttachmentTypeParseError;
+
+impl fmt::Display for AttachmentTypeParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Failed to parse AttachmentType")
+    }
+}
+
+impl StdError for AttachmentTypeParseError {
+    fn description(&self) -> &str {
+        "Failed to parse AttachmentType"
+    }
+
+    fn cause(&self) -> Option<&StdError> {
+        None
+    }
+}
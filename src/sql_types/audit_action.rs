@@ -0,0 +1,149 @@
+use std::error::Error as StdError;
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+
+use diesel::backend::Backend;
+use diesel::deserialize;
+use diesel::serialize;
+use diesel::sql_types::Text;
+
+use super::AuditTargetType;
+
+/// A privileged action recorded in the audit log.
+///
+/// What an entry's `target_id` refers to depends on the action, and is recorded alongside it as
+/// the entry's `target_type`.
+#[derive(AsExpression, Clone, Copy, Debug, Eq, FromSqlRow, Hash, PartialEq)]
+#[sql_type = "Text"]
+pub enum AuditAction {
+    /// A role was granted. The target is the user who received it.
+    GrantRole,
+    /// A role was revoked. The target is the user who lost it.
+    RevokeRole,
+    /// A user was banned or suspended. The target is the new `UserBan`.
+    BanUser,
+    /// A ban was lifted. The target is the `UserBan`.
+    UnbanUser,
+    /// A domain was blocked, or its block changed. The target is the `DomainBlock`.
+    BlockDomain,
+    /// A domain was unblocked. The target is the removed `DomainBlock`.
+    UnblockDomain,
+    /// A report's status changed. The target is the `Report`.
+    UpdateReport,
+    /// A role was created. The target is the new `Role`.
+    CreateRole,
+    /// A role was deleted. The target is the removed `Role`.
+    DeleteRole,
+    /// A permission was attached to a role. The target is the `Role`.
+    AddRolePermission,
+    /// A permission was removed from a role. The target is the `Role`.
+    RemoveRolePermission,
+    /// A permission was taken away from an actor. The target is the new `ActorRestriction`.
+    RestrictActor,
+    /// An actor's restriction was lifted. The target is the removed `ActorRestriction`.
+    UnrestrictActor,
+}
+
+impl AuditAction {
+    /// The kind of record this action's target is.
+    pub fn target_type(&self) -> AuditTargetType {
+        match *self {
+            AuditAction::GrantRole | AuditAction::RevokeRole => AuditTargetType::User,
+            AuditAction::BanUser | AuditAction::UnbanUser => AuditTargetType::UserBan,
+            AuditAction::BlockDomain | AuditAction::UnblockDomain => AuditTargetType::DomainBlock,
+            AuditAction::UpdateReport => AuditTargetType::Report,
+            AuditAction::CreateRole
+            | AuditAction::DeleteRole
+            | AuditAction::AddRolePermission
+            | AuditAction::RemoveRolePermission => AuditTargetType::Role,
+            AuditAction::RestrictActor | AuditAction::UnrestrictActor => {
+                AuditTargetType::ActorRestriction
+            }
+        }
+    }
+}
+
+impl fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AuditAction::GrantRole => write!(f, "grant-role"),
+            AuditAction::RevokeRole => write!(f, "revoke-role"),
+            AuditAction::BanUser => write!(f, "ban-user"),
+            AuditAction::UnbanUser => write!(f, "unban-user"),
+            AuditAction::BlockDomain => write!(f, "block-domain"),
+            AuditAction::UnblockDomain => write!(f, "unblock-domain"),
+            AuditAction::UpdateReport => write!(f, "update-report"),
+            AuditAction::CreateRole => write!(f, "create-role"),
+            AuditAction::DeleteRole => write!(f, "delete-role"),
+            AuditAction::AddRolePermission => write!(f, "add-role-permission"),
+            AuditAction::RemoveRolePermission => write!(f, "remove-role-permission"),
+            AuditAction::RestrictActor => write!(f, "restrict-actor"),
+            AuditAction::UnrestrictActor => write!(f, "unrestrict-actor"),
+        }
+    }
+}
+
+impl FromStr for AuditAction {
+    type Err = AuditActionParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "grant-role" => Ok(AuditAction::GrantRole),
+            "revoke-role" => Ok(AuditAction::RevokeRole),
+            "ban-user" => Ok(AuditAction::BanUser),
+            "unban-user" => Ok(AuditAction::UnbanUser),
+            "block-domain" => Ok(AuditAction::BlockDomain),
+            "unblock-domain" => Ok(AuditAction::UnblockDomain),
+            "update-report" => Ok(AuditAction::UpdateReport),
+            "create-role" => Ok(AuditAction::CreateRole),
+            "delete-role" => Ok(AuditAction::DeleteRole),
+            "add-role-permission" => Ok(AuditAction::AddRolePermission),
+            "remove-role-permission" => Ok(AuditAction::RemoveRolePermission),
+            "restrict-actor" => Ok(AuditAction::RestrictActor),
+            "unrestrict-actor" => Ok(AuditAction::UnrestrictActor),
+            _ => Err(AuditActionParseError),
+        }
+    }
+}
+
+impl<DB> serialize::ToSql<Text, DB> for AuditAction
+where
+    DB: Backend,
+{
+    fn to_sql<W: Write>(&self, out: &mut serialize::Output<W, DB>) -> serialize::Result {
+        serialize::ToSql::<Text, DB>::to_sql(&format!("{}", self), out)
+    }
+}
+
+impl<DB> deserialize::FromSql<Text, DB> for AuditAction
+where
+    DB: Backend<RawValue = [u8]>,
+{
+    fn from_sql(bytes: Option<&DB::RawValue>) -> deserialize::Result<Self> {
+        deserialize::FromSql::<Text, DB>::from_sql(bytes).and_then(|string: String| {
+            string
+                .parse::<AuditAction>()
+                .map_err(|e| Box::new(e) as Box<StdError + Send + Sync>)
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct AuditActionParseError;
+
+impl fmt::Display for AuditActionParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Failed to parse AuditAction")
+    }
+}
+
+impl StdError for AuditActionParseError {
+    fn description(&self) -> &str {
+        "Failed to parse AuditAction"
+    }
+
+    fn cause(&self) -> Option<&StdError> {
+        None
+    }
+}
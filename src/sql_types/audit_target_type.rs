@@ -0,0 +1,91 @@
+use std::error::Error as StdError;
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+
+use diesel::backend::Backend;
+use diesel::deserialize;
+use diesel::serialize;
+use diesel::sql_types::Text;
+
+/// The kind of record an audit log entry's `target_id` refers to.
+#[derive(AsExpression, Clone, Copy, Debug, Eq, FromSqlRow, Hash, PartialEq)]
+#[sql_type = "Text"]
+pub enum AuditTargetType {
+    User,
+    UserBan,
+    DomainBlock,
+    Report,
+    Role,
+    ActorRestriction,
+}
+
+impl fmt::Display for AuditTargetType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AuditTargetType::User => write!(f, "user"),
+            AuditTargetType::UserBan => write!(f, "user-ban"),
+            AuditTargetType::DomainBlock => write!(f, "domain-block"),
+            AuditTargetType::Report => write!(f, "report"),
+            AuditTargetType::Role => write!(f, "role"),
+            AuditTargetType::ActorRestriction => write!(f, "actor-restriction"),
+        }
+    }
+}
+
+impl FromStr for AuditTargetType {
+    type Err = AuditTargetTypeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "user" => Ok(AuditTargetType::User),
+            "user-ban" => Ok(AuditTargetType::UserBan),
+            "domain-block" => Ok(AuditTargetType::DomainBlock),
+            "report" => Ok(AuditTargetType::Report),
+            "role" => Ok(AuditTargetType::Role),
+            "actor-restriction" => Ok(AuditTargetType::ActorRestriction),
+            _ => Err(AuditTargetTypeParseError),
+        }
+    }
+}
+
+impl<DB> serialize::ToSql<Text, DB> for AuditTargetType
+where
+    DB: Backend,
+{
+    fn to_sql<W: Write>(&self, out: &mut serialize::Output<W, DB>) -> serialize::Result {
+        serialize::ToSql::<Text, DB>::to_sql(&format!("{}", self), out)
+    }
+}
+
+impl<DB> deserialize::FromSql<Text, DB> for AuditTargetType
+where
+    DB: Backend<RawValue = [u8]>,
+{
+    fn from_sql(bytes: Option<&DB::RawValue>) -> deserialize::Result<Self> {
+        deserialize::FromSql::<Text, DB>::from_sql(bytes).and_then(|string: String| {
+            string
+                .parse::<AuditTargetType>()
+                .map_err(|e| Box::new(e) as Box<StdError + Send + Sync>)
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct AuditTargetTypeParseError;
+
+impl fmt::Display for AuditTargetTypeParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Failed to parse AuditTargetType")
+    }
+}
+
+impl StdError for AuditTargetTypeParseError {
+    fn description(&self) -> &str {
+        "Failed to parse AuditTargetType"
+    }
+
+    fn cause(&self) -> Option<&StdError> {
+        None
+    }
+}
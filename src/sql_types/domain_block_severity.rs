@@ -0,0 +1,99 @@
+use std::error::Error as StdError;
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+
+use diesel::backend::Backend;
+use diesel::deserialize;
+use diesel::serialize;
+use diesel::sql_types::Text;
+
+#[derive(AsExpression, Clone, Copy, Debug, Eq, FromSqlRow, Hash, PartialEq)]
+#[sql_type = "Text"]
+pub enum DomainBlockSeverity {
+    Silence,
+    Suspend,
+    RejectMedia,
+}
+
+impl DomainBlockSeverity {
+    /// Whether posts from the domain are kept out of the public timeline.
+    pub fn hides_posts(&self) -> bool {
+        match *self {
+            DomainBlockSeverity::Silence | DomainBlockSeverity::Suspend => true,
+            DomainBlockSeverity::RejectMedia => false,
+        }
+    }
+
+    /// Whether media hosted on the domain is refused.
+    pub fn rejects_media(&self) -> bool {
+        match *self {
+            DomainBlockSeverity::Suspend | DomainBlockSeverity::RejectMedia => true,
+            DomainBlockSeverity::Silence => false,
+        }
+    }
+}
+
+impl fmt::Display for DomainBlockSeverity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DomainBlockSeverity::Silence => write!(f, "SILENCE"),
+            DomainBlockSeverity::Suspend => write!(f, "SUSPEND"),
+            DomainBlockSeverity::RejectMedia => write!(f, "REJECT_MEDIA"),
+        }
+    }
+}
+
+impl FromStr for DomainBlockSeverity {
+    type Err = DomainBlockSeverityParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "SILENCE" => Ok(DomainBlockSeverity::Silence),
+            "SUSPEND" => Ok(DomainBlockSeverity::Suspend),
+            "REJECT_MEDIA" => Ok(DomainBlockSeverity::RejectMedia),
+            _ => Err(DomainBlockSeverityParseError),
+        }
+    }
+}
+
+impl<DB> serialize::ToSql<Text, DB> for DomainBlockSeverity
+where
+    DB: Backend,
+{
+    fn to_sql<W: Write>(&self, out: &mut serialize::Output<W, DB>) -> serialize::Result {
+        serialize::ToSql::<Text, DB>::to_sql(&format!("{}", self), out)
+    }
+}
+
+impl<DB> deserialize::FromSql<Text, DB> for DomainBlockSeverity
+where
+    DB: Backend<RawValue = [u8]>,
+{
+    fn from_sql(bytes: Option<&DB::RawValue>) -> deserialize::Result<Self> {
+        deserialize::FromSql::<Text, DB>::from_sql(bytes).and_then(|string: String| {
+            string
+                .parse::<DomainBlockSeverity>()
+                .map_err(|e| Box::new(e) as Box<StdError + Send + Sync>)
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct DomainBlockSeverityParseError;
+
+impl fmt::Display for DomainBlockSeverityParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Failed to parse DomainBlockSeverity")
+    }
+}
+
+impl StdError for DomainBlockSeverityParseError {
+    fn description(&self) -> &str {
+        "Failed to parse DomainBlockSeverity"
+    }
+
+    fn cause(&self) -> Option<&StdError> {
+        None
+    }
+}
@@ -0,0 +1,81 @@
+use std::error::Error as StdError;
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+
+use diesel::backend::Backend;
+use diesel::deserialize;
+use diesel::serialize;
+use diesel::sql_types::Text;
+
+#[derive(AsExpression, Clone, Copy, Debug, Eq, FromSqlRow, Hash, PartialEq)]
+#[sql_type = "Text"]
+pub enum GroupJoinPolicy {
+    Open,
+    Approval,
+    InviteOnly,
+}
+
+impl fmt::Display for GroupJoinPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            GroupJoinPolicy::Open => write!(f, "OPEN"),
+            GroupJoinPolicy::Approval => write!(f, "APPROVAL"),
+            GroupJoinPolicy::InviteOnly => write!(f, "INVITE"),
+        }
+    }
+}
+
+impl FromStr for GroupJoinPolicy {
+    type Err = GroupJoinPolicyParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "OPEN" => Ok(GroupJoinPolicy::Open),
+            "APPROVAL" => Ok(GroupJoinPolicy::Approval),
+            "INVITE" => Ok(GroupJoinPolicy::InviteOnly),
+            _ => Err(GroupJoinPolicyParseError),
+        }
+    }
+}
+
+impl<DB> serialize::ToSql<Text, DB> for GroupJoinPolicy
+where
+    DB: Backend,
+{
+    fn to_sql<W: Write>(&self, out: &mut serialize::Output<W, DB>) -> serialize::Result {
+        serialize::ToSql::<Text, DB>::to_sql(&format!("{}", self), out)
+    }
+}
+
+impl<DB> deserialize::FromSql<Text, DB> for GroupJoinPolicy
+where
+    DB: Backend<RawValue = [u8]>,
+{
+    fn from_sql(bytes: Option<&DB::RawValue>) -> deserialize::Result<Self> {
+        deserialize::FromSql::<Text, DB>::from_sql(bytes).and_then(|string: String| {
+            string
+                .parse::<GroupJoinPolicy>()
+                .map_err(|e| Box::new(e) as Box<StdError + Send + Sync>)
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct GroupJoinPolicyParseError;
+
+impl fmt::Display for GroupJoinPolicyParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Failed to parse GroupJoinPolicy")
+    }
+}
+
+impl StdError for GroupJoinPolicyParseError {
+    fn description(&self) -> &str {
+        "Failed to parse GroupJoinPolicy"
+    }
+
+    fn cause(&self) -> Option<&StdError> {
+        None
+    }
+}
@@ -0,0 +1,106 @@
+use std::error::Error as StdError;
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+
+use diesel::backend::Backend;
+use diesel::deserialize;
+use diesel::serialize;
+use diesel::sql_types::Text;
+
+#[derive(AsExpression, Clone, Copy, Debug, Eq, FromSqlRow, Hash, PartialEq)]
+#[sql_type = "Text"]
+pub enum GroupRole {
+    Owner,
+    Moderator,
+    Member,
+}
+
+impl GroupRole {
+    /// Whether members with this role can invite, remove, and ban other members.
+    pub fn can_moderate(&self) -> bool {
+        match *self {
+            GroupRole::Owner | GroupRole::Moderator => true,
+            GroupRole::Member => false,
+        }
+    }
+
+    /// Whether members with this role can act on members with the `other` role.
+    ///
+    /// Owners outrank everyone but other owners, and moderators outrank members.
+    pub fn outranks(&self, other: GroupRole) -> bool {
+        self.rank() > other.rank()
+    }
+
+    fn rank(&self) -> u8 {
+        match *self {
+            GroupRole::Owner => 2,
+            GroupRole::Moderator => 1,
+            GroupRole::Member => 0,
+        }
+    }
+}
+
+impl fmt::Display for GroupRole {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            GroupRole::Owner => write!(f, "owner"),
+            GroupRole::Moderator => write!(f, "moderator"),
+            GroupRole::Member => write!(f, "member"),
+        }
+    }
+}
+
+impl FromStr for GroupRole {
+    type Err = GroupRoleParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "owner" => Ok(GroupRole::Owner),
+            "moderator" => Ok(GroupRole::Moderator),
+            "member" => Ok(GroupRole::Member),
+            _ => Err(GroupRoleParseError),
+        }
+    }
+}
+
+impl<DB> serialize::ToSql<Text, DB> for GroupRole
+where
+    DB: Backend,
+{
+    fn to_sql<W: Write>(&self, out: &mut serialize::Output<W, DB>) -> serialize::Result {
+        serialize::ToSql::<Text, DB>::to_sql(&format!("{}", self), out)
+    }
+}
+
+impl<DB> deserialize::FromSql<Text, DB> for GroupRole
+where
+    DB: Backend<RawValue = [u8]>,
+{
+    fn from_sql(bytes: Option<&DB::RawValue>) -> deserialize::Result<Self> {
+        deserialize::FromSql::<Text, DB>::from_sql(bytes).and_then(|string: String| {
+            string
+                .parse::<GroupRole>()
+                .map_err(|e| Box::new(e) as Box<StdError + Send + Sync>)
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct GroupRoleParseError;
+
+impl fmt::Display for GroupRoleParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Failed to parse GroupRole")
+    }
+}
+
+impl StdError for GroupRoleParseError {
+    fn description(&self) -> &str {
+        "Failed to parse GroupRole"
+    }
+
+    fn cause(&self) -> Option<&StdError> {
+        None
+    }
+}
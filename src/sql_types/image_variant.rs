@@ -0,0 +1,92 @@
+use std::error::Error as StdError;
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+
+use diesel::backend::Backend;
+use diesel::deserialize;
+use diesel::serialize;
+use diesel::sql_types::Text;
+
+#[derive(AsExpression, Clone, Copy, Debug, Eq, FromSqlRow, Hash, PartialEq)]
+#[sql_type = "Text"]
+pub enum ImageVariant {
+    Original,
+    Preview,
+    Thumbnail,
+}
+
+impl ImageVariant {
+    /// The largest width or height a variant may have, or `None` if it keeps the original size.
+    pub fn max_dimension(&self) -> Option<u32> {
+        match *self {
+            ImageVariant::Original => None,
+            ImageVariant::Preview => Some(800),
+            ImageVariant::Thumbnail => Some(150),
+        }
+    }
+}
+
+impl fmt::Display for ImageVariant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ImageVariant::Original => write!(f, "original"),
+            ImageVariant::Preview => write!(f, "preview"),
+            ImageVariant::Thumbnail => write!(f, "thumbnail"),
+        }
+    }
+}
+
+impl FromStr for ImageVariant {
+    type Err = ImageVariantParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "original" => Ok(ImageVariant::Original),
+            "preview" => Ok(ImageVariant::Preview),
+            "thumbnail" => Ok(ImageVariant::Thumbnail),
+            _ => Err(ImageVariantParseError),
+        }
+    }
+}
+
+impl<DB> serialize::ToSql<Text, DB> for ImageVariant
+where
+    DB: Backend,
+{
+    fn to_sql<W: Write>(&self, out: &mut serialize::Output<W, DB>) -> serialize::Result {
+        serialize::ToSql::<Text, DB>::to_sql(&format!("{}", self), out)
+    }
+}
+
+impl<DB> deserialize::FromSql<Text, DB> for ImageVariant
+where
+    DB: Backend<RawValue = [u8]>,
+{
+    fn from_sql(bytes: Option<&DB::RawValue>) -> deserialize::Result<Self> {
+        deserialize::FromSql::<Text, DB>::from_sql(bytes).and_then(|string: String| {
+            string
+                .parse::<ImageVariant>()
+                .map_err(|e| Box::new(e) as Box<StdError + Send + Sync>)
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ImageVariantParseError;
+
+impl fmt::Display for ImageVariantParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Failed to parse ImageVariant")
+    }
+}
+
+impl StdError for ImageVariantParseError {
+    fn description(&self) -> &str {
+        "Failed to parse ImageVariant"
+    }
+
+    fn cause(&self) -> Option<&StdError> {
+        None
+    }
+}
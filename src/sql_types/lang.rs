@@ -7,22 +7,40 @@ use diesel::backend::Backend;
 use diesel::serialize;
 use diesel::deserialize;
 use diesel::sql_types::Text;
+use language_tags::LanguageTag;
 
-#[derive(AsExpression, Clone, Copy, Debug, Eq, FromSqlRow, Hash, PartialEq)]
+/// A BCP-47 language tag, such as `en`, `en-US`, or `zh-Hant-TW`.
+///
+/// Tags are stored in their normalized form, so `EN-us` is stored as `en-US`.
+#[derive(AsExpression, Clone, Debug, Eq, FromSqlRow, PartialEq)]
 #[sql_type = "Text"]
-pub enum Lang {
-    EnUs,
-    EnUk,
-    EnAu,
+pub struct Lang(pub LanguageTag);
+
+impl Lang {
+    /// The `und` tag, used when the language of some content is not known.
+    pub fn undetermined() -> Self {
+        Lang(LanguageTag {
+            language: Some("und".to_owned()),
+            ..Default::default()
+        })
+    }
+
+    /// The primary language subtag, for example `en` for `en-US`.
+    pub fn primary_language(&self) -> Option<&str> {
+        self.0.language.as_ref().map(|s| s.as_ref())
+    }
+
+    /// Whether this tag, used as a language range, matches the given tag.
+    ///
+    /// The range `en` matches `en`, `en-US`, and `en-GB`, while `en-GB` only matches `en-GB`.
+    pub fn matches(&self, other: &Lang) -> bool {
+        self.0.is_language_range() && self.0.matches(&other.0)
+    }
 }
 
 impl fmt::Display for Lang {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match *self {
-            Lang::EnUs => write!(f, "EnUs"),
-            Lang::EnUk => write!(f, "EnUk"),
-            Lang::EnAu => write!(f, "EnAu"),
-        }
+        write!(f, "{}", self.0)
     }
 }
 
@@ -30,12 +48,17 @@ impl FromStr for Lang {
     type Err = LangParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "EnUs" => Ok(Lang::EnUs),
-            "EnUk" => Ok(Lang::EnUk),
-            "EnAu" => Ok(Lang::EnAu),
-            _ => Err(LangParseError),
-        }
+        // Parsing the displayed tag a second time normalizes the case of each subtag
+        s.parse::<LanguageTag>()
+            .and_then(|tag| format!("{}", tag).parse::<LanguageTag>())
+            .map(Lang)
+            .map_err(|_| LangParseError)
+    }
+}
+
+impl From<LanguageTag> for Lang {
+    fn from(tag: LanguageTag) -> Self {
+        Lang(tag)
     }
 }
 
@@ -79,3 +102,43 @@ impl StdError for LangParseError {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Lang;
+
+    #[test]
+    fn parse_and_normalize_tag() {
+        let lang: Lang = "EN-us".parse().unwrap();
+
+        assert_eq!(format!("{}", lang), "en-US");
+        assert_eq!(lang.primary_language(), Some("en"));
+    }
+
+    #[test]
+    fn parse_complex_tag() {
+        let lang: Lang = "zh-Hant-TW".parse().unwrap();
+
+        assert_eq!(format!("{}", lang), "zh-Hant-TW");
+    }
+
+    #[test]
+    fn dont_parse_invalid_tag() {
+        assert!("EnUs_".parse::<Lang>().is_err(), "Underscores aren't valid in tags");
+        assert!(
+            "en-toolongsubtag".parse::<Lang>().is_err(),
+            "Subtags can't be longer than eight characters"
+        );
+    }
+
+    #[test]
+    fn range_matches_regional_tag() {
+        let range: Lang = "en".parse().unwrap();
+        let tag: Lang = "en-GB".parse().unwrap();
+        let other: Lang = "de-DE".parse().unwrap();
+
+        assert!(range.matches(&tag), "en should match en-GB");
+        assert!(!range.matches(&other), "en should not match de-DE");
+        assert!(!tag.matches(&range), "en-GB should not match en");
+    }
+}
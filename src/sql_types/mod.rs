@@ -1,17 +1,39 @@
+mod attachment_type;
+mod audit_action;
+mod audit_target_type;
 mod lang;
+mod domain_block_severity;
 mod follow_policy;
+mod group_join_policy;
+mod group_role;
+mod image_variant;
 mod mime;
 mod permission;
 mod post_visibility;
 mod reaction_type;
+mod report_category;
+mod report_status;
 mod role;
+mod source_format;
 mod url;
+mod user_ban_kind;
 
+pub use self::attachment_type::AttachmentType;
+pub use self::audit_action::AuditAction;
+pub use self::audit_target_type::AuditTargetType;
 pub use self::lang::Lang;
+pub use self::domain_block_severity::DomainBlockSeverity;
 pub use self::follow_policy::FollowPolicy;
+pub use self::group_join_policy::GroupJoinPolicy;
+pub use self::group_role::GroupRole;
+pub use self::image_variant::ImageVariant;
 pub use self::mime::Mime;
 pub use self::permission::Permission;
 pub use self::post_visibility::PostVisibility;
 pub use self::reaction_type::ReactionType;
+pub use self::report_category::ReportCategory;
+pub use self::report_status::ReportStatus;
 pub use self::role::Role;
+pub use self::source_format::SourceFormat;
 pub use self::url::Url;
+pub use self::user_ban_kind::UserBanKind;
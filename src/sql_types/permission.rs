@@ -22,6 +22,8 @@ pub enum Permission {
     BlockInstance,
     GrantRole,
     RevokeRole,
+    ModerateReports,
+    ManageRoles,
 }
 
 impl fmt::Display for Permission {
@@ -38,6 +40,8 @@ impl fmt::Display for Permission {
             Permission::BlockInstance => write!(f, "block-instance"),
             Permission::GrantRole => write!(f, "grant-role"),
             Permission::RevokeRole => write!(f, "revoke-role"),
+            Permission::ModerateReports => write!(f, "moderate-reports"),
+            Permission::ManageRoles => write!(f, "manage-roles"),
         }
     }
 }
@@ -58,6 +62,8 @@ impl FromStr for Permission {
             "block-instance" => Ok(Permission::BlockInstance),
             "grant-role" => Ok(Permission::GrantRole),
             "revoke-role" => Ok(Permission::RevokeRole),
+            "moderate-reports" => Ok(Permission::ModerateReports),
+            "manage-roles" => Ok(Permission::ManageRoles),
             _ => Err(PermissionParseError),
         }
     }
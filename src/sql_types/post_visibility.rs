@@ -15,6 +15,7 @@ pub enum PostVisibility {
     FollowersOnly,
     FriendsOnly,
     ListedPeopleOnly,
+    GroupOnly,
 }
 
 impl fmt::Display for PostVisibility {
@@ -24,6 +25,7 @@ impl fmt::Display for PostVisibility {
             PostVisibility::FollowersOnly => write!(f, "FL"),
             PostVisibility::FriendsOnly => write!(f, "MUT"),
             PostVisibility::ListedPeopleOnly => write!(f, "LIST"),
+            PostVisibility::GroupOnly => write!(f, "GRP"),
         }
     }
 }
@@ -37,6 +39,7 @@ impl FromStr for PostVisibility {
             "FL" => Ok(PostVisibility::FollowersOnly),
             "MUT" => Ok(PostVisibility::FriendsOnly),
             "LIST" => Ok(PostVisibility::ListedPeopleOnly),
+            "GRP" => Ok(PostVisibility::GroupOnly),
             _ => Err(VisibilityParseError),
         }
     }
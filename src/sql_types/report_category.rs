@@ -0,0 +1,84 @@
+use std::error::Error as StdError;
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+
+use diesel::backend::Backend;
+use diesel::deserialize;
+use diesel::serialize;
+use diesel::sql_types::Text;
+
+#[derive(AsExpression, Clone, Copy, Debug, Eq, FromSqlRow, Hash, PartialEq)]
+#[sql_type = "Text"]
+pub enum ReportCategory {
+    Spam,
+    Abuse,
+    Illegal,
+    Other,
+}
+
+impl fmt::Display for ReportCategory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ReportCategory::Spam => write!(f, "SPAM"),
+            ReportCategory::Abuse => write!(f, "ABUSE"),
+            ReportCategory::Illegal => write!(f, "ILLEGAL"),
+            ReportCategory::Other => write!(f, "OTHER"),
+        }
+    }
+}
+
+impl FromStr for ReportCategory {
+    type Err = ReportCategoryParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "SPAM" => Ok(ReportCategory::Spam),
+            "ABUSE" => Ok(ReportCategory::Abuse),
+            "ILLEGAL" => Ok(ReportCategory::Illegal),
+            "OTHER" => Ok(ReportCategory::Other),
+            _ => Err(ReportCategoryParseError),
+        }
+    }
+}
+
+impl<DB> serialize::ToSql<Text, DB> for ReportCategory
+where
+    DB: Backend,
+{
+    fn to_sql<W: Write>(&self, out: &mut serialize::Output<W, DB>) -> serialize::Result {
+        serialize::ToSql::<Text, DB>::to_sql(&format!("{}", self), out)
+    }
+}
+
+impl<DB> deserialize::FromSql<Text, DB> for ReportCategory
+where
+    DB: Backend<RawValue = [u8]>,
+{
+    fn from_sql(bytes: Option<&DB::RawValue>) -> deserialize::Result<Self> {
+        deserialize::FromSql::<Text, DB>::from_sql(bytes).and_then(|string: String| {
+            string
+                .parse::<ReportCategory>()
+                .map_err(|e| Box::new(e) as Box<StdError + Send + Sync>)
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ReportCategoryParseError;
+
+impl fmt::Display for ReportCategoryParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Failed to parse ReportCategory")
+    }
+}
+
+impl StdError for ReportCategoryParseError {
+    fn description(&self) -> &str {
+        "Failed to parse ReportCategory"
+    }
+
+    fn cause(&self) -> Option<&StdError> {
+        None
+    }
+}
@@ -0,0 +1,110 @@
+use std::error::Error as StdError;
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+
+use diesel::backend::Backend;
+use diesel::deserialize;
+use diesel::serialize;
+use diesel::sql_types::Text;
+
+#[derive(AsExpression, Clone, Copy, Debug, Eq, FromSqlRow, Hash, PartialEq)]
+#[sql_type = "Text"]
+pub enum ReportStatus {
+    Open,
+    InProgress,
+    Resolved,
+    Dismissed,
+}
+
+impl ReportStatus {
+    /// Whether a report with this status still needs a moderator's attention.
+    pub fn is_pending(&self) -> bool {
+        match *self {
+            ReportStatus::Open | ReportStatus::InProgress => true,
+            ReportStatus::Resolved | ReportStatus::Dismissed => false,
+        }
+    }
+
+    /// Whether a report can move from this status to `next`.
+    ///
+    /// Pending reports can be picked up, resolved, or dismissed. Closed reports can only be
+    /// reopened.
+    pub fn can_transition_to(&self, next: ReportStatus) -> bool {
+        match (*self, next) {
+            (ReportStatus::Open, ReportStatus::InProgress) => true,
+            (ReportStatus::InProgress, ReportStatus::Open) => true,
+            (current, ReportStatus::Resolved) | (current, ReportStatus::Dismissed) => {
+                current.is_pending()
+            }
+            (current, ReportStatus::Open) => !current.is_pending(),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for ReportStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ReportStatus::Open => write!(f, "OPEN"),
+            ReportStatus::InProgress => write!(f, "IN_PROGRESS"),
+            ReportStatus::Resolved => write!(f, "RESOLVED"),
+            ReportStatus::Dismissed => write!(f, "DISMISSED"),
+        }
+    }
+}
+
+impl FromStr for ReportStatus {
+    type Err = ReportStatusParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "OPEN" => Ok(ReportStatus::Open),
+            "IN_PROGRESS" => Ok(ReportStatus::InProgress),
+            "RESOLVED" => Ok(ReportStatus::Resolved),
+            "DISMISSED" => Ok(ReportStatus::Dismissed),
+            _ => Err(ReportStatusParseError),
+        }
+    }
+}
+
+impl<DB> serialize::ToSql<Text, DB> for ReportStatus
+where
+    DB: Backend,
+{
+    fn to_sql<W: Write>(&self, out: &mut serialize::Output<W, DB>) -> serialize::Result {
+        serialize::ToSql::<Text, DB>::to_sql(&format!("{}", self), out)
+    }
+}
+
+impl<DB> deserialize::FromSql<Text, DB> for ReportStatus
+where
+    DB: Backend<RawValue = [u8]>,
+{
+    fn from_sql(bytes: Option<&DB::RawValue>) -> deserialize::Result<Self> {
+        deserialize::FromSql::<Text, DB>::from_sql(bytes).and_then(|string: String| {
+            string
+                .parse::<ReportStatus>()
+                .map_err(|e| Box::new(e) as Box<StdError + Send + Sync>)
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ReportStatusParseError;
+
+impl fmt::Display for ReportStatusParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Failed to parse ReportStatus")
+    }
+}
+
+impl StdError for ReportStatusParseError {
+    fn description(&self) -> &str {
+        "Failed to parse ReportStatus"
+    }
+
+    fn cause(&self) -> Option<&StdError> {
+        None
+    }
+}
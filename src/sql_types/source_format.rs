@@ -0,0 +1,78 @@
+use std::error::Error as StdError;
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+
+use diesel::backend::Backend;
+use diesel::deserialize;
+use diesel::serialize;
+use diesel::sql_types::Text;
+
+#[derive(AsExpression, Clone, Copy, Debug, Eq, FromSqlRow, Hash, PartialEq)]
+#[sql_type = "Text"]
+pub enum SourceFormat {
+    Markdown,
+    PlainText,
+}
+
+impl fmt::Display for SourceFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SourceFormat::Markdown => write!(f, "text/markdown"),
+            SourceFormat::PlainText => write!(f, "text/plain"),
+        }
+    }
+}
+
+impl FromStr for SourceFormat {
+    type Err = SourceFormatParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text/markdown" => Ok(SourceFormat::Markdown),
+            "text/plain" => Ok(SourceFormat::PlainText),
+            _ => Err(SourceFormatParseError),
+        }
+    }
+}
+
+impl<DB> serialize::ToSql<Text, DB> for SourceFormat
+where
+    DB: Backend,
+{
+    fn to_sql<W: Write>(&self, out: &mut serialize::Output<W, DB>) -> serialize::Result {
+        serialize::ToSql::<Text, DB>::to_sql(&format!("{}", self), out)
+    }
+}
+
+impl<DB> deserialize::FromSql<Text, DB> for SourceFormat
+where
+    DB: Backend<RawValue = [u8]>,
+{
+    fn from_sql(bytes: Option<&DB::RawValue>) -> deserialize::Result<Self> {
+        deserialize::FromSql::<Text, DB>::from_sql(bytes).and_then(|string: String| {
+            string
+                .parse::<SourceFormat>()
+                .map_err(|e| Box::new(e) as Box<StdError + Send + Sync>)
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SourceFormatParseError;
+
+impl fmt::Display for SourceFormatParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Failed to parse SourceFormat")
+    }
+}
+
+impl StdError for SourceFormatParseError {
+    fn description(&self) -> &str {
+        "Failed to parse SourceFormat"
+    }
+
+    fn cause(&self) -> Option<&StdError> {
+        None
+    }
+}
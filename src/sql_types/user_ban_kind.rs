@@ -0,0 +1,78 @@
+use std::error::Error as StdError;
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+
+use diesel::backend::Backend;
+use diesel::deserialize;
+use diesel::serialize;
+use diesel::sql_types::Text;
+
+#[derive(AsExpression, Clone, Copy, Debug, Eq, FromSqlRow, Hash, PartialEq)]
+#[sql_type = "Text"]
+pub enum UserBanKind {
+    Suspension,
+    Ban,
+}
+
+impl fmt::Display for UserBanKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            UserBanKind::Suspension => write!(f, "SUSPENSION"),
+            UserBanKind::Ban => write!(f, "BAN"),
+        }
+    }
+}
+
+impl FromStr for UserBanKind {
+    type Err = UserBanKindParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "SUSPENSION" => Ok(UserBanKind::Suspension),
+            "BAN" => Ok(UserBanKind::Ban),
+            _ => Err(UserBanKindParseError),
+        }
+    }
+}
+
+impl<DB> serialize::ToSql<Text, DB> for UserBanKind
+where
+    DB: Backend,
+{
+    fn to_sql<W: Write>(&self, out: &mut serialize::Output<W, DB>) -> serialize::Result {
+        serialize::ToSql::<Text, DB>::to_sql(&format!("{}", self), out)
+    }
+}
+
+impl<DB> deserialize::FromSql<Text, DB> for UserBanKind
+where
+    DB: Backend<RawValue = [u8]>,
+{
+    fn from_sql(bytes: Option<&DB::RawValue>) -> deserialize::Result<Self> {
+        deserialize::FromSql::<Text, DB>::from_sql(bytes).and_then(|string: String| {
+            string
+                .parse::<UserBanKind>()
+                .map_err(|e| Box::new(e) as Box<StdError + Send + Sync>)
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct UserBanKindParseError;
+
+impl fmt::Display for UserBanKindParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Failed to parse UserBanKind")
+    }
+}
+
+impl StdError for UserBanKindParseError {
+    fn description(&self) -> &str {
+        "Failed to parse UserBanKind"
+    }
+
+    fn cause(&self) -> Option<&StdError> {
+        None
+    }
+}
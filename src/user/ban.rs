@@ -0,0 +1,280 @@
+use chrono::DateTime;
+use chrono::offset::Utc;
+use diesel;
+use diesel::pg::PgConnection;
+use serde_json::Value;
+
+use audit_log::AuditLogEntry;
+use schema::user_bans;
+use sql_types::{AuditAction, Role, UserBanKind};
+use timer::Timer;
+use user::{has_role_id, UserLike};
+
+/// A moderator's decision to keep a user from logging in and to hide their personas.
+///
+/// A ban is active until it is lifted, either by a moderator or, for bans with a timer, by
+/// `UserBan::expire` once the timer's fire time has passed.
+#[derive(Debug, Identifiable, Queryable)]
+#[table_name = "user_bans"]
+pub struct UserBan {
+    id: i32,
+    user_id: i32,           // foreign key to User
+    moderator: Option<i32>, // foreign key to User
+    kind: UserBanKind,
+    reason: String,
+    timer_id: Option<i32>, // foreign key to Timer
+    created_at: DateTime<Utc>,
+    lifted_at: Option<DateTime<Utc>>,
+    lifted_by: Option<i32>, // foreign key to User
+}
+
+impl UserBan {
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+
+    pub fn user_id(&self) -> i32 {
+        self.user_id
+    }
+
+    /// The user who issued this ban, if they still exist.
+    pub fn moderator(&self) -> Option<i32> {
+        self.moderator
+    }
+
+    pub fn kind(&self) -> UserBanKind {
+        self.kind
+    }
+
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+
+    pub fn timer_id(&self) -> Option<i32> {
+        self.timer_id
+    }
+
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+
+    pub fn lifted_at(&self) -> Option<DateTime<Utc>> {
+        self.lifted_at
+    }
+
+    /// The user who lifted this ban, or `None` if it hasn't been lifted or expired on its own.
+    pub fn lifted_by(&self) -> Option<i32> {
+        self.lifted_by
+    }
+
+    /// The state of this ban, as recorded in the audit log.
+    pub(crate) fn audit_json(&self) -> Value {
+        json!({
+            "user_id": self.user_id,
+            "kind": format!("{}", self.kind),
+            "reason": self.reason,
+            "timer_id": self.timer_id,
+            "lifted_at": self.lifted_at.map(|lifted_at| lifted_at.to_rfc3339()),
+        })
+    }
+
+    /// Fetch a ban that currently applies to the given user, if there is one.
+    pub fn active_for<U: UserLike>(
+        user: &U,
+        conn: &PgConnection,
+    ) -> Result<Option<UserBan>, diesel::result::Error> {
+        use schema::timers;
+        use diesel::prelude::*;
+
+        user_bans::table
+            .left_outer_join(timers::table)
+            .filter(user_bans::dsl::user_id.eq(user.id()))
+            .filter(user_bans::dsl::lifted_at.is_null())
+            .filter(
+                user_bans::dsl::timer_id
+                    .is_null()
+                    .or(timers::dsl::fire_time.gt(Utc::now())),
+            )
+            .select(user_bans::all_columns)
+            .order(user_bans::dsl::created_at.desc())
+            .first(conn)
+            .optional()
+    }
+
+    /// Lift every ban whose timer has fired, returning the bans that were lifted.
+    ///
+    /// This should be run periodically. Bans stop applying once their timer fires even if this
+    /// hasn't run yet.
+    pub fn expire(conn: &PgConnection) -> Result<Vec<UserBan>, diesel::result::Error> {
+        use schema::timers;
+        use diesel::prelude::*;
+
+        let now = Utc::now();
+
+        let fired = timers::table
+            .filter(timers::dsl::fire_time.le(now))
+            .select(timers::dsl::id.nullable());
+
+        diesel::update(
+            user_bans::table
+                .filter(user_bans::dsl::lifted_at.is_null())
+                .filter(user_bans::dsl::timer_id.eq_any(fired)),
+        ).set(user_bans::dsl::lifted_at.eq(now))
+            .get_results(conn)
+    }
+}
+
+/// An SQL condition that holds when the actor in `actor_column` doesn't belong to a user with an
+/// active ban.
+///
+/// This is used to hide banned users' personas and posts from queries.
+pub(crate) fn not_banned_sql(actor_column: &str) -> String {
+    format!(
+        "NOT EXISTS (SELECT 1 FROM base_actors AS banned_actors \
+         INNER JOIN user_bans ON user_bans.user_id = banned_actors.local_user \
+         LEFT OUTER JOIN timers ON timers.id = user_bans.timer_id \
+         WHERE banned_actors.id = {} AND user_bans.lifted_at IS NULL \
+         AND (user_bans.timer_id IS NULL OR timers.fire_time > now()))",
+        actor_column
+    )
+}
+
+#[derive(Insertable)]
+#[table_name = "user_bans"]
+pub struct NewUserBan {
+    user_id: i32,
+    moderator: Option<i32>,
+    kind: UserBanKind,
+    reason: String,
+    timer_id: Option<i32>,
+    created_at: DateTime<Utc>,
+}
+
+impl NewUserBan {
+    pub fn new<U: UserLike>(
+        user: &U,
+        moderator: i32,
+        kind: UserBanKind,
+        reason: String,
+        until: Option<&Timer>,
+    ) -> Self {
+        NewUserBan {
+            user_id: user.id(),
+            moderator: Some(moderator),
+            kind,
+            reason,
+            timer_id: until.map(|timer| timer.id()),
+            created_at: Utc::now(),
+        }
+    }
+}
+
+pub struct UserBanner(i32);
+
+impl UserBanner {
+    pub(crate) fn new(moderator: i32) -> UserBanner {
+        UserBanner(moderator)
+    }
+
+    /// List every ban a user has received, newest first, including ones that have been lifted.
+    pub fn bans<U: UserLike>(
+        &self,
+        user: &U,
+        conn: &PgConnection,
+    ) -> Result<Vec<UserBan>, diesel::result::Error> {
+        use schema::user_bans;
+        use diesel::prelude::*;
+
+        user_bans::table
+            .filter(user_bans::dsl::user_id.eq(user.id()))
+            .order(user_bans::dsl::created_at.desc())
+            .load(conn)
+    }
+
+    /// Ban or suspend a user, optionally until a timer fires.
+    ///
+    /// Banned users can't log in, and their personas are hidden from timelines and searches. Only
+    /// admins can ban other admins.
+    pub fn ban<U: UserLike>(
+        &self,
+        user: &U,
+        kind: UserBanKind,
+        reason: String,
+        until: Option<&Timer>,
+        conn: &PgConnection,
+    ) -> Result<UserBan, UserBanError> {
+        use schema::user_bans;
+        use diesel::prelude::*;
+
+        if user.id() == self.0 {
+            return Err(UserBanError::SelfBan);
+        }
+
+        if user.is_admin(conn)? && !has_role_id(self.0, Role::Admin, conn)? {
+            return Err(UserBanError::Admin);
+        }
+
+        conn.transaction(|| {
+            let ban: UserBan = diesel::insert_into(user_bans::table)
+                .values(&NewUserBan::new(user, self.0, kind, reason, until))
+                .get_result(conn)?;
+
+            AuditLogEntry::record(
+                Some(self.0),
+                AuditAction::BanUser,
+                Some(ban.id()),
+                None,
+                Some(ban.audit_json()),
+                conn,
+            )?;
+
+            Ok(ban)
+        })
+    }
+
+    /// Lift a ban before it expires.
+    pub fn unban(&self, ban: UserBan, conn: &PgConnection) -> Result<UserBan, UserBanError> {
+        use schema::user_bans;
+        use diesel::prelude::*;
+
+        if ban.lifted_at().is_some() {
+            return Ok(ban);
+        }
+
+        conn.transaction(|| {
+            let lifted: UserBan = diesel::update(&ban)
+                .set((
+                    user_bans::dsl::lifted_at.eq(Utc::now()),
+                    user_bans::dsl::lifted_by.eq(self.0),
+                ))
+                .get_result(conn)?;
+
+            AuditLogEntry::record(
+                Some(self.0),
+                AuditAction::UnbanUser,
+                Some(lifted.id()),
+                Some(ban.audit_json()),
+                Some(lifted.audit_json()),
+                conn,
+            )?;
+
+            Ok(lifted)
+        })
+    }
+}
+
+#[derive(Debug, Fail)]
+pub enum UserBanError {
+    #[fail(display = "Error managing ban")]
+    Diesel(#[cause] diesel::result::Error),
+    #[fail(display = "Moderators cannot ban themselves")]
+    SelfBan,
+    #[fail(display = "Only admins can ban admins")]
+    Admin,
+}
+
+impl From<diesel::result::Error> for UserBanError {
+    fn from(e: diesel::result::Error) -> Self {
+        UserBanError::Diesel(e)
+    }
+}
@@ -4,16 +4,23 @@ use diesel;
 use diesel::connection::Connection;
 use diesel::pg::PgConnection;
 
+pub mod ban;
 pub mod email;
 pub mod local_auth;
+mod permission_set;
 mod permissions;
 pub mod role;
+pub mod session;
 
 use schema::users;
+use self::ban::UserBan;
 use self::email::{EmailVerificationToken, UnverifiedEmail, VerifiedEmail, VerifyEmail};
 use self::local_auth::LocalAuth;
+use self::role::user_role::active_grant;
 pub use self::local_auth::{PlaintextPassword, VerificationError};
+pub use self::permission_set::{CachedPermissions, PermissionSet};
 pub use self::permissions::{PermissionError, PermissionResult, PermissionedUser};
+pub(crate) use self::permissions::PostMaker;
 use sql_types::Role;
 
 pub trait UserLike {
@@ -34,19 +41,29 @@ pub trait UserLike {
     }
 
     fn has_role(&self, name: Role, conn: &PgConnection) -> Result<bool, diesel::result::Error> {
-        use schema::{roles, user_roles};
-        use diesel::prelude::*;
-
-        roles::dsl::roles
-            .inner_join(user_roles::dsl::user_roles)
-            .filter(user_roles::dsl::user_id.eq(self.id()))
-            .filter(roles::dsl::name.eq(name))
-            .count()
-            .get_result(conn)
-            .map(|count: i64| count > 0)
+        has_role_id(self.id(), name, conn)
     }
 }
 
+/// Whether the user with the given id holds a role, for when only the id is at hand.
+pub(crate) fn has_role_id(
+    user_id: i32,
+    name: Role,
+    conn: &PgConnection,
+) -> Result<bool, diesel::result::Error> {
+    use schema::{roles, user_roles};
+    use diesel::prelude::*;
+
+    roles::dsl::roles
+        .inner_join(user_roles::dsl::user_roles)
+        .filter(user_roles::dsl::user_id.eq(user_id))
+        .filter(active_grant())
+        .filter(roles::dsl::name.eq(name))
+        .count()
+        .get_result(conn)
+        .map(|count: i64| count > 0)
+}
+
 #[derive(Debug, Fail)]
 pub enum UserVerifyError {
     #[fail(display = "Error in diesel: {}", _0)]
@@ -84,6 +101,28 @@ impl From<diesel::result::Error> for UpdateFieldError {
     }
 }
 
+#[derive(Debug, Fail)]
+pub enum LogInError {
+    #[fail(display = "Error logging in: {}", _0)]
+    Verification(#[cause] VerificationError),
+    #[fail(display = "Error in diesel: {}", _0)]
+    Diesel(#[cause] diesel::result::Error),
+    #[fail(display = "User is banned")]
+    Banned(UserBan),
+}
+
+impl From<VerificationError> for LogInError {
+    fn from(e: VerificationError) -> Self {
+        LogInError::Verification(e)
+    }
+}
+
+impl From<diesel::result::Error> for LogInError {
+    fn from(e: diesel::result::Error) -> Self {
+        LogInError::Diesel(e)
+    }
+}
+
 #[derive(Identifiable)]
 #[table_name = "users"]
 pub struct AuthenticatedUser {
@@ -119,8 +158,8 @@ impl AuthenticatedUser {
             return Err(UserVerifyError::IdMismatch);
         }
 
-        permissions::RoleGranter::new()
-            .grant_role(self, Role::Verified, conn)
+        permissions::RoleGranter::new(None)
+            .grant_role(self, Role::Verified, None, conn)
             .map_err(From::from)
     }
 }
@@ -228,12 +267,22 @@ pub struct UnauthenticatedUser {
 }
 
 impl UnauthenticatedUser {
+    /// Log in with a password.
+    ///
+    /// Users with an active ban can't log in, even with the right password.
     pub fn log_in_local(
         self,
         local_auth: LocalAuth,
         password: PlaintextPassword,
-    ) -> Result<AuthenticatedUser, VerificationError> {
-        local_auth.log_in(self, password)
+        conn: &PgConnection,
+    ) -> Result<AuthenticatedUser, LogInError> {
+        let user = local_auth.log_in(self, password)?;
+
+        if let Some(ban) = UserBan::active_for(&user, conn)? {
+            return Err(LogInError::Banned(ban));
+        }
+
+        Ok(user)
     }
 
     pub fn to_verified(
@@ -0,0 +1,201 @@
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use chrono::DateTime;
+use chrono::offset::Utc;
+use diesel;
+use diesel::pg::PgConnection;
+
+use sql_types::Permission;
+use super::{PermissionError, PermissionResult, PermissionedUser, UserLike};
+use super::role::user_role::active_grant;
+
+/// Bumped whenever roles or their permissions change through this crate.
+static GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+/// Mark every loaded `PermissionSet` as out of date.
+pub(crate) fn invalidate() {
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+}
+
+/// A user's effective permissions, loaded with a single query.
+///
+/// Checking a permission against a set doesn't touch the database. A set goes out of date when a
+/// `RoleGranter`, `RoleRevoker`, or `RoleManager` in this process changes any roles, or when one
+/// of the user's time-limited grants expires, and `refresh` reloads it. Changes made by other
+/// processes aren't noticed, so sets shouldn't be kept longer than a single request.
+#[derive(Clone, Debug)]
+pub struct PermissionSet {
+    user_id: i32,
+    permissions: HashSet<Permission>,
+    expires_at: Option<DateTime<Utc>>,
+    generation: usize,
+}
+
+impl PermissionSet {
+    pub fn load<U: UserLike>(
+        user: &U,
+        conn: &PgConnection,
+    ) -> Result<PermissionSet, diesel::result::Error> {
+        PermissionSet::load_for_id(user.id(), conn)
+    }
+
+    fn load_for_id(user_id: i32, conn: &PgConnection) -> Result<Self, diesel::result::Error> {
+        use schema::{permissions, role_permissions, roles, user_roles};
+        use diesel::prelude::*;
+
+        let generation = GENERATION.load(Ordering::SeqCst);
+
+        let grants: Vec<(String, Option<DateTime<Utc>>)> = roles::table
+            .inner_join(user_roles::table)
+            .inner_join(role_permissions::table)
+            .inner_join(
+                permissions::table
+                    .on(role_permissions::dsl::permission_id.eq(permissions::dsl::id)),
+            )
+            .filter(user_roles::dsl::user_id.eq(user_id))
+            .filter(active_grant())
+            .select((permissions::dsl::name, user_roles::dsl::expires_at))
+            .load(conn)?;
+
+        Ok(PermissionSet {
+            user_id,
+            permissions: grants
+                .iter()
+                .filter_map(|&(ref name, _)| name.parse().ok())
+                .collect(),
+            expires_at: grants.iter().filter_map(|&(_, expires_at)| expires_at).min(),
+            generation,
+        })
+    }
+
+    pub fn user_id(&self) -> i32 {
+        self.user_id
+    }
+
+    pub fn contains(&self, permission: Permission) -> bool {
+        self.permissions.contains(&permission)
+    }
+
+    /// Check every permission in `permissions`, failing if any is missing.
+    pub fn check_all(&self, permissions: &[Permission]) -> PermissionResult<()> {
+        if permissions.iter().all(|permission| self.contains(*permission)) {
+            Ok(())
+        } else {
+            Err(PermissionError::Permission)
+        }
+    }
+
+    pub fn permissions(&self) -> &HashSet<Permission> {
+        &self.permissions
+    }
+
+    /// Whether roles have changed, or one of the user's grants has expired, since this set was
+    /// loaded.
+    pub fn is_stale(&self) -> bool {
+        self.generation != GENERATION.load(Ordering::SeqCst)
+            || self.expires_at
+                .map_or(false, |expires_at| expires_at <= Utc::now())
+    }
+
+    /// Reload this set if roles have changed since it was loaded.
+    pub fn refresh(&mut self, conn: &PgConnection) -> Result<(), diesel::result::Error> {
+        if self.is_stale() {
+            *self = PermissionSet::load_for_id(self.user_id, conn)?;
+        }
+
+        Ok(())
+    }
+
+    /// Use this set for a user's permission checks.
+    ///
+    /// Returns `None` if the set was loaded for a different user or is out of date.
+    pub fn apply<'a, U: UserLike>(&'a self, user: &'a U) -> Option<CachedPermissions<'a, U>> {
+        if self.user_id != user.id() || self.is_stale() {
+            return None;
+        }
+
+        Some(CachedPermissions {
+            user,
+            permissions: self,
+        })
+    }
+}
+
+/// A user whose `PermissionedUser` checks are answered from a `PermissionSet`.
+///
+/// Whether the user may act as an actor, and the actor's restrictions, are still checked against
+/// the database.
+pub struct CachedPermissions<'a, U: UserLike + 'a> {
+    user: &'a U,
+    permissions: &'a PermissionSet,
+}
+
+impl<'a, U: UserLike + 'a> UserLike for CachedPermissions<'a, U> {
+    fn id(&self) -> i32 {
+        self.user.id()
+    }
+
+    fn primary_email(&self) -> Option<i32> {
+        self.user.primary_email()
+    }
+
+    fn created_at(&self) -> DateTime<Utc> {
+        self.user.created_at()
+    }
+}
+
+impl<'a, U: UserLike + 'a> PermissionedUser for CachedPermissions<'a, U> {
+    fn has_permission(&self, permission: Permission, _: &PgConnection) -> PermissionResult<()> {
+        self.permissions.check_all(&[permission])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    fn set(permissions: &[Permission]) -> PermissionSet {
+        PermissionSet {
+            user_id: 1,
+            permissions: permissions.iter().cloned().collect(),
+            expires_at: None,
+            generation: GENERATION.load(Ordering::SeqCst),
+        }
+    }
+
+    #[test]
+    fn check_all_requires_every_permission() {
+        let permissions = set(&[Permission::MakePost, Permission::MakeComment]);
+
+        assert!(
+            permissions
+                .check_all(&[Permission::MakePost, Permission::MakeComment])
+                .is_ok()
+        );
+        assert!(
+            permissions
+                .check_all(&[Permission::MakePost, Permission::BanUser])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn invalidating_makes_sets_stale() {
+        let permissions = set(&[Permission::MakePost]);
+
+        invalidate();
+
+        assert!(permissions.is_stale());
+    }
+
+    #[test]
+    fn expired_grants_make_sets_stale() {
+        let mut permissions = set(&[Permission::BanUser]);
+        permissions.expires_at = Some(Utc::now() - Duration::minutes(1));
+
+        assert!(permissions.is_stale());
+    }
+}
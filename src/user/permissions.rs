@@ -1,19 +1,38 @@
+use chrono::DateTime;
 use chrono::offset::Utc;
 use diesel;
 use diesel::pg::PgConnection;
+use diesel::sql_types::{Bool, Integer};
 use serde_json::Value;
 
-use file::File;
+use audit_log::{AuditLogEntry, AuditLogViewer};
+use domain_block::InstanceBlocker;
 use file::image::Image;
+use file::quota::{QuotaExceeded, StorageUsage, StorageUsageViewer};
 use base_actor::BaseActor;
+use base_actor::block::Blocker;
+use base_actor::delegation::Delegator;
 use base_actor::follow_request::{FollowRequest, NewFollowRequest};
 use base_actor::follower::{Follower, NewFollower};
+use base_actor::group::{Group, GroupAdministrator, GroupJoiner};
+use base_actor::list::{List, ListError, ListManager};
+use base_actor::mute::Muter;
+use base_actor::restriction::ActorRestricter;
 use base_post::{BasePost, NewBasePost};
+use base_post::conversation::{Conversation, ConversationManager};
+use base_post::direct_post::{DirectPost, NewDirectPost};
+use base_post::group_post::GroupPostMaker;
 use base_post::post::{NewPost, Post};
-use base_post::post::media_post::{MediaPost, NewMediaPost};
+use base_post::post::render::LinkResolver;
+use base_post::post::media_post::{MediaAttachment, MediaPost, NewMediaPost};
 use base_post::post::comment::{Comment, NewComment};
-use sql_types::{FollowPolicy, Mime, Permission, PostVisibility, Role};
-use super::UserLike;
+use sql_types::{AuditAction, FollowPolicy, Lang, Mime, Permission, PostVisibility, Role,
+                SourceFormat};
+use report::{ReportModerator, Reporter};
+use super::{permission_set, UserLike};
+use super::ban::UserBanner;
+use super::role::{Role as RoleRecord, RoleManager};
+use super::role::user_role::{active_grant, UserRole};
 
 #[derive(Debug, Fail)]
 pub enum PermissionError {
@@ -45,8 +64,8 @@ pub trait PermissionedUser: UserLike {
         base_actor: &'a BaseActor,
         conn: &PgConnection,
     ) -> PermissionResult<PostMaker<'a>> {
-        self.with_actor(base_actor).and_then(|actor| {
-            self.has_permission(Permission::MakePost, conn)
+        self.with_delegated_actor(base_actor, conn).and_then(|actor| {
+            self.has_actor_permission(actor, Permission::MakePost, conn)
                 .map(|_| PostMaker::new(actor))
         })
     }
@@ -56,8 +75,8 @@ pub trait PermissionedUser: UserLike {
         base_actor: &'a BaseActor,
         conn: &PgConnection,
     ) -> PermissionResult<MediaPostMaker<'a>> {
-        self.with_actor(base_actor).and_then(|actor| {
-            self.has_permission(Permission::MakeMediaPost, conn)
+        self.with_delegated_actor(base_actor, conn).and_then(|actor| {
+            self.has_actor_permission(actor, Permission::MakeMediaPost, conn)
                 .map(|_| MediaPostMaker::new(actor))
         })
     }
@@ -72,8 +91,8 @@ pub trait PermissionedUser: UserLike {
         base_actor: &'a BaseActor,
         conn: &PgConnection,
     ) -> PermissionResult<CommentMaker<'a>> {
-        self.with_actor(base_actor).and_then(|actor| {
-            self.has_permission(Permission::MakeComment, conn)
+        self.with_delegated_actor(base_actor, conn).and_then(|actor| {
+            self.has_actor_permission(actor, Permission::MakeComment, conn)
                 .map(|_| CommentMaker::new(actor))
         })
     }
@@ -84,11 +103,88 @@ pub trait PermissionedUser: UserLike {
         conn: &PgConnection,
     ) -> PermissionResult<ActorFollower<'a>> {
         self.with_actor(base_actor).and_then(|actor| {
-            self.has_permission(Permission::FollowUser, conn)
+            self.has_actor_permission(actor, Permission::FollowUser, conn)
                 .map(|_| ActorFollower::new(actor))
         })
     }
 
+    /// Only members of a group may post in it.
+    fn can_post_to_group<'a>(
+        &self,
+        base_actor: &'a BaseActor,
+        group: &'a Group,
+        conn: &PgConnection,
+    ) -> PermissionResult<GroupPostMaker<'a>> {
+        self.with_delegated_actor(base_actor, conn).and_then(|actor| {
+            self.has_actor_permission(actor, Permission::MakePost, conn)?;
+
+            group
+                .membership(actor, conn)?
+                .map(|_| GroupPostMaker::new(actor, group))
+                .ok_or(PermissionError::Permission)
+        })
+    }
+
+    fn can_join_group<'a>(
+        &self,
+        base_actor: &'a BaseActor,
+        conn: &PgConnection,
+    ) -> PermissionResult<GroupJoiner<'a>> {
+        self.with_actor(base_actor).and_then(|actor| {
+            self.has_actor_permission(actor, Permission::FollowUser, conn)
+                .map(|_| GroupJoiner::new(actor))
+        })
+    }
+
+    /// Only owners and moderators of a group may administer it.
+    fn can_administer_group<'a>(
+        &self,
+        base_actor: &'a BaseActor,
+        group: &'a Group,
+        conn: &PgConnection,
+    ) -> PermissionResult<GroupAdministrator<'a>> {
+        self.with_actor(base_actor).and_then(|actor| {
+            group
+                .membership(actor, conn)?
+                .and_then(|membership| {
+                    if membership.role().can_moderate() {
+                        Some(GroupAdministrator::new(actor, group, membership.role()))
+                    } else {
+                        None
+                    }
+                })
+                .ok_or(PermissionError::Permission)
+        })
+    }
+
+    fn can_manage_lists<'a>(&self, base_actor: &'a BaseActor) -> PermissionResult<ListManager<'a>> {
+        self.with_actor(base_actor).map(ListManager::new)
+    }
+
+    fn can_manage_conversations<'a>(
+        &self,
+        base_actor: &'a BaseActor,
+    ) -> PermissionResult<ConversationManager<'a>> {
+        self.with_actor(base_actor).map(ConversationManager::new)
+    }
+
+    fn can_block<'a>(&self, base_actor: &'a BaseActor) -> PermissionResult<Blocker<'a>> {
+        self.with_actor(base_actor).map(Blocker::new)
+    }
+
+    fn can_mute<'a>(&self, base_actor: &'a BaseActor) -> PermissionResult<Muter<'a>> {
+        self.with_actor(base_actor).map(Muter::new)
+    }
+
+    fn can_report<'a>(&self, base_actor: &'a BaseActor) -> PermissionResult<Reporter<'a>> {
+        self.with_actor(base_actor).map(Reporter::new)
+    }
+
+    /// Only an actor's owner may choose who else can act as it.
+    fn can_delegate<'a>(&self, base_actor: &'a BaseActor) -> PermissionResult<Delegator<'a>> {
+        self.with_actor(base_actor).map(Delegator::new)
+    }
+
     fn can_make_persona(&self, conn: &PgConnection) -> PermissionResult<()> {
         self.has_permission(Permission::MakePersona, conn)
     }
@@ -99,7 +195,7 @@ pub trait PermissionedUser: UserLike {
         conn: &PgConnection,
     ) -> PermissionResult<FollowRequestManager<'a>> {
         self.with_actor(base_actor).and_then(|actor| {
-            self.has_permission(Permission::ManageFollowRequest, conn)
+            self.has_actor_permission(actor, Permission::ManageFollowRequest, conn)
                 .map(|_| FollowRequestManager::new(actor))
         })
     }
@@ -108,24 +204,53 @@ pub trait PermissionedUser: UserLike {
         self.has_permission(Permission::ConfigureInstance, conn)
     }
 
-    fn can_ban_user(&self, conn: &PgConnection) -> PermissionResult<()> {
+    fn can_ban_user(&self, conn: &PgConnection) -> PermissionResult<UserBanner> {
+        self.has_permission(Permission::BanUser, conn)
+            .map(|_| UserBanner::new(self.id()))
+    }
+
+    fn can_restrict_actors(&self, conn: &PgConnection) -> PermissionResult<ActorRestricter> {
         self.has_permission(Permission::BanUser, conn)
+            .map(|_| ActorRestricter::new(self.id()))
     }
 
-    fn can_block_instance(&self, conn: &PgConnection) -> PermissionResult<()> {
+    fn can_block_instance(&self, conn: &PgConnection) -> PermissionResult<InstanceBlocker> {
         self.has_permission(Permission::BlockInstance, conn)
+            .map(|_| InstanceBlocker::new(self.id()))
+    }
+
+    fn can_moderate_reports(&self, conn: &PgConnection) -> PermissionResult<ReportModerator> {
+        self.has_permission(Permission::ModerateReports, conn)
+            .map(|_| ReportModerator::new(self.id()))
+    }
+
+    fn can_view_storage_usage(&self, conn: &PgConnection) -> PermissionResult<StorageUsageViewer> {
+        self.has_permission(Permission::ConfigureInstance, conn)
+            .map(|_| StorageUsageViewer::new())
+    }
+
+    fn can_view_audit_log(&self, conn: &PgConnection) -> PermissionResult<AuditLogViewer> {
+        self.has_permission(Permission::ConfigureInstance, conn)
+            .map(|_| AuditLogViewer::new())
     }
 
     fn can_grant_role(&self, conn: &PgConnection) -> PermissionResult<RoleGranter> {
         self.has_permission(Permission::GrantRole, conn)
-            .map(|_| RoleGranter::new())
+            .map(|_| RoleGranter::new(Some(self.id())))
     }
 
     fn can_revoke_role(&self, conn: &PgConnection) -> PermissionResult<RoleRevoker> {
         self.has_permission(Permission::RevokeRole, conn)
-            .map(|_| RoleRevoker::new())
+            .map(|_| RoleRevoker::new(self.id()))
+    }
+
+    fn can_manage_roles(&self, conn: &PgConnection) -> PermissionResult<RoleManager> {
+        self.has_permission(Permission::ManageRoles, conn)
+            .map(|_| RoleManager::new(self.id()))
     }
 
+    /// Only an actor's owner may manage its account, such as its follows, lists, conversations,
+    /// blocks, and mutes.
     fn with_actor<'a>(&self, base_actor: &'a BaseActor) -> PermissionResult<&'a BaseActor> {
         base_actor
             .local_user()
@@ -139,6 +264,35 @@ pub trait PermissionedUser: UserLike {
             .ok_or(PermissionError::Permission)
     }
 
+    /// A user can post and comment as an actor they own, or one that's been delegated to them.
+    fn with_delegated_actor<'a>(
+        &self,
+        base_actor: &'a BaseActor,
+        conn: &PgConnection,
+    ) -> PermissionResult<&'a BaseActor> {
+        if base_actor.is_delegated_to(self.id(), conn)? {
+            Ok(base_actor)
+        } else {
+            Err(PermissionError::Permission)
+        }
+    }
+
+    /// Check a permission for acting as an actor, taking the actor's restrictions into account.
+    fn has_actor_permission(
+        &self,
+        base_actor: &BaseActor,
+        permission: Permission,
+        conn: &PgConnection,
+    ) -> PermissionResult<()> {
+        self.has_permission(permission, conn)?;
+
+        if base_actor.is_restricted(permission, conn)? {
+            Err(PermissionError::Permission)
+        } else {
+            Ok(())
+        }
+    }
+
     fn has_permission(&self, permission: Permission, conn: &PgConnection) -> PermissionResult<()> {
         use schema::{permissions, role_permissions, roles, user_roles};
         use diesel::prelude::*;
@@ -151,6 +305,7 @@ pub trait PermissionedUser: UserLike {
                     .on(role_permissions::dsl::permission_id.eq(permissions::dsl::id)),
             )
             .filter(user_roles::dsl::user_id.eq(self.id()))
+            .filter(active_grant())
             .filter(permissions::dsl::name.eq(permission))
             .count()
             .get_result(conn)
@@ -165,48 +320,126 @@ pub trait PermissionedUser: UserLike {
     }
 }
 
-pub struct RoleGranter(());
+/// Grants roles to users.
+///
+/// The granting user is `None` when the instance grants a role on its own, such as when a user
+/// verifies their email.
+pub struct RoleGranter(Option<i32>);
 
 impl RoleGranter {
-    pub(crate) fn new() -> RoleGranter {
-        RoleGranter(())
+    pub(crate) fn new(granted_by: Option<i32>) -> RoleGranter {
+        RoleGranter(granted_by)
     }
 
+    /// Grant a built-in role, until `expires_at` if it's given or permanently otherwise.
     pub fn grant_role<U: UserLike>(
         &self,
         user: &U,
         role: Role,
+        expires_at: Option<DateTime<Utc>>,
+        conn: &PgConnection,
+    ) -> Result<(), diesel::result::Error> {
+        RoleRecord::builtin_role(role, conn)
+            .and_then(|role| self.grant_custom_role(user, &role, expires_at, conn))
+    }
+
+    /// Grant any stored role, including ones created with a `RoleManager`.
+    ///
+    /// Granting a role the user already has keeps whichever grant lasts longer, so a permanent
+    /// grant is never made temporary. When the new grant lasts longer, the existing one is
+    /// extended but still records who first granted it. Expired grants are replaced outright.
+    pub fn grant_custom_role<U: UserLike>(
+        &self,
+        user: &U,
+        role: &RoleRecord,
+        expires_at: Option<DateTime<Utc>>,
         conn: &PgConnection,
     ) -> Result<(), diesel::result::Error> {
-        use schema::{roles, user_roles};
+        use schema::user_roles;
         use diesel::prelude::*;
 
-        if user.has_role(role, conn)? {
-            return Ok(());
+        let granted = conn.transaction::<_, diesel::result::Error, _>(|| {
+            let now = Utc::now();
+
+            let existing: Option<UserRole> = user_roles::table
+                .filter(user_roles::dsl::user_id.eq(user.id()))
+                .filter(user_roles::dsl::role_id.eq(role.id()))
+                .for_update()
+                .get_result(conn)
+                .optional()?;
+
+            match existing {
+                Some(ref grant) if grant.expires_at().map_or(false, |at| at <= now) => {
+                    diesel::update(grant)
+                        .set((
+                            user_roles::dsl::created_at.eq(now),
+                            user_roles::dsl::expires_at.eq(expires_at),
+                            user_roles::dsl::granted_by.eq(self.0),
+                        ))
+                        .execute(conn)?;
+                }
+                Some(ref grant) => {
+                    let lasts_longer = match (grant.expires_at(), expires_at) {
+                        (None, _) => false,
+                        (Some(_), None) => true,
+                        (Some(current), Some(new)) => new > current,
+                    };
+
+                    if !lasts_longer {
+                        return Ok(false);
+                    }
+
+                    diesel::update(grant)
+                        .set(user_roles::dsl::expires_at.eq(expires_at))
+                        .execute(conn)?;
+                }
+                None => {
+                    diesel::insert_into(user_roles::table)
+                        .values((
+                            user_roles::dsl::user_id.eq(user.id()),
+                            user_roles::dsl::role_id.eq(role.id()),
+                            user_roles::dsl::created_at.eq(now),
+                            user_roles::dsl::expires_at.eq(expires_at),
+                            user_roles::dsl::granted_by.eq(self.0),
+                        ))
+                        .on_conflict_do_nothing()
+                        .execute(conn)?;
+                }
+            }
+
+            AuditLogEntry::record(
+                self.0,
+                AuditAction::GrantRole,
+                Some(user.id()),
+                existing.as_ref().map(|grant| {
+                    json!({
+                        "role": role.name(),
+                        "expires_at": grant.expires_at().map(|at| at.to_rfc3339()),
+                    })
+                }),
+                Some(json!({
+                    "role": role.name(),
+                    "expires_at": expires_at.map(|at| at.to_rfc3339()),
+                })),
+                conn,
+            )?;
+
+            Ok(true)
+        })?;
+
+        if granted {
+            permission_set::invalidate();
         }
 
-        roles::table
-            .filter(roles::dsl::name.eq(role))
-            .select(roles::dsl::id)
-            .get_result(conn)
-            .and_then(|role_id: i32| {
-                diesel::insert_into(user_roles::table)
-                    .values((
-                        user_roles::dsl::user_id.eq(user.id()),
-                        user_roles::dsl::role_id.eq(role_id),
-                        user_roles::dsl::created_at.eq(Utc::now()),
-                    ))
-                    .execute(conn)
-                    .map(|_| ())
-            })
+        Ok(())
     }
 }
 
-pub struct RoleRevoker(());
+pub struct RoleRevoker(i32);
 
 impl RoleRevoker {
-    pub(crate) fn new() -> RoleRevoker {
-        RoleRevoker(())
+    pub(crate) fn new(revoked_by: i32) -> RoleRevoker {
+        RoleRevoker(revoked_by)
     }
 
     pub fn revoke_role<U: UserLike>(
@@ -215,25 +448,40 @@ impl RoleRevoker {
         role: Role,
         conn: &PgConnection,
     ) -> Result<(), diesel::result::Error> {
-        use schema::{roles, user_roles};
+        RoleRecord::builtin_role(role, conn)
+            .and_then(|role| self.revoke_custom_role(user, &role, conn))
+    }
+
+    /// Revoke any stored role, including ones created with a `RoleManager`.
+    pub fn revoke_custom_role<U: UserLike>(
+        &self,
+        user: &U,
+        role: &RoleRecord,
+        conn: &PgConnection,
+    ) -> Result<(), diesel::result::Error> {
+        use schema::user_roles;
         use diesel::prelude::*;
 
-        if !user.has_role(role, conn)? {
-            return Ok(());
-        }
+        conn.transaction(|| {
+            let user_role = user_roles::table
+                .filter(user_roles::dsl::user_id.eq(user.id()))
+                .filter(user_roles::dsl::role_id.eq(role.id()));
 
-        roles::table
-            .filter(roles::dsl::name.eq(role))
-            .select(roles::dsl::id)
-            .get_result(conn)
-            .and_then(|role_id: i32| {
-                let user_role = user_roles::table
-                    .filter(user_roles::dsl::user_id.eq(user.id()))
-                    .filter(user_roles::dsl::role_id.eq(role_id));
+            let deleted = diesel::delete(user_role).execute(conn)?;
 
-                diesel::delete(user_role).execute(conn)
-            })
-            .map(|_| ())
+            if deleted == 0 {
+                return Ok(());
+            }
+
+            AuditLogEntry::record(
+                Some(self.0),
+                AuditAction::RevokeRole,
+                Some(user.id()),
+                Some(json!({ "role": role.name() })),
+                None,
+                conn,
+            )
+        }).map(|_| permission_set::invalidate())
     }
 }
 
@@ -244,24 +492,44 @@ impl<'a> PostMaker<'a> {
         PostMaker(base_actor)
     }
 
-    pub fn make_post(
+    /// Create a post, rendering its `source` into sanitized HTML content.
+    ///
+    /// When `sensitive` is `None`, the post is marked sensitive if the poster's persona defaults to
+    /// sensitive posts.
+    pub fn make_post<R: LinkResolver>(
         &self,
         name: Option<String>,
+        summary: Option<String>,
+        sensitive: Option<bool>,
         media_type: Mime,
         icon: Option<&Image>,
         visibility: PostVisibility,
         original_json: Value,
-        content: String,
         source: String,
+        source_format: SourceFormat,
+        language: Lang,
+        resolver: &R,
         conn: &PgConnection,
     ) -> Result<(BasePost, Post), diesel::result::Error> {
-        use schema::{base_posts, posts};
+        use schema::{base_posts, personas, posts};
         use diesel::prelude::*;
 
         conn.transaction(|| {
+            let sensitive = match sensitive {
+                Some(sensitive) => sensitive,
+                None => personas::table
+                    .filter(personas::dsl::base_actor.eq(self.0.id()))
+                    .select(personas::dsl::default_sensitive)
+                    .get_result(conn)
+                    .optional()?
+                    .unwrap_or(false),
+            };
+
             diesel::insert_into(base_posts::table)
                 .values(&NewBasePost::new(
                     name,
+                    summary,
+                    sensitive,
                     media_type,
                     self.0,
                     icon,
@@ -271,12 +539,135 @@ impl<'a> PostMaker<'a> {
                 .get_result(conn)
                 .and_then(|base_post: BasePost| {
                     diesel::insert_into(posts::table)
-                        .values(&NewPost::new(content, Some(source), &base_post))
+                        .values(&NewPost::from_source(
+                            source,
+                            source_format,
+                            language,
+                            &base_post,
+                            resolver,
+                        ))
                         .get_result(conn)
                         .map(|post: Post| (base_post, post))
                 })
         })
     }
+
+    /// Create a post addressed to everyone on one of the poster's lists.
+    ///
+    /// The post is only visible to the actors on the list when it is made. Actors added to the
+    /// list afterwards can't see it.
+    pub fn make_list_post<R: LinkResolver>(
+        &self,
+        name: Option<String>,
+        summary: Option<String>,
+        sensitive: Option<bool>,
+        media_type: Mime,
+        icon: Option<&Image>,
+        original_json: Value,
+        source: String,
+        source_format: SourceFormat,
+        language: Lang,
+        resolver: &R,
+        list: &List,
+        conn: &PgConnection,
+    ) -> Result<(BasePost, Post, Vec<DirectPost>), ListError> {
+        use schema::direct_posts;
+        use diesel::prelude::*;
+
+        if list.owner() != self.0.id() {
+            return Err(ListError::IdMismatch);
+        }
+
+        conn.transaction(|| {
+            let (base_post, post) = self.make_post(
+                name,
+                summary,
+                sensitive,
+                media_type,
+                icon,
+                PostVisibility::ListedPeopleOnly,
+                original_json,
+                source,
+                source_format,
+                language,
+                resolver,
+                conn,
+            )?;
+
+            let recipients = list
+                .member_actors(conn)?
+                .iter()
+                .map(|actor| NewDirectPost::new(&base_post, actor))
+                .collect::<Vec<_>>();
+
+            let direct_posts = if recipients.is_empty() {
+                Vec::new()
+            } else {
+                diesel::insert_into(direct_posts::table)
+                    .values(&recipients)
+                    .get_results(conn)?
+            };
+
+            Ok((base_post, post, direct_posts))
+        })
+    }
+
+    /// Create a post addressed directly to `recipients`, and record it in a conversation.
+    ///
+    /// If `in_reply_to` is part of a conversation, the post continues it. Otherwise, a new
+    /// conversation is started.
+    pub fn make_direct_post<R: LinkResolver>(
+        &self,
+        name: Option<String>,
+        summary: Option<String>,
+        sensitive: Option<bool>,
+        media_type: Mime,
+        icon: Option<&Image>,
+        original_json: Value,
+        source: String,
+        source_format: SourceFormat,
+        language: Lang,
+        resolver: &R,
+        recipients: &[BaseActor],
+        in_reply_to: Option<&BasePost>,
+        conn: &PgConnection,
+    ) -> Result<(BasePost, Post, Conversation), diesel::result::Error> {
+        use schema::direct_posts;
+        use diesel::prelude::*;
+
+        conn.transaction(|| {
+            let (base_post, post) = self.make_post(
+                name,
+                summary,
+                sensitive,
+                media_type,
+                icon,
+                PostVisibility::ListedPeopleOnly,
+                original_json,
+                source,
+                source_format,
+                language,
+                resolver,
+                conn,
+            )?;
+
+            let direct_posts = recipients
+                .iter()
+                .map(|actor| NewDirectPost::new(&base_post, actor))
+                .collect::<Vec<_>>();
+
+            if !direct_posts.is_empty() {
+                diesel::insert_into(direct_posts::table)
+                    .values(&direct_posts)
+                    .execute(conn)?;
+            }
+
+            let conversation =
+                Conversation::record(&base_post, self.0, recipients, in_reply_to, conn)?;
+
+            Ok((base_post, post, conversation))
+        })
+    }
 }
 
 pub struct MediaPostMaker<'a>(&'a BaseActor);
@@ -286,43 +677,98 @@ impl<'a> MediaPostMaker<'a> {
         MediaPostMaker(base_actor)
     }
 
-    pub fn make_media_post(
+    /// Create a post with the given attachments, in the order they are provided.
+    ///
+    /// Attachments count against the posting user's storage quota unless they're already charged
+    /// for them, such as files they stored themselves. Posting is refused if the attachments
+    /// would take the user over their quota.
+    pub fn make_media_post<R: LinkResolver>(
         &self,
         name: Option<String>,
+        summary: Option<String>,
+        sensitive: Option<bool>,
         media_type: Mime,
         icon: Option<&Image>,
         visibility: PostVisibility,
         original_json: Value,
-        content: String,
         source: String,
-        media: &File,
+        source_format: SourceFormat,
+        language: Lang,
+        resolver: &R,
+        media: &[MediaAttachment],
         conn: &PgConnection,
-    ) -> Result<(BasePost, Post, MediaPost), diesel::result::Error> {
+    ) -> Result<(BasePost, Post, Vec<MediaPost>), MediaPostError> {
         use schema::media_posts;
         use diesel::prelude::*;
 
-        conn.transaction(|| {
+        conn.transaction::<_, MediaPostError, _>(|| {
+            if let Some(user_id) = self.0.local_user() {
+                let files = media
+                    .iter()
+                    .map(|attachment| (attachment.file().id(), attachment.file().size()))
+                    .collect::<Vec<_>>();
+
+                StorageUsage::charge::<MediaPostError>(user_id, &files, conn)?;
+            }
+
             PostMaker::new(self.0)
                 .make_post(
                     name,
+                    summary,
+                    sensitive,
                     media_type,
                     icon,
                     visibility,
                     original_json,
-                    content,
                     source,
+                    source_format,
+                    language,
+                    resolver,
                     conn,
                 )
                 .and_then(|(base_post, post)| {
+                    if media.is_empty() {
+                        return Ok((base_post, post, Vec::new()));
+                    }
+
+                    let new_media_posts = media
+                        .iter()
+                        .enumerate()
+                        .map(|(position, attachment)| {
+                            NewMediaPost::new(attachment, position as i32, &post)
+                        })
+                        .collect::<Vec<_>>();
+
                     diesel::insert_into(media_posts::table)
-                        .values(&NewMediaPost::new(media, &post))
-                        .get_result(conn)
-                        .map(|media_post: MediaPost| (base_post, post, media_post))
+                        .values(&new_media_posts)
+                        .get_results(conn)
+                        .map(|media_posts: Vec<MediaPost>| (base_post, post, media_posts))
                 })
+                .map_err(From::from)
         })
     }
 }
 
+#[derive(Debug, Fail)]
+pub enum MediaPostError {
+    #[fail(display = "Error creating media post: {}", _0)]
+    Diesel(#[cause] diesel::result::Error),
+    #[fail(display = "Not enough storage: {}", _0)]
+    Quota(#[cause] QuotaExceeded),
+}
+
+impl From<diesel::result::Error> for MediaPostError {
+    fn from(e: diesel::result::Error) -> Self {
+        MediaPostError::Diesel(e)
+    }
+}
+
+impl From<QuotaExceeded> for MediaPostError {
+    fn from(e: QuotaExceeded) -> Self {
+        MediaPostError::Quota(e)
+    }
+}
+
 pub struct CommentMaker<'a>(&'a BaseActor);
 
 impl<'a> CommentMaker<'a> {
@@ -330,15 +776,19 @@ impl<'a> CommentMaker<'a> {
         CommentMaker(base_actor)
     }
 
-    pub fn make_comment(
+    pub fn make_comment<R: LinkResolver>(
         &self,
         name: Option<String>,
+        summary: Option<String>,
+        sensitive: Option<bool>,
         media_type: Mime,
         icon: Option<&Image>,
         visibility: PostVisibility,
         original_json: Value,
-        content: String,
         source: String,
+        source_format: SourceFormat,
+        language: Lang,
+        resolver: &R,
         conversation: &Post,
         parent: &Post,
         conn: &PgConnection,
@@ -346,50 +796,36 @@ impl<'a> CommentMaker<'a> {
         use schema::{base_posts, comments};
         use diesel::prelude::*;
 
-        let conversation_base: BasePost = base_posts::table
-            .filter(base_posts::dsl::id.eq(conversation.base_post()))
-            .get_result(conn)?;
-
-        if !(conversation_base.visibility() == PostVisibility::Public)
-            && !self.0.is_following_id(conversation_base.posted_by(), conn)?
-        {
-            // Bail if conversation post isn't public and actor isn't following author
-            return Err(CommentError::Permission);
-        } else if conversation_base.visibility() == PostVisibility::ListedPeopleOnly
-            && !conversation_base.is_viewable_by(self.0, conn)?
-        {
-            // Bail if conversation post is a direct post not to the current actor
-            return Err(CommentError::Permission);
-        }
+        let mut base_post_ids = vec![conversation.base_post()];
 
         if parent.id() != conversation.id() {
-            let parent_base: BasePost = base_posts::table
-                .filter(base_posts::dsl::id.eq(parent.base_post()))
-                .get_result(conn)?;
+            base_post_ids.push(parent.base_post());
+        }
 
-            if !(parent_base.visibility() == PostVisibility::Public)
-                && !self.0.is_following_id(parent_base.posted_by(), conn)?
-            {
-                // Bail if parent post isn't pubilc and actor isn't following author
-                return Err(CommentError::Permission);
-            } else if parent_base.visibility() == PostVisibility::ListedPeopleOnly
-                && !parent_base.is_viewable_by(self.0, conn)?
-            {
-                // Bail if parent post is a direct post not to the current actor
-                return Err(CommentError::Permission);
-            }
+        let base_posts: Vec<BasePost> = base_posts::table
+            .filter(base_posts::dsl::id.eq_any(base_post_ids.clone()))
+            .load(conn)?;
+
+        if base_posts.len() != base_post_ids.len() {
+            return Err(CommentError::Diesel(diesel::result::Error::NotFound));
         }
 
+        self.check_commentable(&base_posts, conn)?;
+
         conn.transaction(|| {
             PostMaker::new(self.0)
                 .make_post(
                     name,
+                    summary,
+                    sensitive,
                     media_type,
                     icon,
                     visibility,
                     original_json,
-                    content,
                     source,
+                    source_format,
+                    language,
+                    resolver,
                     conn,
                 )
                 .and_then(|(base_post, post)| {
@@ -400,6 +836,75 @@ impl<'a> CommentMaker<'a> {
                 })
         }).map_err(From::from)
     }
+
+    /// Check that the actor may reply to the posts in the thread they're commenting on.
+    ///
+    /// Blocks and follows between the actor and every post's author are looked up with a single
+    /// query. Group and direct posts still need a query each to check who can see them.
+    fn check_commentable(
+        &self,
+        base_posts: &[BasePost],
+        conn: &PgConnection,
+    ) -> Result<(), CommentError> {
+        use diesel::prelude::*;
+        use diesel::sql_types::Array;
+
+        let authors = base_posts
+            .iter()
+            .map(|base_post| base_post.posted_by())
+            .collect::<Vec<_>>();
+
+        let relations = diesel::sql_query(
+            "SELECT base_actors.id AS id, \
+             EXISTS (SELECT 1 FROM blocks \
+             WHERE (blocks.blocker = $1 AND blocks.blocked = base_actors.id) \
+             OR (blocks.blocker = base_actors.id AND blocks.blocked = $1)) AS blocked, \
+             EXISTS (SELECT 1 FROM followers \
+             WHERE followers.follower = $1 AND followers.follows = base_actors.id) AS following \
+             FROM base_actors WHERE base_actors.id = ANY($2)",
+        ).bind::<Integer, _>(self.0.id())
+            .bind::<Array<Integer>, _>(authors)
+            .load::<AuthorRelation>(conn)?;
+
+        for base_post in base_posts {
+            let relation = relations
+                .iter()
+                .find(|relation| relation.id == base_post.posted_by())
+                .ok_or(CommentError::Diesel(diesel::result::Error::NotFound))?;
+
+            if relation.blocked {
+                // Bail if the post's author and the actor have blocked each other
+                return Err(CommentError::Blocked);
+            }
+
+            if base_post.visibility() == PostVisibility::GroupOnly {
+                if !base_post.is_viewable_by(self.0, conn)? {
+                    // Bail if the post is a group post and actor isn't a group member
+                    return Err(CommentError::Permission);
+                }
+            } else if !(base_post.visibility() == PostVisibility::Public) && !relation.following {
+                // Bail if the post isn't public and actor isn't following author
+                return Err(CommentError::Permission);
+            } else if base_post.visibility() == PostVisibility::ListedPeopleOnly
+                && !base_post.is_viewable_by(self.0, conn)?
+            {
+                // Bail if the post is a direct post not to the current actor
+                return Err(CommentError::Permission);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(QueryableByName)]
+struct AuthorRelation {
+    #[sql_type = "Integer"]
+    id: i32,
+    #[sql_type = "Bool"]
+    blocked: bool,
+    #[sql_type = "Bool"]
+    following: bool,
 }
 
 #[derive(Debug, Fail)]
@@ -408,6 +913,8 @@ pub enum CommentError {
     Diesel(diesel::result::Error),
     #[fail(display = "Not allowed to comment on provided post")]
     Permission,
+    #[fail(display = "Author of provided post and actor have blocked each other")]
+    Blocked,
 }
 
 impl From<diesel::result::Error> for CommentError {
@@ -431,6 +938,10 @@ impl<'a> ActorFollower<'a> {
         use schema::follow_requests;
         use diesel::prelude::*;
 
+        if self.0.is_blocked_with_id(target_actor.id(), conn)? {
+            return Err(FollowError::Blocked);
+        }
+
         match target_actor.follow_policy() {
             FollowPolicy::AutoAccept | FollowPolicy::ManualReview => {
                 diesel::insert_into(follow_requests::table)
@@ -449,6 +960,8 @@ pub enum FollowError {
     Diesel(#[cause] diesel::result::Error),
     #[fail(display = "Target actor is not accepting follow requests")]
     Reject,
+    #[fail(display = "Actors have blocked each other")]
+    Blocked,
 }
 
 impl From<diesel::result::Error> for FollowError {
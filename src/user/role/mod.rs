@@ -1,19 +1,32 @@
 use chrono::DateTime;
 use chrono::offset::Utc;
+use diesel;
+use diesel::pg::PgConnection;
+use serde_json::Value;
 
 pub mod permission;
 pub mod role_permission;
 pub mod user_role;
 
+use audit_log::AuditLogEntry;
 use schema::roles;
-use sql_types::Role as RoleSql;
+use sql_types::{AuditAction, Role as RoleSql};
+use self::permission::Permission;
+use self::role_permission::NewRolePermission;
+use self::user_role::active_grant;
+use user::{permission_set, UserLike};
 
+/// A named set of permissions that can be granted to users.
+///
+/// The built-in roles are named by `sql_types::Role`. Admins can create other roles at runtime,
+/// so a role's name isn't necessarily one of the built-in names.
 #[derive(Debug, Identifiable, Queryable)]
 #[table_name = "roles"]
 pub struct Role {
     id: i32,
-    name: RoleSql,
+    name: String, // max_length: 256
     created_at: DateTime<Utc>,
+    storage_quota: Option<i64>,
 }
 
 impl Role {
@@ -21,11 +34,271 @@ impl Role {
         self.id
     }
 
-    pub fn name(&self) -> RoleSql {
-        self.name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The built-in role this is, or `None` for roles created at runtime.
+    pub fn builtin(&self) -> Option<RoleSql> {
+        self.name.parse().ok()
     }
 
     pub fn created_at(&self) -> DateTime<Utc> {
         self.created_at
     }
+
+    /// The number of bytes users with this role may store, or `None` if there is no limit.
+    pub fn storage_quota(&self) -> Option<i64> {
+        self.storage_quota
+    }
+
+    pub fn all(conn: &PgConnection) -> Result<Vec<Role>, diesel::result::Error> {
+        use diesel::prelude::*;
+
+        roles::table.order(roles::dsl::name.asc()).load(conn)
+    }
+
+    pub fn by_name(name: &str, conn: &PgConnection) -> Result<Option<Role>, diesel::result::Error> {
+        use diesel::prelude::*;
+
+        roles::table
+            .filter(roles::dsl::name.eq(name))
+            .get_result(conn)
+            .optional()
+    }
+
+    /// Fetch the stored row for a built-in role.
+    pub fn builtin_role(role: RoleSql, conn: &PgConnection) -> Result<Role, diesel::result::Error> {
+        use diesel::prelude::*;
+
+        roles::table
+            .filter(roles::dsl::name.eq(role))
+            .get_result(conn)
+    }
+
+    /// Fetch the roles that have been granted to a user.
+    pub fn for_user(user_id: i32, conn: &PgConnection) -> Result<Vec<Role>, diesel::result::Error> {
+        use schema::user_roles;
+        use diesel::prelude::*;
+
+        roles::table
+            .inner_join(user_roles::table)
+            .filter(user_roles::dsl::user_id.eq(user_id))
+            .filter(active_grant())
+            .select(roles::all_columns)
+            .order(roles::dsl::name.asc())
+            .load(conn)
+    }
+
+    /// Fetch the permissions attached to this role.
+    pub fn permissions(
+        &self,
+        conn: &PgConnection,
+    ) -> Result<Vec<Permission>, diesel::result::Error> {
+        use schema::{permissions, role_permissions};
+        use diesel::prelude::*;
+
+        permissions::table
+            .inner_join(role_permissions::table)
+            .filter(role_permissions::dsl::role_id.eq(self.id))
+            .select(permissions::all_columns)
+            .order(permissions::dsl::name.asc())
+            .load(conn)
+    }
+
+    /// The state of this role, as recorded in the audit log.
+    pub(crate) fn audit_json(&self) -> Value {
+        json!({
+            "name": self.name,
+            "storage_quota": self.storage_quota,
+        })
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "roles"]
+pub struct NewRole {
+    name: String,
+    created_at: DateTime<Utc>,
+    storage_quota: Option<i64>,
+}
+
+impl NewRole {
+    pub fn new(name: String, storage_quota: Option<i64>) -> Self {
+        NewRole {
+            name,
+            created_at: Utc::now(),
+            storage_quota,
+        }
+    }
+}
+
+/// Creates and deletes roles, and changes which permissions they carry.
+pub struct RoleManager(i32);
+
+impl RoleManager {
+    pub(crate) fn new(admin: i32) -> RoleManager {
+        RoleManager(admin)
+    }
+
+    pub fn roles(&self, conn: &PgConnection) -> Result<Vec<Role>, diesel::result::Error> {
+        Role::all(conn)
+    }
+
+    pub fn permissions(
+        &self,
+        conn: &PgConnection,
+    ) -> Result<Vec<Permission>, diesel::result::Error> {
+        Permission::all(conn)
+    }
+
+    /// Fetch every permission a user has through any of their roles.
+    pub fn effective_permissions<U: UserLike>(
+        &self,
+        user: &U,
+        conn: &PgConnection,
+    ) -> Result<Vec<Permission>, diesel::result::Error> {
+        Permission::for_user(user.id(), conn)
+    }
+
+    pub fn create_role(
+        &self,
+        name: String,
+        storage_quota: Option<i64>,
+        conn: &PgConnection,
+    ) -> Result<Role, RoleManagerError> {
+        use schema::roles;
+        use diesel::prelude::*;
+
+        conn.transaction(|| {
+            let role: Role = diesel::insert_into(roles::table)
+                .values(&NewRole::new(name.clone(), storage_quota))
+                .on_conflict_do_nothing()
+                .get_result(conn)
+                .optional()?
+                .ok_or(RoleManagerError::Exists(name))?;
+
+            AuditLogEntry::record(
+                Some(self.0),
+                AuditAction::CreateRole,
+                Some(role.id()),
+                None,
+                Some(role.audit_json()),
+                conn,
+            )?;
+
+            Ok(role)
+        })
+    }
+
+    /// Delete a role, taking it away from every user who had it.
+    ///
+    /// Built-in roles can't be deleted.
+    pub fn delete_role(
+        &self,
+        role: Role,
+        conn: &PgConnection,
+    ) -> Result<(), RoleManagerError> {
+        use diesel::prelude::*;
+
+        if let Some(builtin) = role.builtin() {
+            return Err(RoleManagerError::Builtin(builtin));
+        }
+
+        conn.transaction::<_, diesel::result::Error, _>(|| {
+            diesel::delete(&role).execute(conn)?;
+
+            AuditLogEntry::record(
+                Some(self.0),
+                AuditAction::DeleteRole,
+                Some(role.id()),
+                Some(role.audit_json()),
+                None,
+                conn,
+            )?;
+
+            Ok(())
+        })?;
+
+        permission_set::invalidate();
+
+        Ok(())
+    }
+
+    pub fn add_permission(
+        &self,
+        role: &Role,
+        permission: &Permission,
+        conn: &PgConnection,
+    ) -> Result<(), diesel::result::Error> {
+        use schema::role_permissions;
+        use diesel::prelude::*;
+
+        conn.transaction(|| {
+            let inserted = diesel::insert_into(role_permissions::table)
+                .values(&NewRolePermission::new(role, permission))
+                .on_conflict_do_nothing()
+                .execute(conn)?;
+
+            if inserted == 0 {
+                return Ok(());
+            }
+
+            AuditLogEntry::record(
+                Some(self.0),
+                AuditAction::AddRolePermission,
+                Some(role.id()),
+                None,
+                Some(json!({ "permission": permission.name() })),
+                conn,
+            )
+        }).map(|_| permission_set::invalidate())
+    }
+
+    pub fn remove_permission(
+        &self,
+        role: &Role,
+        permission: &Permission,
+        conn: &PgConnection,
+    ) -> Result<(), diesel::result::Error> {
+        use schema::role_permissions;
+        use diesel::prelude::*;
+
+        conn.transaction(|| {
+            let role_permission = role_permissions::table
+                .filter(role_permissions::dsl::role_id.eq(role.id()))
+                .filter(role_permissions::dsl::permission_id.eq(permission.id()));
+
+            let deleted = diesel::delete(role_permission).execute(conn)?;
+
+            if deleted == 0 {
+                return Ok(());
+            }
+
+            AuditLogEntry::record(
+                Some(self.0),
+                AuditAction::RemoveRolePermission,
+                Some(role.id()),
+                Some(json!({ "permission": permission.name() })),
+                None,
+                conn,
+            )
+        }).map(|_| permission_set::invalidate())
+    }
+}
+
+#[derive(Debug, Fail)]
+pub enum RoleManagerError {
+    #[fail(display = "Error managing roles")]
+    Diesel(#[cause] diesel::result::Error),
+    #[fail(display = "A role named {} already exists", _0)]
+    Exists(String),
+    #[fail(display = "Built-in role {} can't be deleted", _0)]
+    Builtin(RoleSql),
+}
+
+impl From<diesel::result::Error> for RoleManagerError {
+    fn from(e: diesel::result::Error) -> Self {
+        RoleManagerError::Diesel(e)
+    }
 }
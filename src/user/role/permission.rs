@@ -1,14 +1,21 @@
 use chrono::DateTime;
 use chrono::offset::Utc;
+use diesel;
+use diesel::pg::PgConnection;
 
 use schema::permissions;
 use sql_types::Permission as PermissionSql;
+use super::user_role::active_grant;
 
+/// Something a role allows its users to do.
+///
+/// Only permissions named by `sql_types::Permission` are checked by this crate, but the table may
+/// hold others.
 #[derive(Debug, Identifiable, Queryable)]
 #[table_name = "permissions"]
 pub struct Permission {
     id: i32,
-    name: PermissionSql,
+    name: String, // max_length: 256
     created_at: DateTime<Utc>,
 }
 
@@ -17,11 +24,71 @@ impl Permission {
         self.id
     }
 
-    pub fn name(&self) -> PermissionSql {
-        self.name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The built-in permission this is, or `None` if this crate doesn't know about it.
+    pub fn known(&self) -> Option<PermissionSql> {
+        self.name.parse().ok()
     }
 
     pub fn created_at(&self) -> DateTime<Utc> {
         self.created_at
     }
+
+    pub fn all(conn: &PgConnection) -> Result<Vec<Permission>, diesel::result::Error> {
+        use diesel::prelude::*;
+
+        permissions::table
+            .order(permissions::dsl::name.asc())
+            .load(conn)
+    }
+
+    pub fn by_name(
+        name: &str,
+        conn: &PgConnection,
+    ) -> Result<Option<Permission>, diesel::result::Error> {
+        use diesel::prelude::*;
+
+        permissions::table
+            .filter(permissions::dsl::name.eq(name))
+            .get_result(conn)
+            .optional()
+    }
+
+    /// Fetch the stored row for a built-in permission.
+    pub fn builtin_permission(
+        permission: PermissionSql,
+        conn: &PgConnection,
+    ) -> Result<Permission, diesel::result::Error> {
+        use diesel::prelude::*;
+
+        permissions::table
+            .filter(permissions::dsl::name.eq(permission))
+            .get_result(conn)
+    }
+
+    /// Fetch every permission a user has through any of their roles.
+    pub fn for_user(
+        user_id: i32,
+        conn: &PgConnection,
+    ) -> Result<Vec<Permission>, diesel::result::Error> {
+        use schema::{role_permissions, user_roles};
+        use diesel::prelude::*;
+
+        let role_ids = user_roles::table
+            .filter(user_roles::dsl::user_id.eq(user_id))
+            .filter(active_grant())
+            .select(user_roles::dsl::role_id);
+
+        let permission_ids = role_permissions::table
+            .filter(role_permissions::dsl::role_id.eq_any(role_ids))
+            .select(role_permissions::dsl::permission_id);
+
+        permissions::table
+            .filter(permissions::dsl::id.eq_any(permission_ids))
+            .order(permissions::dsl::name.asc())
+            .load(conn)
+    }
 }
@@ -2,6 +2,8 @@ use chrono::DateTime;
 use chrono::offset::Utc;
 
 use schema::role_permissions;
+use super::Role;
+use super::permission::Permission;
 
 #[derive(Debug, Identifiable, Queryable)]
 #[table_name = "role_permissions"]
@@ -29,3 +31,21 @@ impl RolePermission {
         self.created_at
     }
 }
+
+#[derive(Insertable)]
+#[table_name = "role_permissions"]
+pub struct NewRolePermission {
+    role_id: i32,
+    permission_id: i32,
+    created_at: DateTime<Utc>,
+}
+
+impl NewRolePermission {
+    pub fn new(role: &Role, permission: &Permission) -> Self {
+        NewRolePermission {
+            role_id: role.id(),
+            permission_id: permission.id(),
+            created_at: Utc::now(),
+        }
+    }
+}
@@ -1,8 +1,25 @@
 use chrono::DateTime;
 use chrono::offset::Utc;
+use diesel;
+use diesel::dsl::sql;
+use diesel::expression::SqlLiteral;
+use diesel::pg::PgConnection;
+use diesel::sql_types::Bool;
 
+use audit_log::AuditLogEntry;
 use schema::user_roles;
+use sql_types::AuditAction;
+use user::permission_set;
 
+/// A condition on `user_roles` that holds for grants that haven't expired.
+///
+/// Expired grants stop applying as soon as they expire, even before `UserRole::expire` deletes
+/// them, so every query that looks at a user's roles should filter with this.
+pub(crate) fn active_grant() -> SqlLiteral<Bool> {
+    sql("(user_roles.expires_at IS NULL OR user_roles.expires_at > now())")
+}
+
+/// A role granted to a user, possibly only until a set time.
 #[derive(Debug, Identifiable, Queryable)]
 #[table_name = "user_roles"]
 pub struct UserRole {
@@ -10,6 +27,8 @@ pub struct UserRole {
     user_id: i32, // foreign key to User
     role_id: i32, // foreign key to Role
     created_at: DateTime<Utc>,
+    expires_at: Option<DateTime<Utc>>,
+    granted_by: Option<i32>, // foreign key to User
 }
 
 impl UserRole {
@@ -28,4 +47,56 @@ impl UserRole {
     pub fn created_at(&self) -> DateTime<Utc> {
         self.created_at
     }
+
+    /// When this grant stops applying, or `None` if it's permanent.
+    pub fn expires_at(&self) -> Option<DateTime<Utc>> {
+        self.expires_at
+    }
+
+    /// The user who granted this role, or `None` if the instance granted it on its own or the
+    /// granting user no longer exists.
+    pub fn granted_by(&self) -> Option<i32> {
+        self.granted_by
+    }
+
+    /// Delete every grant that has expired, returning the grants that were deleted.
+    ///
+    /// This should be run periodically. Expired grants stop applying even if this hasn't run yet.
+    pub fn expire(conn: &PgConnection) -> Result<Vec<UserRole>, diesel::result::Error> {
+        use schema::roles;
+        use diesel::prelude::*;
+
+        let expired = conn.transaction::<_, diesel::result::Error, _>(|| {
+            let expired: Vec<UserRole> = diesel::delete(
+                user_roles::table.filter(user_roles::dsl::expires_at.le(Utc::now())),
+            ).get_results(conn)?;
+
+            for user_role in &expired {
+                let role: String = roles::table
+                    .find(user_role.role_id)
+                    .select(roles::dsl::name)
+                    .get_result(conn)?;
+
+                AuditLogEntry::record(
+                    None,
+                    AuditAction::RevokeRole,
+                    Some(user_role.user_id),
+                    Some(json!({
+                        "role": role,
+                        "expires_at": user_role.expires_at.map(|at| at.to_rfc3339()),
+                    })),
+                    None,
+                    conn,
+                )?;
+            }
+
+            Ok(expired)
+        })?;
+
+        if !expired.is_empty() {
+            permission_set::invalidate();
+        }
+
+        Ok(expired)
+    }
 }
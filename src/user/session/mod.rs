@@ -0,0 +1,244 @@
+use chrono::DateTime;
+use chrono::offset::Utc;
+use diesel;
+use diesel::pg::PgConnection;
+
+mod token;
+
+use schema::sessions;
+use self::token::{create_token, HashedSessionToken};
+pub use self::token::{CreationError, PresentedSessionToken, SessionToken};
+use user::{AuthenticatedUser, QueriedUser, UserLike};
+use user::ban::UserBan;
+
+#[derive(Debug, Fail)]
+pub enum SessionError {
+    #[fail(display = "Error in diesel: {}", _0)]
+    Diesel(#[cause] diesel::result::Error),
+    #[fail(display = "Session token is unknown, expired, or revoked")]
+    Invalid,
+    #[fail(display = "User is banned")]
+    Banned(UserBan),
+    #[fail(display = "Session belongs to another user")]
+    IdMismatch,
+}
+
+impl From<diesel::result::Error> for SessionError {
+    fn from(e: diesel::result::Error) -> Self {
+        SessionError::Diesel(e)
+    }
+}
+
+/// A persisted login.
+///
+/// Only a hash of the session's token is stored, so a `Session` can't be turned back into the
+/// token that was handed to the client.
+#[derive(Debug, Identifiable, Queryable)]
+#[table_name = "sessions"]
+pub struct Session {
+    id: i32,
+    user_id: i32, // foreign key to User
+    token_hash: HashedSessionToken,
+    ip_address: Option<String>, // max_length: 45
+    user_agent: Option<String>,
+    created_at: DateTime<Utc>,
+    last_seen_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+    revoked_at: Option<DateTime<Utc>>,
+}
+
+impl Session {
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+
+    pub fn user_id(&self) -> i32 {
+        self.user_id
+    }
+
+    /// The IP address the session was started from.
+    pub fn ip_address(&self) -> Option<&str> {
+        self.ip_address.as_ref().map(|s| s.as_ref())
+    }
+
+    /// The user agent the session was started from.
+    pub fn user_agent(&self) -> Option<&str> {
+        self.user_agent.as_ref().map(|s| s.as_ref())
+    }
+
+    pub fn created_at(&self) -> DateTime<Utc> {
+        self.created_at
+    }
+
+    /// The last time this session's token was used.
+    pub fn last_seen_at(&self) -> DateTime<Utc> {
+        self.last_seen_at
+    }
+
+    pub fn expires_at(&self) -> DateTime<Utc> {
+        self.expires_at
+    }
+
+    pub fn revoked_at(&self) -> Option<DateTime<Utc>> {
+        self.revoked_at
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.revoked_at.is_none() && self.expires_at > Utc::now()
+    }
+
+    /// Resume the session a token belongs to, logging its user in.
+    ///
+    /// This fails if the token is unknown, if the session has expired or been revoked, or if the
+    /// user has an active ban.
+    pub fn resume(
+        token: &PresentedSessionToken,
+        conn: &PgConnection,
+    ) -> Result<(Session, AuthenticatedUser), SessionError> {
+        use schema::users;
+        use diesel::prelude::*;
+
+        let now = Utc::now();
+
+        let session: Session = diesel::update(
+            sessions::table
+                .filter(sessions::dsl::token_hash.eq(HashedSessionToken::from(token)))
+                .filter(sessions::dsl::revoked_at.is_null())
+                .filter(sessions::dsl::expires_at.gt(now)),
+        ).set(sessions::dsl::last_seen_at.eq(now))
+            .get_result(conn)
+            .optional()?
+            .ok_or(SessionError::Invalid)?;
+
+        let user: QueriedUser = users::table.find(session.user_id).get_result(conn)?;
+
+        if let Some(ban) = UserBan::active_for(&user, conn)? {
+            return Err(SessionError::Banned(ban));
+        }
+
+        Ok((
+            session,
+            AuthenticatedUser {
+                id: user.id(),
+                primary_email: user.primary_email(),
+                created_at: user.created_at(),
+            },
+        ))
+    }
+
+    /// Fetch the user's sessions that haven't expired or been revoked, most recently used first.
+    pub fn active_for<U: UserLike>(
+        user: &U,
+        conn: &PgConnection,
+    ) -> Result<Vec<Session>, diesel::result::Error> {
+        use diesel::prelude::*;
+
+        sessions::table
+            .filter(sessions::dsl::user_id.eq(user.id()))
+            .filter(sessions::dsl::revoked_at.is_null())
+            .filter(sessions::dsl::expires_at.gt(Utc::now()))
+            .order(sessions::dsl::last_seen_at.desc())
+            .load(conn)
+    }
+
+    /// End this session. Its token can't be used again.
+    pub fn revoke<U: UserLike>(
+        self,
+        user: &U,
+        conn: &PgConnection,
+    ) -> Result<Session, SessionError> {
+        use diesel::prelude::*;
+
+        if self.user_id != user.id() {
+            return Err(SessionError::IdMismatch);
+        }
+
+        if self.revoked_at.is_some() {
+            return Ok(self);
+        }
+
+        diesel::update(&self)
+            .set(sessions::dsl::revoked_at.eq(Utc::now()))
+            .get_result(conn)
+            .map_err(From::from)
+    }
+
+    /// End every active session of the user except `current`, such as after a password change.
+    pub fn revoke_others<U: UserLike>(
+        current: &Session,
+        user: &U,
+        conn: &PgConnection,
+    ) -> Result<usize, SessionError> {
+        use diesel::prelude::*;
+
+        if current.user_id != user.id() {
+            return Err(SessionError::IdMismatch);
+        }
+
+        diesel::update(
+            sessions::table
+                .filter(sessions::dsl::user_id.eq(user.id()))
+                .filter(sessions::dsl::id.ne(current.id))
+                .filter(sessions::dsl::revoked_at.is_null()),
+        ).set(sessions::dsl::revoked_at.eq(Utc::now()))
+            .execute(conn)
+            .map_err(From::from)
+    }
+
+    /// Delete sessions that have expired or been revoked.
+    ///
+    /// This should be run periodically. Such sessions can't be resumed even if this hasn't run
+    /// yet.
+    pub fn delete_inactive(conn: &PgConnection) -> Result<usize, diesel::result::Error> {
+        use diesel::prelude::*;
+
+        diesel::delete(
+            sessions::table.filter(
+                sessions::dsl::revoked_at
+                    .is_not_null()
+                    .or(sessions::dsl::expires_at.le(Utc::now())),
+            ),
+        ).execute(conn)
+    }
+}
+
+#[derive(Insertable)]
+#[table_name = "sessions"]
+pub struct NewSession {
+    user_id: i32,
+    token_hash: HashedSessionToken,
+    ip_address: Option<String>,
+    user_agent: Option<String>,
+    created_at: DateTime<Utc>,
+    last_seen_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+}
+
+impl NewSession {
+    /// Start a session for a user who has just logged in.
+    ///
+    /// The returned `SessionToken` must be handed to the client, since only its hash is stored.
+    pub fn new(
+        user: &AuthenticatedUser,
+        ip_address: Option<String>,
+        user_agent: Option<String>,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(Self, SessionToken), CreationError> {
+        let now = Utc::now();
+
+        create_token().map(|(session_token, token_hash)| {
+            (
+                NewSession {
+                    user_id: user.id(),
+                    token_hash,
+                    ip_address,
+                    user_agent,
+                    created_at: now,
+                    last_seen_at: now,
+                    expires_at,
+                },
+                session_token,
+            )
+        })
+    }
+}
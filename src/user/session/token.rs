@@ -0,0 +1,145 @@
+use std::io::Write;
+use std::fmt;
+
+use diesel::backend::Backend;
+use diesel::deserialize;
+use diesel::serialize;
+use diesel::sql_types::Text;
+use rand::{OsRng, Rng};
+use sha2::{Digest, Sha256};
+
+#[derive(Clone, Copy, Debug, Eq, Fail, PartialEq)]
+pub enum CreationError {
+    #[fail(display = "Failed to create Random Number Generator")]
+    Rng,
+}
+
+/// Create a session token along with the hash that gets stored.
+///
+/// Unlike email tokens, session tokens are checked on every request, so they're hashed with
+/// SHA-256 rather than bcrypt. The tokens are long and random, so a slow hash wouldn't make them
+/// any harder to guess.
+pub(crate) fn create_token() -> Result<(SessionToken, HashedSessionToken), CreationError> {
+    let mut rng = OsRng::new().map_err(|_| CreationError::Rng)?;
+
+    let token = rng.gen_ascii_chars()
+        .take(48)
+        .map(|c| c.to_string())
+        .collect::<Vec<_>>()
+        .join("");
+
+    let hashed_token = HashedSessionToken::from_token(&token);
+
+    Ok((SessionToken(token), hashed_token))
+}
+
+#[derive(AsExpression, FromSqlRow)]
+#[sql_type = "Text"]
+pub struct HashedSessionToken(String);
+
+impl HashedSessionToken {
+    fn from_token(token: &str) -> Self {
+        HashedSessionToken(
+            Sha256::digest(token.as_bytes())
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect(),
+        )
+    }
+}
+
+impl<'a> From<&'a PresentedSessionToken> for HashedSessionToken {
+    fn from(token: &'a PresentedSessionToken) -> Self {
+        HashedSessionToken::from_token(&token.0)
+    }
+}
+
+impl fmt::Debug for HashedSessionToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "********")
+    }
+}
+
+impl fmt::Display for HashedSessionToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "********")
+    }
+}
+
+impl<DB> serialize::ToSql<Text, DB> for HashedSessionToken
+where
+    DB: Backend,
+{
+    fn to_sql<W: Write>(&self, out: &mut serialize::Output<W, DB>) -> serialize::Result {
+        serialize::ToSql::<Text, DB>::to_sql(&self.0, out)
+    }
+}
+
+impl<DB> deserialize::FromSql<Text, DB> for HashedSessionToken
+where
+    DB: Backend<RawValue = [u8]>,
+{
+    fn from_sql(bytes: Option<&DB::RawValue>) -> deserialize::Result<Self> {
+        deserialize::FromSql::<Text, DB>::from_sql(bytes).map(HashedSessionToken)
+    }
+}
+
+/// The token handed to a client when a session starts. It is only available at that point.
+#[derive(Serialize)]
+pub struct SessionToken(String);
+
+impl fmt::Debug for SessionToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl fmt::Display for SessionToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A token a client presents to resume its session.
+#[derive(Deserialize)]
+pub struct PresentedSessionToken(String);
+
+impl PresentedSessionToken {
+    /// Wrap a token read from somewhere other than a deserialized payload, such as a cookie.
+    pub fn new(token: String) -> Self {
+        PresentedSessionToken(token)
+    }
+}
+
+impl fmt::Debug for PresentedSessionToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "********")
+    }
+}
+
+impl fmt::Display for PresentedSessionToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "********")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presented_token_hashes_like_created_token() {
+        let (token, hashed) = create_token().unwrap();
+        let presented = PresentedSessionToken(format!("{}", token));
+
+        assert_eq!(HashedSessionToken::from(&presented).0, hashed.0);
+    }
+
+    #[test]
+    fn tokens_are_not_reused() {
+        let (first, _) = create_token().unwrap();
+        let (second, _) = create_token().unwrap();
+
+        assert_ne!(first.0, second.0);
+    }
+}